@@ -1,152 +1,531 @@
-use super::{SubscriptionData, UsageData};
+use super::{ApiConfig, SubscriptionData, UsageData};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::fs;
-use std::path::PathBuf;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 use std::time::SystemTime;
 
 /// 缓存有效期：5分钟
 const CACHE_FRESH_SECONDS: u64 = 300;
 
-/// 获取缓存文件路径
-fn get_cache_file(cache_type: &str) -> Option<PathBuf> {
-    let home = dirs::home_dir()?;
-    let cache_dir = home.join(".claude").join("byebyecode").join("cache");
+/// 超过这个时间未更新的缓存条目会被清理（切换密钥、模型或中转站后留下的旧条目）
+const CACHE_GC_SECONDS: u64 = 7 * 24 * 3600;
+
+/// 获取缓存目录
+fn get_cache_dir() -> Option<PathBuf> {
+    let cache_dir = crate::utils::paths::cache_dir()?;
 
     // 确保缓存目录存在
     fs::create_dir_all(&cache_dir).ok()?;
 
-    Some(cache_dir.join(format!("{}.json", cache_type)))
+    Some(cache_dir)
 }
 
-/// 检查缓存是��新鲜（5分钟内）
-fn is_cache_fresh(cache_file: &PathBuf) -> bool {
-    if let Ok(metadata) = fs::metadata(cache_file) {
-        if let Ok(modified) = metadata.modified() {
-            if let Ok(elapsed) = SystemTime::now().duration_since(modified) {
-                return elapsed.as_secs() < CACHE_FRESH_SECONDS;
-            }
+/// 某个账号的一类缓存数据
+/// 文件名带 (provider, url, api_key, model) 的哈希，多个账号、中转站或模型的缓存互不覆盖
+struct CacheEntry {
+    /// "usage" 或 "subscriptions"
+    kind: &'static str,
+    key: String,
+}
+
+impl CacheEntry {
+    fn new(kind: &'static str, api_config: &ApiConfig, url: &str, model: Option<&str>) -> Self {
+        let provider = api_config
+            .provider()
+            .map(|p| p.name().to_string())
+            .or_else(|| api_config.provider.clone())
+            .unwrap_or_default();
+
+        let mut hasher = Sha256::new();
+        for part in [
+            provider.as_str(),
+            url,
+            &api_config.api_key,
+            model.unwrap_or(""),
+        ] {
+            hasher.update(part.as_bytes());
+            hasher.update([0]);
         }
+        let key = format!("{:x}", hasher.finalize());
+
+        Self {
+            kind,
+            key: key[..16].to_string(),
+        }
+    }
+
+    fn usage(api_config: &ApiConfig, model: Option<&str>) -> Self {
+        Self::new("usage", api_config, &api_config.usage_url, model)
+    }
+
+    fn subscriptions(api_config: &ApiConfig, model: Option<&str>) -> Self {
+        Self::new(
+            "subscriptions",
+            api_config,
+            &api_config.subscription_url,
+            model,
+        )
+    }
+
+    /// 数据：`usage-<hash>.json`
+    fn data_file(&self) -> Option<PathBuf> {
+        self.file(".json")
+    }
+
+    /// 最近一次刷新失败：`usage-<hash>.error.json`
+    fn error_file(&self) -> Option<PathBuf> {
+        self.file(".error.json")
+    }
+
+    /// 刷新进行中的标记：`usage-<hash>.pending`
+    fn pending_file(&self) -> Option<PathBuf> {
+        self.file(".pending")
     }
-    false
+
+    fn file(&self, suffix: &str) -> Option<PathBuf> {
+        Some(get_cache_dir()?.join(format!("{}-{}{}", self.kind, self.key, suffix)))
+    }
+}
+
+/// 检查缓存是否新鲜（5分钟内）
+fn is_cache_fresh(cache_file: &Path) -> bool {
+    file_age_seconds(cache_file).is_some_and(|age| age < CACHE_FRESH_SECONDS)
+}
+
+/// 文件距离上次修改的秒数
+fn file_age_seconds(path: &Path) -> Option<u64> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    let elapsed = SystemTime::now().duration_since(modified).ok()?;
+    Some(elapsed.as_secs())
 }
 
 /// 读取缓存文件
-fn read_cache<T: serde::de::DeserializeOwned>(cache_file: &PathBuf) -> Option<T> {
+fn read_cache<T: serde::de::DeserializeOwned>(cache_file: &Path) -> Option<T> {
     let content = fs::read_to_string(cache_file).ok()?;
     serde_json::from_str(&content).ok()
 }
 
 /// 保存缓存文件（覆盖旧缓存）
+/// 多个状态栏进程和后台刷新进程可能同时写同一条目：持有 `.lock` 文件的独占锁，
+/// 先写临时文件再重命名替换，读取方不会读到写了一半的 JSON
 fn save_cache<T: serde::Serialize>(
-    cache_file: &PathBuf,
+    cache_file: &Path,
     data: &T,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let json = serde_json::to_string(data)?;
-    fs::write(cache_file, json)?;
+
+    let lock = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(cache_file.with_extension("lock"))?;
+    lock.lock()?;
+
+    let file_name = cache_file
+        .file_name()
+        .ok_or("invalid cache file")?
+        .to_string_lossy();
+    let tmp = cache_file.with_file_name(format!("{}.{}.tmp", file_name, std::process::id()));
+    fs::write(&tmp, json)?;
+    if let Err(e) = fs::rename(&tmp, cache_file) {
+        let _ = fs::remove_file(&tmp);
+        return Err(e.into());
+    }
     Ok(())
 }
 
-/// 获取订阅数据缓存
-/// 返回: (缓存数据, 是否需要延迟刷新)
+/// 读取某个条目的缓存
+/// 返回: (缓存数据, 是否需要后台刷新)
 /// - 5分钟内：返回缓存，不需要刷新
-/// - 5分钟外：返回缓存，需要延迟1秒后台刷新
-pub fn get_cached_subscriptions() -> (Option<Vec<SubscriptionData>>, bool) {
-    let cache_file = match get_cache_file("subscriptions") {
+/// - 5分钟外：返回缓存，需要后台刷新
+fn get_cached<T: serde::de::DeserializeOwned>(entry: &CacheEntry) -> (Option<T>, bool) {
+    let cache_file = match entry.data_file() {
         Some(f) => f,
         None => return (None, false),
     };
 
     // 读取缓存
-    let cached_data: Option<Vec<SubscriptionData>> = read_cache(&cache_file);
+    let cached_data: Option<T> = read_cache(&cache_file);
 
     if cached_data.is_none() {
         // 没有缓存，需要立即获取
         return (None, false);
     }
 
-    // 检查缓存新鲜度
-    let is_fresh = is_cache_fresh(&cache_file);
+    // 返回缓存数据 + 是否需要刷新（5分钟外需要刷新）
+    (cached_data, !is_cache_fresh(&cache_file))
+}
 
-    // 返回缓存数据 + 是否需要延迟刷新（5分钟外需要刷新）
-    (cached_data, !is_fresh)
+fn save_cached<T: serde::Serialize>(
+    entry: &CacheEntry,
+    data: &T,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(cache_file) = entry.data_file() {
+        save_cache(&cache_file, data)?;
+    }
+    Ok(())
+}
+
+/// 获取订阅数据缓存
+/// 返回: (缓存数据, 是否需要后台刷新)
+pub fn get_cached_subscriptions(
+    api_config: &ApiConfig,
+    model: Option<&str>,
+) -> (Option<Vec<SubscriptionData>>, bool) {
+    get_cached(&CacheEntry::subscriptions(api_config, model))
 }
 
 /// 保存订阅数据到缓存（覆盖旧缓存）
 pub fn save_cached_subscriptions(
+    api_config: &ApiConfig,
+    model: Option<&str>,
     data: &Vec<SubscriptionData>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    if let Some(cache_file) = get_cache_file("subscriptions") {
-        save_cache(&cache_file, data)?;
-    }
-    Ok(())
+    save_cached(&CacheEntry::subscriptions(api_config, model), data)
 }
 
 /// 获取使用量数据缓存
-/// 返回: (缓存数据, 是否需要延迟刷新)
-pub fn get_cached_usage() -> (Option<UsageData>, bool) {
-    let cache_file = match get_cache_file("usage") {
-        Some(f) => f,
-        None => return (None, false),
+/// 返回: (缓存数据, 是否需要后台刷新)
+pub fn get_cached_usage(api_config: &ApiConfig, model: Option<&str>) -> (Option<UsageData>, bool) {
+    get_cached(&CacheEntry::usage(api_config, model))
+}
+
+/// 保存使用量数据到缓存（覆盖旧缓存）
+pub fn save_cached_usage(
+    api_config: &ApiConfig,
+    model: Option<&str>,
+    data: &UsageData,
+) -> Result<(), Box<dyn std::error::Error>> {
+    save_cached(&CacheEntry::usage(api_config, model), data)
+}
+
+/// 清理长时间未更新的缓存条目，以及旧版本不区分账号的缓存文件和明文密钥缓存
+pub fn collect_garbage() {
+    let Some(cache_dir) = get_cache_dir() else {
+        return;
+    };
+    let Ok(entries) = fs::read_dir(&cache_dir) else {
+        return;
     };
 
-    // 读取缓存
-    let cached_data: Option<UsageData> = read_cache(&cache_file);
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        // 旧版本把 cmd: 密钥的输出明文写在缓存目录的 secrets/ 下，现在改为运行时目录
+        if name == "secrets" {
+            let _ = fs::remove_dir_all(entry.path());
+            continue;
+        }
+        let legacy = matches!(
+            name.as_str(),
+            "usage.json" | "subscriptions.json" | "refresh_error.json" | "refresh.pending.json"
+        );
+        // 锁文件可能正被其他进程持有，删除后新建的同名文件会让两个进程各持一把锁
+        let ours = (name.starts_with("usage-") || name.starts_with("subscriptions-"))
+            && !name.ends_with(".lock");
+        let expired = file_age_seconds(&entry.path()).is_some_and(|age| age >= CACHE_GC_SECONDS);
+        if legacy || (ours && expired) {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
 
-    if cached_data.is_none() {
-        return (None, false);
+/// 刷新进行中的标记超过这个时间视为失效（子进程异常退出）
+const REFRESH_PENDING_SECONDS: u64 = 60;
+
+/// 刷新失败后，这段时间内不再重试，避免每次渲染都请求出错的接口
+const REFRESH_RETRY_SECONDS: u64 = 60;
+
+/// 后台刷新任务：使用段落解析出的中转站配置（provider、URL、密钥）和当前模型，
+/// 通过 stdin 交给 `byebyecode refresh` 子进程执行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefreshJob {
+    pub api_config: ApiConfig,
+    pub model: Option<String>,
+    pub usage: bool,
+    pub subscriptions: bool,
+}
+
+/// 最近一次刷新失败的信息，成功刷新后清除
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefreshError {
+    /// "usage" 或 "subscriptions"
+    pub kind: String,
+    pub message: String,
+    /// Unix 时间戳（秒）
+    pub at: u64,
+}
+
+impl RefreshJob {
+    /// 任务涉及的缓存条目
+    fn entries(&self) -> Vec<CacheEntry> {
+        let model = self.model.as_deref();
+        let mut entries = Vec::new();
+        if self.usage {
+            entries.push(CacheEntry::usage(&self.api_config, model));
+        }
+        if self.subscriptions {
+            entries.push(CacheEntry::subscriptions(&self.api_config, model));
+        }
+        entries
+    }
+}
+
+/// 读取当前账号最近一次刷新失败的信息（使用量或订阅数据，取较新的一条）
+pub fn last_refresh_error(api_config: &ApiConfig, model: Option<&str>) -> Option<RefreshError> {
+    [
+        CacheEntry::usage(api_config, model),
+        CacheEntry::subscriptions(api_config, model),
+    ]
+    .iter()
+    .filter_map(read_refresh_error)
+    .max_by_key(|e| e.at)
+}
+
+fn read_refresh_error(entry: &CacheEntry) -> Option<RefreshError> {
+    read_cache(&entry.error_file()?)
+}
+
+/// 记录刷新失败
+fn record_refresh_error(entry: &CacheEntry, message: &str) {
+    if let Some(cache_file) = entry.error_file() {
+        let error = RefreshError {
+            kind: entry.kind.to_string(),
+            message: message.to_string(),
+            at: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+        };
+        let _ = save_cache(&cache_file, &error);
     }
+}
 
-    // 检查缓存新鲜度
-    let is_fresh = is_cache_fresh(&cache_file);
+/// 刷新成功后清除失败记录
+fn clear_refresh_error(entry: &CacheEntry) {
+    if let Some(cache_file) = entry.error_file() {
+        let _ = fs::remove_file(cache_file);
+    }
+}
 
-    (cached_data, !is_fresh)
+/// 占用条目的刷新标记，已有未失效的刷新在进行中时返回 false
+/// `create_new` 保证多个状态栏进程同时发现缓存过期时只有一个能启动刷新
+fn claim_refresh(entry: &CacheEntry) -> bool {
+    let Some(pending) = entry.pending_file() else {
+        return false;
+    };
+    if file_age_seconds(&pending).is_some_and(|age| age >= REFRESH_PENDING_SECONDS) {
+        let _ = fs::remove_file(&pending);
+    }
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&pending)
+        .is_ok()
 }
 
-/// 保存使用量数据到缓存（覆盖旧缓存）
-pub fn save_cached_usage(data: &UsageData) -> Result<(), Box<dyn std::error::Error>> {
-    if let Some(cache_file) = get_cache_file("usage") {
-        save_cache(&cache_file, data)?;
+fn release_refresh(entry: &CacheEntry) {
+    if let Some(pending) = entry.pending_file() {
+        let _ = fs::remove_file(pending);
     }
-    Ok(())
 }
 
-/// 后台异步更新订阅数据（延迟1秒执行）
-pub fn spawn_background_subscription_update(api_key: String) {
+/// 条目在 `REFRESH_RETRY_SECONDS` 内刷新失败过
+fn failed_recently(entry: &CacheEntry) -> bool {
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    read_refresh_error(entry).is_some_and(|e| now.saturating_sub(e.at) < REFRESH_RETRY_SECONDS)
+}
+
+/// 启动独立的子进程刷新缓存，状态栏进程退出后子进程继续运行
+/// 已有刷新在进行中或刚刚失败过时直接返回
+pub fn spawn_background_refresh(job: &RefreshJob) {
+    let entries = job.entries();
+    if entries.is_empty() || entries.iter().any(failed_recently) {
+        return;
+    }
+
+    let claimed: Vec<_> = entries.iter().filter(|e| claim_refresh(e)).collect();
+    // 读取缓存之后、占用标记之前，其他进程可能已经刷新完成
+    let refreshed = entries
+        .iter()
+        .all(|e| e.data_file().is_some_and(|f| is_cache_fresh(&f)));
+    if claimed.len() < entries.len() || refreshed {
+        claimed.into_iter().for_each(release_refresh);
+        return;
+    }
+
+    if spawn_refresh_process(job).is_err() {
+        entries.iter().for_each(release_refresh);
+    }
+}
+
+fn spawn_refresh_process(job: &RefreshJob) -> Result<(), Box<dyn std::error::Error>> {
+    let exe = std::env::current_exe()?;
+    let mut command = Command::new(exe);
+    command
+        .arg("refresh")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    // 脱离状态栏进程所在的进程组 / 控制台，避免被 Claude Code 一起结束
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const DETACHED_PROCESS: u32 = 0x0000_0008;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(DETACHED_PROCESS | CREATE_NO_WINDOW);
+    }
+
+    let mut child = command.spawn()?;
+    // 密钥通过 stdin 传递，不出现在进程参数中
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(serde_json::to_string(job)?.as_bytes())?;
+    }
+    // 常驻的守护进程需要回收子进程，否则每次刷新都留下僵尸进程；
+    // 普通状态栏进程先退出时子进程由 init 接管
     std::thread::spawn(move || {
-        std::thread::sleep(std::time::Duration::from_secs(1));
+        let _ = child.wait();
+    });
+    Ok(())
+}
 
-        let api_config = super::ApiConfig {
-            enabled: true,
-            api_key,
-            subscription_url: "https://api.cometix.cn/v1/billing/subscription/list".to_string(),
-            usage_url: "https://api.byebyecode.tech/v1/balance/remaining".to_string(),
-        };
+/// `byebyecode refresh`：从 stdin 读取刷新任务，更新缓存并记录错误
+pub fn run_refresh_from_stdin() -> Result<(), Box<dyn std::error::Error>> {
+    let mut input = String::new();
+    std::io::stdin().read_to_string(&mut input)?;
+    let job: RefreshJob = serde_json::from_str(&input)?;
 
-        if let Ok(client) = super::client::ApiClient::new(api_config) {
-            // 后台缓存更新时没有 model 信息，传 None
-            if let Ok(subs) = client.get_subscriptions(None) {
-                let _ = save_cached_subscriptions(&subs);
+    let result = run_refresh(&job);
+    job.entries().iter().for_each(release_refresh);
+    // 后台进程顺便清理过期条目，不影响状态栏渲染
+    collect_garbage();
+    result
+}
+
+fn run_refresh(job: &RefreshJob) -> Result<(), Box<dyn std::error::Error>> {
+    let model = job.model.as_deref();
+    let usage_entry = CacheEntry::usage(&job.api_config, model);
+    let subscriptions_entry = CacheEntry::subscriptions(&job.api_config, model);
+
+    let client = match super::client::ApiClient::new(job.api_config.clone()) {
+        Ok(client) => client,
+        Err(e) => {
+            let entry = if job.usage {
+                &usage_entry
+            } else {
+                &subscriptions_entry
+            };
+            record_refresh_error(entry, &e.to_string());
+            return Err(e);
+        }
+    };
+
+    if job.usage {
+        match client.get_usage(model) {
+            Ok(usage) => {
+                save_cached(&usage_entry, &usage)?;
+                clear_refresh_error(&usage_entry);
             }
+            Err(e) => record_refresh_error(&usage_entry, &e.to_string()),
         }
-    });
+    }
+
+    if job.subscriptions && client.provider().supports_subscriptions() {
+        match client.get_subscriptions(model) {
+            Ok(subs) => {
+                save_cached(&subscriptions_entry, &subs)?;
+                clear_refresh_error(&subscriptions_entry);
+            }
+            Err(e) => record_refresh_error(&subscriptions_entry, &e.to_string()),
+        }
+    }
+
+    Ok(())
 }
 
-/// 后台异步更新使用量数据（延迟1秒执行）
-pub fn spawn_background_usage_update(api_key: String) {
-    std::thread::spawn(move || {
-        std::thread::sleep(std::time::Duration::from_secs(1));
+/// 缓存优先获取使用量
+/// - 缓存新鲜：直接返回
+/// - 缓存过期：返回旧缓存，并启动后台刷新
+/// - 没有缓存：同步请求 API；刚刚失败过时不再请求，避免每次渲染都阻塞在出错的接口上
+pub fn usage_with_refresh(api_config: &ApiConfig, model: Option<&str>) -> Option<UsageData> {
+    let entry = CacheEntry::usage(api_config, model);
+    let (cached, needs_refresh) = get_cached(&entry);
+    if let Some(usage) = cached {
+        if needs_refresh {
+            spawn_background_refresh(&RefreshJob {
+                api_config: api_config.clone(),
+                model: model.map(str::to_string),
+                usage: true,
+                subscriptions: false,
+            });
+        }
+        return Some(usage);
+    }
 
-        let api_config = super::ApiConfig {
-            enabled: true,
-            api_key,
-            subscription_url: "https://api.cometix.cn/v1/billing/subscription/list".to_string(),
-            usage_url: "https://api.byebyecode.tech/v1/balance/remaining".to_string(),
-        };
+    if failed_recently(&entry) {
+        return None;
+    }
 
-        if let Ok(client) = super::client::ApiClient::new(api_config) {
-            // 后台缓存更新时没有 model 信息，传 None
-            if let Ok(usage) = client.get_usage(None) {
-                let _ = save_cached_usage(&usage);
-            }
+    let result = super::client::ApiClient::new(api_config.clone())
+        .and_then(|client| client.get_usage(model));
+    match result {
+        Ok(usage) => {
+            let _ = save_cached(&entry, &usage);
+            clear_refresh_error(&entry);
+            Some(usage)
         }
-    });
+        Err(e) => {
+            record_refresh_error(&entry, &e.to_string());
+            None
+        }
+    }
+}
+
+/// 缓存优先获取订阅数据，规则同 `usage_with_refresh`
+pub fn subscriptions_with_refresh(
+    api_config: &ApiConfig,
+    model: Option<&str>,
+) -> Option<Vec<SubscriptionData>> {
+    let entry = CacheEntry::subscriptions(api_config, model);
+    let (cached, needs_refresh) = get_cached(&entry);
+    if let Some(subs) = cached {
+        if needs_refresh {
+            spawn_background_refresh(&RefreshJob {
+                api_config: api_config.clone(),
+                model: model.map(str::to_string),
+                usage: false,
+                subscriptions: true,
+            });
+        }
+        return Some(subs);
+    }
+
+    if failed_recently(&entry) {
+        return None;
+    }
+
+    let result = super::client::ApiClient::new(api_config.clone())
+        .and_then(|client| client.get_subscriptions(model));
+    match result {
+        Ok(subs) => {
+            let _ = save_cached(&entry, &subs);
+            clear_refresh_error(&entry);
+            Some(subs)
+        }
+        Err(e) => {
+            record_refresh_error(&entry, &e.to_string());
+            None
+        }
+    }
 }
@@ -1,95 +1,74 @@
+use super::provider::{self, PackyProvider, RelayProvider};
 use super::{ApiConfig, SubscriptionData, UsageData};
 use reqwest::blocking::Client;
-use std::time::Duration;
 
 pub struct ApiClient {
     config: ApiConfig,
     client: Client,
+    provider: Box<dyn RelayProvider>,
 }
 
 impl ApiClient {
     pub fn new(config: ApiConfig) -> Result<Self, Box<dyn std::error::Error>> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(10))
-            .user_agent("byebyecode/1.0.0")
-            .build()?;
+        let client = crate::utils::http::client(&config.http)?;
+
+        // 显式配置了 provider 时必须能识别
+        // 未配置且无法从 URL 推断时，沿用 Packy 兼容格式（New API 风格）
+        let provider = match config.provider() {
+            Some(provider) => provider,
+            None if config.has_explicit_provider() => {
+                return Err(format!(
+                    "Unknown relay provider: {} (available: {}, custom)",
+                    config.provider.as_deref().unwrap_or_default(),
+                    provider::PROVIDER_NAMES.join(", ")
+                )
+                .into());
+            }
+            None => Box::new(PackyProvider),
+        };
+
+        Ok(Self {
+            config,
+            client,
+            provider,
+        })
+    }
 
-        Ok(Self { config, client })
+    /// 当前使用的中转站适配器
+    pub fn provider(&self) -> &dyn RelayProvider {
+        self.provider.as_ref()
     }
 
     pub fn get_usage(&self, model: Option<&str>) -> Result<UsageData, Box<dyn std::error::Error>> {
-        // 88code 使用特定的 API 格式（POST + ResponseDTO）
-        // 其他中转站（Packy 及其他）使用通用格式（GET + 直接响应）
-        let is_88code = self.config.is_88code();
-
-        let response = if is_88code {
-            // 88code 系列：POST 请求，传入 model 参数
-            let body = match model {
-                Some(m) => serde_json::json!({ "model": m }),
-                None => serde_json::json!({}),
-            };
-            self.client
-                .post(&self.config.usage_url)
-                .header("Authorization", format!("Bearer {}", self.config.api_key))
-                .header("Content-Type", "application/json")
-                .json(&body)
-                .send()?
-        } else {
-            // Packy 及其他中转站：GET 请求
-            self.client
-                .get(&self.config.usage_url)
-                .header("Authorization", format!("Bearer {}", self.config.api_key))
-                .send()?
-        };
+        let request = self
+            .provider
+            .usage_request(&self.client, &self.config, model);
+        let (status, response_text) = crate::utils::http::send(request, &self.config.http)?;
 
-        if !response.status().is_success() {
-            return Err(format!("Usage API request failed: {}", response.status()).into());
+        if !status.is_success() {
+            return Err(format!("Usage API request failed: {}", status).into());
         }
 
-        let response_text = response.text()?;
-
-        let mut usage: UsageData = if is_88code {
-            // 88code：解析 ResponseDTO 包装的响应
-            let resp: super::ResponseDTO<super::Code88UsageData> =
-                serde_json::from_str(&response_text).map_err(|e| {
-                    format!(
-                        "88code JSON parse error: {} | Response: {}",
-                        e, response_text
-                    )
-                })?;
-            let data = resp.data;
-
-            // 检查 usage API 数据是否有效
-            // 如果无效（creditLimit=null, subscriptionEntityList=null），fallback 到 subscription API
-            if !data.is_valid() {
-                // Fallback: 从 subscription API 获取数据
-                match self.get_subscriptions(model) {
-                    Ok(subscriptions) => {
-                        let fallback_data =
-                            super::Code88UsageData::from_subscriptions(&subscriptions);
-                        UsageData::Code88(fallback_data)
-                    }
-                    Err(_) => {
-                        // subscription API 也失败了，返回原始数据（可能显示异常）
-                        UsageData::Code88(data)
-                    }
+        let mut usage = self.provider.parse_usage(&response_text)?;
+
+        // 检查 usage API 数据是否有效
+        // 如果无效（如 88code 返回 creditLimit=null, subscriptionEntityList=null），
+        // fallback 到 subscription API
+        if !usage.is_valid() && self.provider.supports_subscriptions() {
+            if let Ok(subscriptions) = self.get_subscriptions(model) {
+                if let Some(fallback) = self.provider.usage_from_subscriptions(&subscriptions) {
+                    usage = fallback;
                 }
-            } else {
-                UsageData::Code88(data)
             }
-        } else {
-            // Packy 及其他中转站：使用 Packy 格式解析
-            let resp: super::PackyUsageResponse =
-                serde_json::from_str(&response_text).map_err(|e| {
-                    format!(
-                        "Relay JSON parse error: {} | Response: {}",
-                        e, response_text
-                    )
-                })?;
-            UsageData::Packy(resp.data)
-        };
+            // subscription API 也失败了，返回原始数据（可能显示异常）
+        }
 
         usage.calculate();
+
+        // 每次成功获取都记录到本地时间序列，供 `byebyecode usage history` 统计
+        let _ =
+            super::history::record_usage(&self.config.account_key(), self.provider.name(), &usage);
+
         Ok(usage)
     }
 
@@ -97,37 +76,23 @@ impl ApiClient {
         &self,
         model: Option<&str>,
     ) -> Result<Vec<SubscriptionData>, Box<dyn std::error::Error>> {
-        // 构建请求体，传入 model 参数以获取正确的套餐信息
-        // 如果不传 model，API 会默认返回 free 套餐
-        let body = match model {
-            Some(m) => serde_json::json!({ "model": m }),
-            None => serde_json::json!({}),
-        };
-
-        let response = self
-            .client
-            .post(&self.config.subscription_url)
-            .header("Authorization", format!("Bearer {}", self.config.api_key))
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()?;
-
-        if !response.status().is_success() {
-            return Err(format!("Subscription API request failed: {}", response.status()).into());
-        }
-
-        let response_text = response.text()?;
-
-        // 解析 ResponseDTO 包装的响应
-        let resp: super::ResponseDTO<Vec<SubscriptionData>> = serde_json::from_str(&response_text)
-            .map_err(|e| {
+        let request = self
+            .provider
+            .subscription_request(&self.client, &self.config, model)
+            .ok_or_else(|| {
                 format!(
-                    "Subscription JSON parse error: {} | Response: {}",
-                    e, response_text
+                    "Relay provider {} does not support subscriptions",
+                    self.provider.name()
                 )
             })?;
 
-        let mut subscriptions = resp.data;
+        let (status, response_text) = crate::utils::http::send(request, &self.config.http)?;
+
+        if !status.is_success() {
+            return Err(format!("Subscription API request failed: {}", status).into());
+        }
+
+        let mut subscriptions = self.provider.parse_subscriptions(&response_text)?;
 
         // 格式化每个订阅的显示数据
         for subscription in &mut subscriptions {
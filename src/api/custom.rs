@@ -0,0 +1,385 @@
+use super::provider::RelayProvider;
+use super::{ApiConfig, CustomUsageData, UsageData};
+use reqwest::blocking::{Client, RequestBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// 自定义中转站响应格式（在 config.toml 中声明，无需重新编译）
+///
+/// 示例：
+/// ```toml
+/// [segments.options]
+/// provider = "custom"
+/// usage_url = "https://relay.example.com/api/user/self"
+///
+/// [segments.options.relay_schema]
+/// method = "GET"
+/// unit_divisor = 500000
+///
+/// [segments.options.relay_schema.headers]
+/// Authorization = "Bearer {api_key}"
+///
+/// [segments.options.relay_schema.fields]
+/// current_credits = "$.data.quota"
+/// used_credits = "$.data.used_quota"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomRelaySchema {
+    /// HTTP 方法（GET / POST）
+    #[serde(default = "default_method")]
+    pub method: String,
+    /// 请求头，值支持 `{api_key}`、`{model}` 占位符
+    #[serde(default = "default_headers")]
+    pub headers: HashMap<String, String>,
+    /// 请求体模板（JSON 字符串），支持 `{api_key}`、`{model}` 占位符
+    /// 占位符写在 JSON 字符串内，如 `{"key": "{api_key}"}`，替换时按 JSON 转义
+    #[serde(default)]
+    pub body: Option<String>,
+    /// 字段映射（JSONPath 风格路径）
+    #[serde(default)]
+    pub fields: CustomFieldPaths,
+    /// 额度单位换算：原始数值除以该值得到美元（如 New API 为 500000）
+    #[serde(default = "default_unit_divisor")]
+    pub unit_divisor: f64,
+}
+
+/// 各字段在响应 JSON 中的路径，如 `$.data.quota` 或 `data.items[0].quota`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CustomFieldPaths {
+    /// 套餐总额度
+    #[serde(default)]
+    pub credit_limit: Option<String>,
+    /// 剩余额度
+    #[serde(default)]
+    pub current_credits: Option<String>,
+    /// 已用额度
+    #[serde(default)]
+    pub used_credits: Option<String>,
+    /// 到期时间（时间戳或字符串）
+    #[serde(default)]
+    pub expires_at: Option<String>,
+    /// 套餐名称
+    #[serde(default)]
+    pub plan_name: Option<String>,
+    /// 是否无限额度
+    #[serde(default)]
+    pub unlimited: Option<String>,
+}
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+fn default_headers() -> HashMap<String, String> {
+    let mut headers = HashMap::new();
+    headers.insert("Authorization".to_string(), "Bearer {api_key}".to_string());
+    headers
+}
+
+fn default_unit_divisor() -> f64 {
+    1.0
+}
+
+impl CustomRelaySchema {
+    /// 从段配置的 `relay_schema` 选项解析，未配置时返回 Ok(None)
+    /// 格式错误时返回具体原因，由 `--check` 和状态栏显示
+    pub fn from_options(options: &HashMap<String, Value>) -> Result<Option<Self>, String> {
+        options
+            .get("relay_schema")
+            .map(Self::from_value)
+            .transpose()
+    }
+
+    pub fn from_value(value: &Value) -> Result<Self, String> {
+        serde_json::from_value(value.clone()).map_err(|e| format!("invalid relay_schema: {}", e))
+    }
+
+    /// 替换占位符；escape 为 JSON 字符串转义函数，请求体中的密钥含引号或反斜杠时不会破坏 JSON
+    fn render_template(
+        template: &str,
+        config: &ApiConfig,
+        model: Option<&str>,
+        escape: fn(&str) -> String,
+    ) -> String {
+        template
+            .replace("{api_key}", &escape(&config.api_key))
+            .replace("{model}", &escape(model.unwrap_or("")))
+    }
+
+    /// 从响应 JSON 中提取数值字段，缺失或 null 视为 None
+    fn extract_number(root: &Value, path: Option<&str>) -> Option<f64> {
+        match json_path(root, path?)? {
+            Value::Number(n) => n.as_f64(),
+            Value::String(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    fn extract_string(root: &Value, path: Option<&str>) -> Option<String> {
+        match json_path(root, path?)? {
+            Value::Null => None,
+            Value::String(s) => Some(s.clone()),
+            other => Some(other.to_string()),
+        }
+    }
+
+    fn extract_bool(root: &Value, path: Option<&str>) -> bool {
+        match path.and_then(|p| json_path(root, p)) {
+            Some(Value::Bool(b)) => *b,
+            Some(Value::Number(n)) => n.as_f64().is_some_and(|v| v != 0.0),
+            Some(Value::String(s)) => s.eq_ignore_ascii_case("true"),
+            _ => false,
+        }
+    }
+
+    /// 按字段映射把响应解析为统一的用量数据
+    pub fn parse(&self, body: &str) -> Result<CustomUsageData, Box<dyn std::error::Error>> {
+        let root: Value = serde_json::from_str(body)
+            .map_err(|e| format!("Custom relay JSON parse error: {} | Response: {}", e, body))?;
+
+        let divisor = if self.unit_divisor > 0.0 {
+            self.unit_divisor
+        } else {
+            1.0
+        };
+        let scaled = |path: &Option<String>| {
+            Self::extract_number(&root, path.as_deref()).map(|v| v / divisor)
+        };
+
+        let credit_limit = scaled(&self.fields.credit_limit);
+        let current_credits = scaled(&self.fields.current_credits);
+        let used_credits = scaled(&self.fields.used_credits);
+
+        if credit_limit.is_none() && current_credits.is_none() && used_credits.is_none() {
+            return Err(format!(
+                "Custom relay schema matched no credit fields | Response: {}",
+                body
+            )
+            .into());
+        }
+
+        // 三个字段任意两个即可推算第三个，缺失的按 0 处理
+        let (credit_limit, current_credits, used_credits) =
+            match (credit_limit, current_credits, used_credits) {
+                (Some(limit), Some(current), used) => {
+                    (limit, current, used.unwrap_or(limit - current))
+                }
+                (Some(limit), None, Some(used)) => (limit, limit - used, used),
+                (None, Some(current), Some(used)) => (current + used, current, used),
+                (limit, current, used) => (
+                    limit.unwrap_or(0.0),
+                    current.unwrap_or(0.0),
+                    used.unwrap_or(0.0),
+                ),
+            };
+
+        Ok(CustomUsageData {
+            relay: "custom".to_string(),
+            plan_name: Self::extract_string(&root, self.fields.plan_name.as_deref()),
+            expires_at: Self::extract_string(&root, self.fields.expires_at.as_deref()),
+            unlimited: Self::extract_bool(&root, self.fields.unlimited.as_deref()),
+            credit_limit,
+            current_credits,
+            used_credits,
+            used_tokens: 0,
+            remaining_tokens: 0,
+            percentage_used: 0.0,
+        })
+    }
+}
+
+/// 按 JSONPath 风格路径取值
+///
+/// 支持 `$.data.quota`、`data.quota`、`data.items[0].quota` 和 `data.items.0.quota`
+pub fn json_path<'a>(root: &'a Value, path: &str) -> Option<&'a Value> {
+    let path = path.trim();
+    let path = path.strip_prefix('$').unwrap_or(path);
+
+    let mut current = root;
+    for part in path.split('.').filter(|p| !p.is_empty()) {
+        // 拆分 key[0][1] 形式
+        let (key, indexes) = match part.find('[') {
+            Some(pos) => (&part[..pos], &part[pos..]),
+            None => (part, ""),
+        };
+
+        if !key.is_empty() {
+            current = match current {
+                Value::Object(map) => map.get(key)?,
+                Value::Array(items) => items.get(key.parse::<usize>().ok()?)?,
+                _ => return None,
+            };
+        }
+
+        for index in indexes.split('[').filter(|s| !s.is_empty()) {
+            let index: usize = index.trim_end_matches(']').trim().parse().ok()?;
+            current = current.as_array()?.get(index)?;
+        }
+    }
+
+    Some(current)
+}
+
+/// 转义为 JSON 字符串内容（不含两侧引号）
+fn json_escape(value: &str) -> String {
+    let quoted = serde_json::to_string(value).unwrap_or_default();
+    quoted
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// 基于配置声明的中转站
+pub struct CustomProvider {
+    schema: CustomRelaySchema,
+}
+
+impl CustomProvider {
+    pub fn new(schema: CustomRelaySchema) -> Self {
+        Self { schema }
+    }
+}
+
+impl RelayProvider for CustomProvider {
+    fn name(&self) -> &'static str {
+        "relay"
+    }
+
+    fn usage_request(
+        &self,
+        client: &Client,
+        config: &ApiConfig,
+        model: Option<&str>,
+    ) -> RequestBuilder {
+        let mut request = if self.schema.method.eq_ignore_ascii_case("POST") {
+            client.post(&config.usage_url)
+        } else {
+            client.get(&config.usage_url)
+        };
+
+        for (name, value) in &self.schema.headers {
+            request = request.header(
+                name.as_str(),
+                CustomRelaySchema::render_template(value, config, model, str::to_string),
+            );
+        }
+
+        if let Some(body) = &self.schema.body {
+            request = request.header("Content-Type", "application/json").body(
+                CustomRelaySchema::render_template(body, config, model, json_escape),
+            );
+        }
+
+        request
+    }
+
+    fn parse_usage(&self, body: &str) -> Result<UsageData, Box<dyn std::error::Error>> {
+        Ok(UsageData::Custom(self.schema.parse(body)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample() -> Value {
+        json!({
+            "data": {
+                "quota": 1500000,
+                "used_quota": "500000",
+                "items": [{"quota": 1}, {"quota": 2, "tags": [["a", "b"]]}],
+                "plan": null
+            }
+        })
+    }
+
+    #[test]
+    fn json_path_accepts_dollar_and_plain_paths() {
+        let root = sample();
+        assert_eq!(json_path(&root, "$.data.quota"), Some(&json!(1500000)));
+        assert_eq!(json_path(&root, "data.quota"), Some(&json!(1500000)));
+        assert_eq!(json_path(&root, " $.data.quota "), Some(&json!(1500000)));
+        assert_eq!(json_path(&root, "$"), Some(&root));
+    }
+
+    #[test]
+    fn json_path_indexes_arrays() {
+        let root = sample();
+        assert_eq!(json_path(&root, "data.items[1].quota"), Some(&json!(2)));
+        assert_eq!(json_path(&root, "data.items.0.quota"), Some(&json!(1)));
+        assert_eq!(
+            json_path(&root, "data.items[1].tags[0][1]"),
+            Some(&json!("b"))
+        );
+    }
+
+    #[test]
+    fn json_path_misses_return_none() {
+        let root = sample();
+        assert_eq!(json_path(&root, "data.missing"), None);
+        assert_eq!(json_path(&root, "data.items[5]"), None);
+        assert_eq!(json_path(&root, "data.items[x]"), None);
+        assert_eq!(json_path(&root, "data.quota.inner"), None);
+        assert_eq!(json_path(&root, "data.quota[0]"), None);
+        assert_eq!(json_path(&root, "data.plan"), Some(&Value::Null));
+    }
+
+    #[test]
+    fn parse_derives_missing_field_and_scales() {
+        let schema: CustomRelaySchema = serde_json::from_value(json!({
+            "unit_divisor": 500000,
+            "fields": {"current_credits": "$.data.quota", "used_credits": "$.data.used_quota"}
+        }))
+        .unwrap();
+        let usage = schema.parse(&sample().to_string()).unwrap();
+        assert_eq!(usage.current_credits, 3.0);
+        assert_eq!(usage.used_credits, 1.0);
+        assert_eq!(usage.credit_limit, 4.0);
+        assert!(schema.parse("{}").is_err());
+        assert!(schema.parse("not json").is_err());
+    }
+
+    #[test]
+    fn body_placeholders_are_json_escaped() {
+        let schema = CustomRelaySchema::from_value(&json!({
+            "method": "POST",
+            "body": r#"{"key": "{api_key}", "model": "{model}"}"#
+        }))
+        .unwrap();
+        let config = ApiConfig {
+            usage_url: "http://127.0.0.1/usage".to_string(),
+            api_key: r#"sk-"quoted"\key"#.to_string(),
+            ..ApiConfig::default()
+        };
+        let request = CustomProvider::new(schema)
+            .usage_request(&Client::new(), &config, Some("m\n"))
+            .build()
+            .unwrap();
+
+        let body: Value =
+            serde_json::from_slice(request.body().unwrap().as_bytes().unwrap()).unwrap();
+        assert_eq!(body["key"], json!(r#"sk-"quoted"\key"#));
+        assert_eq!(body["model"], json!("m\n"));
+        assert_eq!(
+            request.headers()["Authorization"],
+            r#"Bearer sk-"quoted"\key"#
+        );
+    }
+
+    #[test]
+    fn from_options_reports_invalid_schemas() {
+        let options = |schema: Value| HashMap::from([("relay_schema".to_string(), schema)]);
+        assert!(CustomRelaySchema::from_options(&HashMap::new())
+            .unwrap()
+            .is_none());
+        assert!(CustomRelaySchema::from_options(&options(json!({})))
+            .unwrap()
+            .is_some());
+        let error =
+            CustomRelaySchema::from_options(&options(json!({ "unit_divisor": "x" }))).unwrap_err();
+        assert!(error.starts_with("invalid relay_schema:"), "{}", error);
+    }
+}
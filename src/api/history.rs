@@ -0,0 +1,560 @@
+use super::UsageData;
+use chrono::{DateTime, Duration, Local, TimeZone, Timelike};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// 数据未变化时的最小记录间隔：5分钟
+/// 状态栏每次刷新都会请求用量接口，避免写入大量重复样本
+const UNCHANGED_SAMPLE_INTERVAL_SECONDS: i64 = 300;
+
+/// 样本保留天数，更早的样本在压缩时删除（`usage history --days` 最多能看到这么久）
+const RETENTION_DAYS: i64 = 30;
+
+/// 从文件末尾向前读取时每次多读的字节数
+const TAIL_CHUNK_BYTES: u64 = 64 * 1024;
+
+/// 用量时间序列中的一条样本（JSONL 一行）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageSample {
+    /// Unix 时间戳（秒）
+    pub timestamp: i64,
+    /// 账号标识（见 `ApiConfig::account_key`），旧版本记录的样本为空
+    #[serde(default)]
+    pub account: String,
+    /// 中转站名称（88code / packy / relay）
+    pub service: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan_name: Option<String>,
+    pub credit_limit: f64,
+    pub current_credits: f64,
+}
+
+impl UsageSample {
+    pub fn from_usage(account: &str, service: &str, usage: &UsageData) -> Self {
+        Self {
+            timestamp: Local::now().timestamp(),
+            account: account.to_string(),
+            service: service.to_string(),
+            plan_name: usage.get_plan_name(),
+            credit_limit: usage.get_credit_limit(),
+            current_credits: usage.get_current_credits(),
+        }
+    }
+
+    pub fn local_time(&self) -> DateTime<Local> {
+        Local
+            .timestamp_opt(self.timestamp, 0)
+            .single()
+            .unwrap_or_else(Local::now)
+    }
+
+    /// 是否与另一样本属于同一账号的同一套餐（跨套餐的额度变化不计入消耗）
+    fn same_plan(&self, other: &UsageSample) -> bool {
+        self.account == other.account
+            && self.service == other.service
+            && self.plan_name == other.plan_name
+    }
+
+    fn same_values(&self, other: &UsageSample) -> bool {
+        self.same_plan(other)
+            && (self.credit_limit - other.credit_limit).abs() < f64::EPSILON
+            && (self.current_credits - other.current_credits).abs() < f64::EPSILON
+    }
+}
+
+/// 获取历史文件路径（默认 ~/.claude/byebyecode/history/usage.jsonl）
+fn get_history_file() -> Option<PathBuf> {
+    let history_dir = crate::utils::paths::byebyecode_home()?.join("history");
+
+    // 确保目录存在
+    fs::create_dir_all(&history_dir).ok()?;
+
+    Some(history_dir.join("usage.jsonl"))
+}
+
+/// 读取某个账号的最后一条样本（只读取文件末尾，避免每次刷新都扫描整个文件）
+fn read_last_sample(file: &PathBuf, account: &str) -> Option<UsageSample> {
+    let mut f = fs::File::open(file).ok()?;
+    let len = f.metadata().ok()?.len();
+    let start = len.saturating_sub(16 * 1024);
+    f.seek(SeekFrom::Start(start)).ok()?;
+
+    let mut tail = String::new();
+    f.read_to_string(&mut tail).ok()?;
+
+    tail.lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str::<UsageSample>(line).ok())
+        .find(|sample| sample.account == account)
+}
+
+/// 追加一条用量样本
+/// 与该账号上一条样本数据相同且间隔不足5分钟时跳过；
+/// 文件里最早的样本超过保留期一天以上时顺带压缩，文件大小不会无限增长
+pub fn record_usage(
+    account: &str,
+    service: &str,
+    usage: &UsageData,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(file) = get_history_file() else {
+        return Ok(());
+    };
+
+    // 多个状态栏进程可能同时追加，压缩时的重命名不能和追加交错
+    let lock = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(file.with_extension("lock"))?;
+    lock.lock()?;
+
+    let sample = UsageSample::from_usage(account, service, usage);
+
+    if let Some(last) = read_last_sample(&file, account) {
+        if sample.same_values(&last)
+            && sample.timestamp - last.timestamp < UNCHANGED_SAMPLE_INTERVAL_SECONDS
+        {
+            return Ok(());
+        }
+    }
+
+    let mut f = OpenOptions::new().create(true).append(true).open(&file)?;
+    writeln!(f, "{}", serde_json::to_string(&sample)?)?;
+    drop(f);
+
+    let retention = sample.timestamp - Duration::days(RETENTION_DAYS).num_seconds();
+    let compact_before = retention - Duration::days(1).num_seconds();
+    if read_first_sample(&file).is_some_and(|first| first.timestamp < compact_before) {
+        compact(&file, retention)?;
+    }
+    Ok(())
+}
+
+/// 读取文件里的第一条样本（最早记录的样本）
+fn read_first_sample(file: &Path) -> Option<UsageSample> {
+    let f = fs::File::open(file).ok()?;
+    BufReader::new(f)
+        .lines()
+        .map_while(Result::ok)
+        .find_map(|line| serde_json::from_str::<UsageSample>(&line).ok())
+}
+
+/// 只保留 since 之后的样本：先写临时文件再重命名替换
+fn compact(file: &Path, since: i64) -> Result<(), Box<dyn std::error::Error>> {
+    let mut content = String::new();
+    for sample in read_samples_since(file, since) {
+        content.push_str(&serde_json::to_string(&sample)?);
+        content.push('\n');
+    }
+
+    let tmp = file.with_extension(format!("{}.tmp", std::process::id()));
+    fs::write(&tmp, content)?;
+    if let Err(e) = fs::rename(&tmp, file) {
+        let _ = fs::remove_file(&tmp);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// 从文件末尾向前读取，直到读到 since 之前的样本或文件开头
+/// 样本按追加顺序（即时间顺序）写入，窗口外的部分不需要解析
+fn read_samples_since(file: &Path, since: i64) -> Vec<UsageSample> {
+    let Ok(mut f) = fs::File::open(file) else {
+        return Vec::new();
+    };
+    let Ok(len) = f.metadata().map(|m| m.len()) else {
+        return Vec::new();
+    };
+
+    let mut chunk = TAIL_CHUNK_BYTES;
+    loop {
+        let start = len.saturating_sub(chunk);
+        let mut bytes = Vec::new();
+        if f.seek(SeekFrom::Start(start)).is_err() || f.read_to_end(&mut bytes).is_err() {
+            return Vec::new();
+        }
+        let tail = String::from_utf8_lossy(&bytes);
+
+        // 不是从文件开头读的话，第一行可能只读到一半
+        let samples: Vec<UsageSample> = tail
+            .lines()
+            .skip(usize::from(start > 0))
+            .filter_map(|line| serde_json::from_str::<UsageSample>(line).ok())
+            .collect();
+
+        if start == 0 || samples.first().is_some_and(|s| s.timestamp < since) {
+            return samples
+                .into_iter()
+                .filter(|s| s.timestamp >= since)
+                .collect();
+        }
+        chunk *= 4;
+    }
+}
+
+/// 读取指定时间之后的全部样本（按时间排序，跳过损坏的行）
+pub fn load_samples(since: DateTime<Local>) -> Vec<UsageSample> {
+    let Some(file) = get_history_file() else {
+        return Vec::new();
+    };
+
+    let mut samples = read_samples_since(&file, since.timestamp());
+    samples.sort_by_key(|s| s.timestamp);
+    samples
+}
+
+/// 只保留某个账号的样本
+pub fn account_samples(samples: &[UsageSample], account: &str) -> Vec<UsageSample> {
+    samples
+        .iter()
+        .filter(|s| s.account == account)
+        .cloned()
+        .collect()
+}
+
+/// 同一账号相邻样本间的额度消耗，归属到后一条样本的时间点
+///
+/// 多个账号的样本交错记录时分别计算；额度增加（重置、充值）或切换套餐时不计入消耗。
+pub fn consumption_deltas(samples: &[UsageSample]) -> Vec<(DateTime<Local>, f64)> {
+    let mut previous: HashMap<&str, &UsageSample> = HashMap::new();
+    let mut deltas = Vec::new();
+
+    for sample in samples {
+        if let Some(last) = previous.insert(sample.account.as_str(), sample) {
+            let used = last.current_credits - sample.current_credits;
+            if last.same_plan(sample) && used > 0.0 {
+                deltas.push((sample.local_time(), used));
+            }
+        }
+    }
+
+    deltas
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Granularity {
+    Hourly,
+    Daily,
+}
+
+impl Granularity {
+    fn truncate(&self, time: DateTime<Local>) -> DateTime<Local> {
+        let hour = match self {
+            Granularity::Hourly => time.hour(),
+            Granularity::Daily => 0,
+        };
+        time.date_naive()
+            .and_hms_opt(hour, 0, 0)
+            .and_then(|t| Local.from_local_datetime(&t).earliest())
+            .unwrap_or(time)
+    }
+
+    fn step(&self) -> Duration {
+        match self {
+            Granularity::Hourly => Duration::hours(1),
+            Granularity::Daily => Duration::days(1),
+        }
+    }
+}
+
+/// 一个时间桶内的消耗
+#[derive(Debug, Clone)]
+pub struct ConsumptionBucket {
+    pub start: DateTime<Local>,
+    /// 消耗额度（美元）
+    pub used: f64,
+    /// 桶内有消耗的样本数
+    pub samples: usize,
+}
+
+/// 按小时/天汇总消耗，从 since 到现在的每个时间桶都会输出（无消耗为 0）
+pub fn bucket_consumption(
+    samples: &[UsageSample],
+    granularity: Granularity,
+    since: DateTime<Local>,
+) -> Vec<ConsumptionBucket> {
+    let now = Local::now();
+    let mut buckets = Vec::new();
+    let mut start = granularity.truncate(since);
+    while start <= now {
+        buckets.push(ConsumptionBucket {
+            start,
+            used: 0.0,
+            samples: 0,
+        });
+        let next = granularity.truncate(start + granularity.step());
+        // 夏令时切换时 truncate 可能回退，保证单调递增
+        start = if next > start {
+            next
+        } else {
+            start + granularity.step()
+        };
+    }
+
+    for (time, used) in consumption_deltas(samples) {
+        let key = granularity.truncate(time);
+        if let Some(bucket) = buckets.iter_mut().find(|b| b.start == key) {
+            bucket.used += used;
+            bucket.samples += 1;
+        }
+    }
+
+    buckets
+}
+
+/// 消耗速率
+#[derive(Debug, Clone)]
+pub struct BurnRate {
+    /// 每小时消耗（美元）
+    pub per_hour: f64,
+    /// 实际参与计算的时间跨度（小时）
+    pub window_hours: f64,
+    /// 最新样本的剩余额度（美元）
+    pub remaining: f64,
+    /// 按当前速率预计还能使用的小时数
+    pub hours_left: Option<f64>,
+}
+
+/// 计算最近一段时间的消耗速率，`samples` 应只包含一个账号（见 `account_samples`）
+/// 样本不足两条或时间跨度为 0 时返回 None
+pub fn burn_rate(samples: &[UsageSample], window: Duration) -> Option<BurnRate> {
+    let latest = samples.last()?;
+    let cutoff = latest.timestamp - window.num_seconds();
+    let recent: Vec<UsageSample> = samples
+        .iter()
+        .filter(|s| s.timestamp >= cutoff)
+        .cloned()
+        .collect();
+
+    let first = recent.first()?;
+    let span_seconds = latest.timestamp - first.timestamp;
+    if recent.len() < 2 || span_seconds <= 0 {
+        return None;
+    }
+
+    let used: f64 = consumption_deltas(&recent).iter().map(|(_, u)| u).sum();
+    let window_hours = span_seconds as f64 / 3600.0;
+    let per_hour = used / window_hours;
+
+    Some(BurnRate {
+        per_hour,
+        window_hours,
+        remaining: latest.current_credits,
+        hours_left: (per_hour > 0.0).then(|| latest.current_credits.max(0.0) / per_hour),
+    })
+}
+
+fn format_hours(hours: f64) -> String {
+    if hours >= 48.0 {
+        format!("{:.1}d", hours / 24.0)
+    } else if hours >= 1.0 {
+        format!("{:.1}h", hours)
+    } else {
+        format!("{:.0}m", hours * 60.0)
+    }
+}
+
+fn print_buckets(title: &str, buckets: &[ConsumptionBucket], time_format: &str) {
+    let total: f64 = buckets.iter().map(|b| b.used).sum();
+    let peak = buckets.iter().map(|b| b.used).fold(0.0, f64::max);
+
+    println!("{}", title);
+    println!("{:<18} {:>10} {:>8}", "Period", "Used ($)", "Samples");
+    for bucket in buckets {
+        // 简易条形图，按峰值归一化
+        let bar_len = if peak > 0.0 {
+            (bucket.used / peak * 20.0).round() as usize
+        } else {
+            0
+        };
+        println!(
+            "{:<18} {:>10.2} {:>8}  {}",
+            bucket.start.format(time_format),
+            bucket.used,
+            bucket.samples,
+            "█".repeat(bar_len)
+        );
+    }
+    println!("{:<18} {:>10.2}", "Total", total);
+    println!();
+}
+
+/// `byebyecode usage history`：打印按天/按小时的消耗表和消耗速率
+pub fn print_history(days: u32, hours: u32) -> Result<(), Box<dyn std::error::Error>> {
+    let now = Local::now();
+    let days = days.max(1);
+    let hours = hours.max(1);
+
+    // 多取一条窗口外的样本，保证窗口内第一个时间桶的消耗能算出来
+    let since = now - Duration::days(days as i64).max(Duration::hours(hours as i64));
+    let samples = load_samples(since - Duration::hours(1));
+
+    if samples.len() < 2 {
+        println!("Not enough usage history yet.");
+        println!(
+            "Samples are recorded each time the statusline fetches usage ({}).",
+            get_history_file()
+                .map(|p| p.display().to_string())
+                .unwrap_or_else(|| "~/.claude/byebyecode/history/usage.jsonl".to_string())
+        );
+        return Ok(());
+    }
+
+    let daily_since = now - Duration::days(days as i64 - 1);
+    print_buckets(
+        &format!("Daily consumption (last {} days)", days),
+        &bucket_consumption(&samples, Granularity::Daily, daily_since),
+        "%Y-%m-%d",
+    );
+
+    let hourly_since = now - Duration::hours(hours as i64 - 1);
+    print_buckets(
+        &format!("Hourly consumption (last {} hours)", hours),
+        &bucket_consumption(&samples, Granularity::Hourly, hourly_since),
+        "%m-%d %H:00",
+    );
+
+    if let Some(latest) = samples.last() {
+        println!(
+            "Current plan: {} {} (${:.2} / ${:.2} remaining)",
+            latest.service,
+            latest.plan_name.as_deref().unwrap_or("-"),
+            latest.current_credits,
+            latest.credit_limit
+        );
+    }
+
+    // 消耗速率和剩余额度只针对最近使用的账号
+    let current = samples
+        .last()
+        .map(|latest| account_samples(&samples, &latest.account))
+        .unwrap_or_default();
+    for window in [1, 24] {
+        match burn_rate(&current, Duration::hours(window)) {
+            Some(rate) => println!(
+                "Burn rate (last {}h): ${:.2}/h over {}{}",
+                window,
+                rate.per_hour,
+                format_hours(rate.window_hours),
+                rate.hours_left
+                    .map(|h| format!(", ~{} until exhausted", format_hours(h)))
+                    .unwrap_or_default()
+            ),
+            None => println!("Burn rate (last {}h): not enough samples", window),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(timestamp: i64, account: &str, current_credits: f64) -> UsageSample {
+        UsageSample {
+            timestamp,
+            account: account.to_string(),
+            service: "packy".to_string(),
+            plan_name: Some("pro".to_string()),
+            credit_limit: 100.0,
+            current_credits,
+        }
+    }
+
+    #[test]
+    fn deltas_are_computed_per_account() {
+        // 两个账号交错记录：a 从 90 用到 80，b 从 50 用到 45
+        let samples = vec![
+            sample(0, "a", 90.0),
+            sample(60, "b", 50.0),
+            sample(120, "a", 85.0),
+            sample(180, "b", 45.0),
+            sample(240, "a", 80.0),
+        ];
+        let used: Vec<f64> = consumption_deltas(&samples)
+            .into_iter()
+            .map(|(_, used)| used)
+            .collect();
+        assert_eq!(used, vec![5.0, 5.0, 5.0]);
+    }
+
+    #[test]
+    fn refills_and_plan_changes_are_not_consumption() {
+        let max = |timestamp, current| UsageSample {
+            plan_name: Some("max".to_string()),
+            ..sample(timestamp, "a", current)
+        };
+        let samples = vec![
+            sample(0, "a", 50.0),
+            sample(60, "a", 100.0),
+            max(120, 70.0),
+            max(180, 60.0),
+        ];
+        let used: Vec<f64> = consumption_deltas(&samples)
+            .into_iter()
+            .map(|(_, used)| used)
+            .collect();
+        assert_eq!(used, vec![10.0]);
+    }
+
+    #[test]
+    fn burn_rate_uses_one_account() {
+        let samples = vec![
+            sample(0, "a", 90.0),
+            sample(1800, "b", 10.0),
+            sample(3600, "a", 80.0),
+        ];
+        let rate = burn_rate(&account_samples(&samples, "a"), Duration::hours(2)).unwrap();
+        assert_eq!(rate.per_hour, 10.0);
+        assert_eq!(rate.remaining, 80.0);
+        assert_eq!(rate.hours_left, Some(8.0));
+        assert!(burn_rate(&account_samples(&samples, "b"), Duration::hours(2)).is_none());
+    }
+
+    #[test]
+    fn old_samples_without_account_still_parse() {
+        let line = r#"{"timestamp":1,"service":"packy","credit_limit":10.0,"current_credits":5.0}"#;
+        let sample: UsageSample = serde_json::from_str(line).unwrap();
+        assert_eq!(sample.account, "");
+    }
+
+    fn write_samples(name: &str, timestamps: impl Iterator<Item = i64>) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("byebyecode-history-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join(name);
+        let content: String = timestamps
+            .map(|t| serde_json::to_string(&sample(t, "a", 1.0)).unwrap() + "\n")
+            .collect();
+        fs::write(&file, content).unwrap();
+        file
+    }
+
+    #[test]
+    fn reads_only_samples_since_from_the_tail() {
+        // 约 100 字节一行，2 万行远大于一次读取的块大小
+        let file = write_samples("tail.jsonl", 0..20_000);
+        let samples = read_samples_since(&file, 19_990);
+        let timestamps: Vec<i64> = samples.iter().map(|s| s.timestamp).collect();
+        assert_eq!(timestamps, (19_990..20_000).collect::<Vec<_>>());
+
+        assert_eq!(read_samples_since(&file, 0).len(), 20_000);
+        let _ = fs::remove_file(file);
+    }
+
+    #[test]
+    fn compaction_drops_samples_before_retention() {
+        let file = write_samples("compact.jsonl", (0..10).map(|t| t * 100));
+        compact(&file, 500).unwrap();
+        let timestamps: Vec<i64> = read_samples_since(&file, 0)
+            .iter()
+            .map(|s| s.timestamp)
+            .collect();
+        assert_eq!(timestamps, vec![500, 600, 700, 800, 900]);
+        assert_eq!(read_first_sample(&file).unwrap().timestamp, 500);
+        let _ = fs::remove_file(file);
+    }
+}
@@ -1,6 +1,11 @@
 pub mod cache;
 pub mod client;
+pub mod custom;
+pub mod history;
+pub mod provider;
 
+use custom::{CustomProvider, CustomRelaySchema};
+use provider::RelayProvider;
 use serde::{Deserialize, Deserializer, Serialize};
 use std::path::PathBuf;
 
@@ -30,6 +35,15 @@ pub struct ApiConfig {
     pub api_key: String,
     pub usage_url: String,
     pub subscription_url: String,
+    /// 显式指定的中转站类型（如 "88code"、"packy"），为空时根据 usage_url 推断
+    #[serde(default)]
+    pub provider: Option<String>,
+    /// 自定义中转站响应格式（provider = "custom" 时使用）
+    #[serde(default)]
+    pub relay_schema: Option<CustomRelaySchema>,
+    /// 代理、根证书和超时（配置文件的 `[http]` 段）
+    #[serde(default)]
+    pub http: crate::config::HttpConfig,
 }
 
 /// 后端统一响应包装器
@@ -48,39 +62,83 @@ impl Default for ApiConfig {
             api_key: String::new(),
             usage_url: "https://www.88code.ai/api/usage".to_string(),
             subscription_url: "https://www.88code.ai/api/subscription".to_string(),
+            provider: None,
+            relay_schema: None,
+            http: crate::config::HttpConfig::default(),
         }
     }
 }
 
 impl ApiConfig {
+    /// 获取中转站适配器
+    /// 优先使用显式配置的 provider，未配置时才根据 usage_url 推断
+    /// 无法识别时返回 None
+    pub fn provider(&self) -> Option<Box<dyn RelayProvider>> {
+        match self.provider.as_deref().filter(|s| !s.trim().is_empty()) {
+            Some(name) if name.trim().eq_ignore_ascii_case("custom") => self.custom_provider(),
+            Some(name) => provider::from_name(name),
+            // 配置了 relay_schema 但未指定 provider 时，视为自定义中转站
+            None if self.relay_schema.is_some() => self.custom_provider(),
+            None => provider::detect(&self.usage_url),
+        }
+    }
+
+    /// 账号标识：(provider, usage_url, api_key) 的哈希前 16 位
+    /// 用于区分不同账号的用量样本，不会泄露密钥
+    pub fn account_key(&self) -> String {
+        use sha2::{Digest, Sha256};
+
+        let provider = self
+            .provider()
+            .map(|p| p.name().to_string())
+            .or_else(|| self.provider.clone())
+            .unwrap_or_default();
+
+        let mut hasher = Sha256::new();
+        for part in [provider.as_str(), &self.usage_url, &self.api_key] {
+            hasher.update(part.as_bytes());
+            hasher.update([0]);
+        }
+        format!("{:x}", hasher.finalize())[..16].to_string()
+    }
+
+    fn custom_provider(&self) -> Option<Box<dyn RelayProvider>> {
+        let schema = self.relay_schema.clone()?;
+        Some(Box::new(CustomProvider::new(schema)))
+    }
+
+    /// 是否显式配置了 provider
+    pub fn has_explicit_provider(&self) -> bool {
+        self.provider
+            .as_deref()
+            .is_some_and(|s| !s.trim().is_empty())
+    }
+
     /// 判断是否是 88code 系列中转站
     /// 88code 使用特定的 API 格式（POST + ResponseDTO 包装）
     pub fn is_88code(&self) -> bool {
-        self.usage_url.contains("88code") || self.usage_url.contains("rainapp.top")
+        self.provider().is_some_and(|p| p.name() == "88code")
     }
 
     /// 判断是否是 Packy 中转站
     pub fn is_packy(&self) -> bool {
-        self.usage_url.contains("packyapi.com")
+        self.provider().is_some_and(|p| p.name() == "packy")
     }
 
     /// 获取服务名称（用于状态栏显示）
     pub fn get_service_name(&self) -> &'static str {
-        if self.is_88code() {
-            "88code"
-        } else if self.is_packy() {
-            "packy"
-        } else {
-            "relay" // 其他中转站统一显示为 relay
-        }
+        self.provider().map(|p| p.name()).unwrap_or("relay") // 其他中转站统一显示为 relay
     }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum UsageData {
-    Code88(Code88UsageData),
+    // Code88 必须排在最后：Code88UsageData 所有字段都有默认值，会吞掉任意 JSON，
+    // Custom 和 Packy 有必填字段，读取缓存时才能还原成正确的类型
+    Custom(CustomUsageData),
     Packy(PackyUsageData),
+    Code88(Code88UsageData),
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -158,9 +216,36 @@ pub struct PackyUsageData {
     pub current_credits: f64,
 }
 
+/// 自定义中转站用量数据（由 relay_schema 字段映射得到，单位已换算为美元）
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CustomUsageData {
+    /// 数据来源标记（缓存反序列化时用于区分格式）
+    pub relay: String,
+    #[serde(default)]
+    pub plan_name: Option<String>,
+    #[serde(default)]
+    pub expires_at: Option<String>,
+    #[serde(default)]
+    pub unlimited: bool,
+    #[serde(default, deserialize_with = "deserialize_null_as_zero")]
+    pub credit_limit: f64,
+    #[serde(default, deserialize_with = "deserialize_null_as_zero")]
+    pub current_credits: f64,
+    #[serde(default, deserialize_with = "deserialize_null_as_zero")]
+    pub used_credits: f64,
+
+    #[serde(default)]
+    pub used_tokens: u64,
+    #[serde(default)]
+    pub remaining_tokens: u64,
+    #[serde(default)]
+    pub percentage_used: f64,
+}
+
 impl UsageData {
     pub fn calculate(&mut self) {
         match self {
+            UsageData::Custom(data) => data.calculate(),
             UsageData::Code88(data) => data.calculate(),
             UsageData::Packy(data) => data.calculate(),
         }
@@ -168,6 +253,7 @@ impl UsageData {
 
     pub fn is_exhausted(&self) -> bool {
         match self {
+            UsageData::Custom(data) => data.is_exhausted(),
             UsageData::Code88(data) => data.is_exhausted(),
             UsageData::Packy(data) => data.is_exhausted(),
         }
@@ -175,6 +261,7 @@ impl UsageData {
 
     pub fn get_used_tokens(&self) -> u64 {
         match self {
+            UsageData::Custom(data) => data.used_tokens,
             UsageData::Code88(data) => data.used_tokens,
             UsageData::Packy(data) => data.used_tokens,
         }
@@ -182,6 +269,7 @@ impl UsageData {
 
     pub fn get_remaining_tokens(&self) -> u64 {
         match self {
+            UsageData::Custom(data) => data.remaining_tokens,
             UsageData::Code88(data) => data.remaining_tokens,
             UsageData::Packy(data) => data.remaining_tokens,
         }
@@ -189,16 +277,37 @@ impl UsageData {
 
     pub fn get_credit_limit(&self) -> f64 {
         match self {
+            UsageData::Custom(data) => data.credit_limit,
             UsageData::Code88(data) => data.credit_limit,
             UsageData::Packy(data) => data.credit_limit,
         }
     }
 
+    /// 剩余额度（美元），超额使用时可能为负数
+    pub fn get_current_credits(&self) -> f64 {
+        match self {
+            UsageData::Custom(data) => data.current_credits,
+            UsageData::Code88(data) => data.current_credits,
+            UsageData::Packy(data) => data.current_credits,
+        }
+    }
+
+    /// 当前扣费的套餐名称（如果中转站提供）
+    pub fn get_plan_name(&self) -> Option<String> {
+        match self {
+            UsageData::Custom(data) => data.plan_name.clone(),
+            UsageData::Code88(data) => data
+                .active_subscription()
+                .map(|s| s.subscription_name.clone()),
+            UsageData::Packy(data) => Some(data.name.clone()).filter(|s| !s.is_empty()),
+        }
+    }
+
     /// 判断是否只有 FREE 套餐（仅 88code 支持）
     pub fn has_only_free(&self) -> bool {
         match self {
             UsageData::Code88(data) => data.has_only_free(),
-            UsageData::Packy(_) => false, // Packy 不支持
+            UsageData::Custom(_) | UsageData::Packy(_) => false, // Packy 及自定义中转站不支持
         }
     }
 
@@ -207,11 +316,38 @@ impl UsageData {
     pub fn is_valid(&self) -> bool {
         match self {
             UsageData::Code88(data) => data.is_valid(),
-            UsageData::Packy(_) => true, // Packy 格式不受影响
+            UsageData::Custom(_) | UsageData::Packy(_) => true, // Packy 格式不受影响
+        }
+    }
+
+    /// 套餐到期时间（如果中转站提供）
+    pub fn get_expires_at(&self) -> Option<String> {
+        match self {
+            UsageData::Custom(data) => data.expires_at.clone(),
+            UsageData::Packy(data) if data.expires_at > 0 => Some(data.expires_at.to_string()),
+            _ => None,
         }
     }
 }
 
+impl CustomUsageData {
+    pub fn calculate(&mut self) {
+        // 与 88code 统一：used_tokens / remaining_tokens 以 cents 表示（显示层会除以 100）
+        self.used_tokens = (self.used_credits * 100.0).max(0.0) as u64;
+        self.remaining_tokens = (self.current_credits * 100.0).max(0.0) as u64;
+
+        self.percentage_used = if self.credit_limit > 0.0 {
+            (self.used_credits / self.credit_limit * 100.0).clamp(0.0, 100.0)
+        } else {
+            0.0
+        };
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        !self.unlimited && self.current_credits <= 0.0
+    }
+}
+
 impl Code88UsageData {
     /// 判断 usage API 返回的数据是否有效
     /// 当 API 返回 creditLimit=null, subscriptionEntityList=null 时为无效
@@ -220,19 +356,20 @@ impl Code88UsageData {
         self.credit_limit > 0.0 || !self.subscription_entity_list.is_empty()
     }
 
-    pub fn calculate(&mut self) {
-        // 从 subscriptionEntityList 中找到正在扣费的套餐
-        // Claude Code 环境下跳过 FREE 套餐（FREE 不支持 CC）
-        // 选择第一个有消费（currentCredits < creditLimit）的非 FREE 活跃套餐
-        let active_subscription = self
-            .subscription_entity_list
+    /// 从 subscriptionEntityList 中找到正在扣费的套餐
+    /// Claude Code 环境下跳过 FREE 套餐（FREE 不支持 CC）
+    /// 选择第一个有消费（currentCredits < creditLimit）的非 FREE 活跃套餐
+    pub fn active_subscription(&self) -> Option<&SubscriptionEntity> {
+        self.subscription_entity_list
             .iter()
             .filter(|s| s.is_active)
             .filter(|s| s.subscription_name.to_uppercase() != "FREE") // 跳过 FREE
-            .find(|s| s.current_credits < s.credit_limit);
+            .find(|s| s.current_credits < s.credit_limit)
+    }
 
+    pub fn calculate(&mut self) {
         // 如果找到正在扣费的套餐，用那个套餐的数据
-        let (credit_limit, current_credits) = match active_subscription {
+        let (credit_limit, current_credits) = match self.active_subscription() {
             Some(sub) => (sub.credit_limit, sub.current_credits),
             None => (self.credit_limit, self.current_credits),
         };
@@ -462,7 +599,7 @@ struct ClaudeEnv {
 
 /// Get the path to Claude settings.json (cross-platform)
 fn get_claude_settings_path() -> Option<PathBuf> {
-    dirs::home_dir().map(|home| home.join(".claude").join("settings.json"))
+    crate::utils::paths::claude_settings_path()
 }
 
 /// Read API key from Claude settings.json if base URL is 88code or packyapi
@@ -0,0 +1,360 @@
+use super::{
+    ApiConfig, Code88UsageData, PackyUsageResponse, ResponseDTO, SubscriptionData, UsageData,
+};
+use reqwest::blocking::{Client, RequestBuilder};
+
+/// 中转站适配器
+///
+/// 每个中转站负责构建自己的请求、把响应解析为统一的 `UsageData`，
+/// 以及（可选）获取订阅信息。`ApiClient` 只负责发送请求和处理 HTTP 状态。
+pub trait RelayProvider: Send + Sync {
+    /// 服务名称（用于状态栏显示和动态图标）
+    fn name(&self) -> &'static str;
+
+    /// 构建用量查询请求
+    fn usage_request(
+        &self,
+        client: &Client,
+        config: &ApiConfig,
+        model: Option<&str>,
+    ) -> RequestBuilder;
+
+    /// 解析用量响应（不需要调用 `calculate`，由 `ApiClient` 统一处理）
+    fn parse_usage(&self, body: &str) -> Result<UsageData, Box<dyn std::error::Error>>;
+
+    /// 构建订阅查询请求，不支持订阅接口的中转站返回 None
+    fn subscription_request(
+        &self,
+        _client: &Client,
+        _config: &ApiConfig,
+        _model: Option<&str>,
+    ) -> Option<RequestBuilder> {
+        None
+    }
+
+    /// 解析订阅响应
+    fn parse_subscriptions(
+        &self,
+        _body: &str,
+    ) -> Result<Vec<SubscriptionData>, Box<dyn std::error::Error>> {
+        Err(format!("{} does not support subscriptions", self.name()).into())
+    }
+
+    /// 是否支持订阅接口
+    fn supports_subscriptions(&self) -> bool {
+        false
+    }
+
+    /// 当用量接口返回无效数据时，基于订阅数据构造用量（fallback）
+    fn usage_from_subscriptions(&self, _subscriptions: &[SubscriptionData]) -> Option<UsageData> {
+        None
+    }
+
+    /// 根据 usage_url 推断 subscription_url
+    fn default_subscription_url(&self, _usage_url: &str) -> Option<String> {
+        None
+    }
+}
+
+/// 88code 系列中转站：POST + ResponseDTO 包装
+pub struct Code88Provider;
+
+impl RelayProvider for Code88Provider {
+    fn name(&self) -> &'static str {
+        "88code"
+    }
+
+    fn usage_request(
+        &self,
+        client: &Client,
+        config: &ApiConfig,
+        model: Option<&str>,
+    ) -> RequestBuilder {
+        // 传入 model 参数以获取正确的套餐信息
+        client
+            .post(&config.usage_url)
+            .header("Authorization", format!("Bearer {}", config.api_key))
+            .header("Content-Type", "application/json")
+            .json(&model_body(model))
+    }
+
+    fn parse_usage(&self, body: &str) -> Result<UsageData, Box<dyn std::error::Error>> {
+        let resp: ResponseDTO<Code88UsageData> = serde_json::from_str(body)
+            .map_err(|e| format!("88code JSON parse error: {} | Response: {}", e, body))?;
+        Ok(UsageData::Code88(resp.data))
+    }
+
+    fn subscription_request(
+        &self,
+        client: &Client,
+        config: &ApiConfig,
+        model: Option<&str>,
+    ) -> Option<RequestBuilder> {
+        // 如果不传 model，API 会默认返回 free 套餐
+        Some(
+            client
+                .post(&config.subscription_url)
+                .header("Authorization", format!("Bearer {}", config.api_key))
+                .header("Content-Type", "application/json")
+                .json(&model_body(model)),
+        )
+    }
+
+    fn parse_subscriptions(
+        &self,
+        body: &str,
+    ) -> Result<Vec<SubscriptionData>, Box<dyn std::error::Error>> {
+        let resp: ResponseDTO<Vec<SubscriptionData>> = serde_json::from_str(body)
+            .map_err(|e| format!("Subscription JSON parse error: {} | Response: {}", e, body))?;
+        Ok(resp.data)
+    }
+
+    fn supports_subscriptions(&self) -> bool {
+        true
+    }
+
+    fn usage_from_subscriptions(&self, subscriptions: &[SubscriptionData]) -> Option<UsageData> {
+        Some(UsageData::Code88(Code88UsageData::from_subscriptions(
+            subscriptions,
+        )))
+    }
+
+    fn default_subscription_url(&self, usage_url: &str) -> Option<String> {
+        if usage_url.is_empty() {
+            None
+        } else {
+            Some(usage_url.replace("/usage", "/subscription"))
+        }
+    }
+}
+
+/// Packy 及 New API 兼容中转站：GET + 直接响应
+pub struct PackyProvider;
+
+impl RelayProvider for PackyProvider {
+    fn name(&self) -> &'static str {
+        "packy"
+    }
+
+    fn usage_request(
+        &self,
+        client: &Client,
+        config: &ApiConfig,
+        _model: Option<&str>,
+    ) -> RequestBuilder {
+        client
+            .get(&config.usage_url)
+            .header("Authorization", format!("Bearer {}", config.api_key))
+    }
+
+    fn parse_usage(&self, body: &str) -> Result<UsageData, Box<dyn std::error::Error>> {
+        let resp: PackyUsageResponse = serde_json::from_str(body)
+            .map_err(|e| format!("Relay JSON parse error: {} | Response: {}", e, body))?;
+        Ok(UsageData::Packy(resp.data))
+    }
+}
+
+fn model_body(model: Option<&str>) -> serde_json::Value {
+    match model {
+        Some(m) => serde_json::json!({ "model": m }),
+        None => serde_json::json!({}),
+    }
+}
+
+/// 所有内置中转站的名称（用于 `provider` 配置项）
+pub const PROVIDER_NAMES: &[&str] = &["88code", "packy"];
+
+/// 根据 `provider` 配置项获取中转站适配器
+pub fn from_name(name: &str) -> Option<Box<dyn RelayProvider>> {
+    match name.trim().to_lowercase().as_str() {
+        "88code" | "code88" => Some(Box::new(Code88Provider)),
+        "packy" | "packyapi" => Some(Box::new(PackyProvider)),
+        _ => None,
+    }
+}
+
+/// 根据 usage_url 推断中转站（仅在未显式配置 provider 时使用）
+pub fn detect(usage_url: &str) -> Option<Box<dyn RelayProvider>> {
+    if usage_url.contains("88code") || usage_url.contains("rainapp.top") {
+        Some(Box::new(Code88Provider))
+    } else if usage_url.contains("packyapi.com") {
+        Some(Box::new(PackyProvider))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn config(usage_url: &str) -> ApiConfig {
+        ApiConfig {
+            usage_url: usage_url.to_string(),
+            subscription_url: usage_url.replace("/usage", "/subscription"),
+            api_key: "sk-test".to_string(),
+            ..ApiConfig::default()
+        }
+    }
+
+    fn body_json(request: &reqwest::blocking::Request) -> Value {
+        serde_json::from_slice(request.body().unwrap().as_bytes().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn from_name_accepts_aliases_and_ignores_case() {
+        for (name, expected) in [
+            ("88code", "88code"),
+            (" Code88 ", "88code"),
+            ("packy", "packy"),
+            ("PackyAPI", "packy"),
+        ] {
+            assert_eq!(
+                from_name(name).map(|p| p.name()),
+                Some(expected),
+                "{}",
+                name
+            );
+        }
+        assert!(from_name("custom").is_none());
+        assert!(from_name("").is_none());
+    }
+
+    #[test]
+    fn detect_uses_known_hosts_only() {
+        let name = |url: &str| detect(url).map(|p| p.name());
+        assert_eq!(name("https://www.88code.ai/api/usage"), Some("88code"));
+        assert_eq!(name("https://api.rainapp.top/api/usage"), Some("88code"));
+        assert_eq!(name("https://www.packyapi.com/api/usage"), Some("packy"));
+        assert_eq!(name("https://relay.example.com/api/usage"), None);
+    }
+
+    #[test]
+    fn code88_posts_the_model() {
+        let config = config("https://www.88code.ai/api/usage");
+        let client = Client::new();
+
+        let request = Code88Provider
+            .usage_request(&client, &config, Some("claude-sonnet-4"))
+            .build()
+            .unwrap();
+        assert_eq!(request.method(), "POST");
+        assert_eq!(request.headers()["Authorization"], "Bearer sk-test");
+        assert_eq!(body_json(&request), json!({ "model": "claude-sonnet-4" }));
+
+        // 不传 model 时发送空对象
+        let request = Code88Provider
+            .subscription_request(&client, &config, None)
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(request.url().path(), "/api/subscription");
+        assert_eq!(body_json(&request), json!({}));
+    }
+
+    #[test]
+    fn packy_gets_usage_without_subscriptions() {
+        let config = config("https://www.packyapi.com/api/usage");
+        let client = Client::new();
+        let request = PackyProvider
+            .usage_request(&client, &config, Some("m"))
+            .build()
+            .unwrap();
+        assert_eq!(request.method(), "GET");
+        assert!(request.body().is_none());
+
+        assert!(!PackyProvider.supports_subscriptions());
+        assert!(PackyProvider
+            .subscription_request(&client, &config, None)
+            .is_none());
+        assert!(PackyProvider.parse_subscriptions("{}").is_err());
+        assert!(PackyProvider.default_subscription_url("x").is_none());
+    }
+
+    #[test]
+    fn parses_code88_usage_and_picks_the_active_plan() {
+        let body = json!({
+            "code": 0, "ok": true, "msg": "",
+            "data": {
+                "totalTokens": 0,
+                "creditLimit": null,
+                "currentCredits": null,
+                "subscriptionEntityList": [
+                    { "subscriptionName": "FREE", "creditLimit": 10.0, "currentCredits": 5.0, "isActive": true },
+                    { "subscriptionName": "PRO", "creditLimit": 50.0, "currentCredits": 20.0, "isActive": true }
+                ]
+            }
+        });
+        let mut usage = Code88Provider.parse_usage(&body.to_string()).unwrap();
+        usage.calculate();
+
+        assert!(usage.is_valid());
+        assert_eq!(usage.get_plan_name().as_deref(), Some("PRO"));
+        assert_eq!(usage.get_credit_limit(), 50.0);
+        assert_eq!(usage.get_current_credits(), 20.0);
+        assert_eq!(usage.get_used_tokens(), 3000);
+        assert!(!usage.has_only_free());
+
+        let error = Code88Provider.parse_usage("<html>").unwrap_err();
+        assert!(error.to_string().starts_with("88code JSON parse error"));
+    }
+
+    #[test]
+    fn parses_packy_usage_in_dollars() {
+        // Packy 以 500000 积分为 1 美元，超额使用时剩余额度为负数
+        let body = json!({
+            "code": true, "message": "",
+            "data": {
+                "expires_at": 1767225600, "name": "pro", "object": "credit_summary",
+                "total_available": -250000, "total_granted": 5000000,
+                "total_used": 5250000, "unlimited_quota": false
+            }
+        });
+        let mut usage = PackyProvider.parse_usage(&body.to_string()).unwrap();
+        usage.calculate();
+
+        assert_eq!(usage.get_credit_limit(), 10.0);
+        assert_eq!(usage.get_current_credits(), -0.5);
+        assert_eq!(usage.get_used_tokens(), 1050);
+        assert_eq!(usage.get_remaining_tokens(), 0);
+        assert!(usage.is_exhausted());
+        assert_eq!(usage.get_expires_at().as_deref(), Some("1767225600"));
+        assert!(PackyProvider.parse_usage("{}").is_err());
+    }
+
+    #[test]
+    fn code88_falls_back_to_subscriptions() {
+        let subscription = |name: &str, id: i64, current: f64| {
+            json!({
+                "subscriptionPlanName": name, "cost": 198.0, "endDate": null,
+                "subscriptionStatus": "活跃中", "remainingDays": 20,
+                "billingCycleDesc": "月", "resetTimes": 1, "isActive": true,
+                "currentCredits": current, "subscriptionPlan": { "creditLimit": 100.0 },
+                "id": id
+            })
+        };
+        let body = json!({
+            "code": 0, "ok": true, "msg": "",
+            "data": [subscription("FREE", 1, 10.0), subscription("PLUS", 2, 40.0)]
+        });
+        let mut subscriptions = Code88Provider
+            .parse_subscriptions(&body.to_string())
+            .unwrap();
+        subscriptions.iter_mut().for_each(|s| s.format());
+        assert_eq!(subscriptions[1].plan_price, "¥198/月");
+        assert_eq!(subscriptions[1].credit_limit, 100.0);
+
+        let usage = Code88Provider
+            .usage_from_subscriptions(&subscriptions)
+            .unwrap();
+        assert_eq!(usage.get_plan_name().as_deref(), Some("PLUS"));
+        assert_eq!(usage.get_current_credits(), 40.0);
+        assert_eq!(
+            Code88Provider
+                .default_subscription_url("https://www.88code.ai/api/usage")
+                .as_deref(),
+            Some("https://www.88code.ai/api/subscription")
+        );
+    }
+}
@@ -8,7 +8,7 @@ pub struct ClaudeSettingsConfigurator;
 impl ClaudeSettingsConfigurator {
     /// 获取 Claude settings.json 的路径
     pub fn get_settings_path() -> Option<PathBuf> {
-        dirs::home_dir().map(|home| home.join(".claude").join("settings.json"))
+        crate::utils::paths::claude_settings_path()
     }
 
     /// 获取当前二进制文件的绝对路径
@@ -107,6 +107,10 @@ impl ClaudeSettingsConfigurator {
         // 只在有修改时写回文件
         if modified {
             let formatted = serde_json::to_string_pretty(&settings)?;
+            // byebyecode 目录不在 Claude Code 目录下时（BYEBYECODE_HOME），该目录可能还不存在
+            if let Some(dir) = settings_path.parent() {
+                fs::create_dir_all(dir)?;
+            }
             fs::write(&settings_path, formatted)?;
             println!("✓ Claude settings.json 配置完成");
             println!("  路径: {}", settings_path.display());
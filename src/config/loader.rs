@@ -1,4 +1,5 @@
-use super::types::Config;
+use super::migrations::{self, Migration};
+use super::types::{Config, SegmentId};
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -54,13 +55,9 @@ impl ConfigLoader {
         Ok(())
     }
 
-    /// Get the themes directory path (~/.claude/byebyecode/themes/)
+    /// Get the themes directory path (`themes/` in the byebyecode home)
     pub fn get_themes_path() -> PathBuf {
-        if let Some(home) = dirs::home_dir() {
-            home.join(".claude").join("byebyecode").join("themes")
-        } else {
-            PathBuf::from(".claude/byebyecode/themes")
-        }
+        crate::utils::paths::byebyecode_home_or_default().join("themes")
     }
 
     /// Ensure themes directory exists and has built-in themes (silent mode)
@@ -104,7 +101,14 @@ impl ConfigLoader {
 
 impl Config {
     /// Load configuration from default location
+    ///
+    /// Older schemas are migrated; the original file is backed up and then rewritten.
     pub fn load() -> Result<Config, Box<dyn std::error::Error>> {
+        Self::load_migrated(true)
+    }
+
+    /// Load configuration, optionally writing migrations back to disk
+    pub(crate) fn load_migrated(write_back: bool) -> Result<Config, Box<dyn std::error::Error>> {
         // Ensure themes directory exists and has built-in themes
         ConfigLoader::ensure_themes_exist();
 
@@ -114,11 +118,34 @@ impl Config {
             return Ok(Config::default());
         }
 
-        let content = fs::read_to_string(config_path)?;
-        let config: Config = toml::from_str(&content)?;
+        let content = fs::read_to_string(&config_path)?;
+        let mut table: toml::Table = toml::from_str(&content)?;
+        let from_version = migrations::config_version(&table);
+        let applied = migrations::migrate(&mut table)?;
+        let config: Config = toml::Value::Table(table).try_into()?;
+
+        // Only rewrite once the migrated config is known to be valid
+        if write_back
+            && !applied.is_empty()
+            && migrations::backup(&config_path, from_version).is_ok()
+        {
+            let _ = config.save();
+        }
+
         Ok(config)
     }
 
+    /// Migrations the config file on disk has not received yet
+    pub fn pending_migrations() -> Result<Vec<&'static Migration>, Box<dyn std::error::Error>> {
+        let config_path = Self::get_config_path();
+        if !config_path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let table: toml::Table = toml::from_str(&fs::read_to_string(config_path)?)?;
+        Ok(migrations::pending(&table))
+    }
+
     /// Save configuration to default location
     pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
         let config_path = Self::get_config_path();
@@ -133,13 +160,10 @@ impl Config {
         Ok(())
     }
 
-    /// Get the default config file path (~/.claude/byebyecode/config.toml)
-    fn get_config_path() -> PathBuf {
-        if let Some(home) = dirs::home_dir() {
-            home.join(".claude").join("byebyecode").join("config.toml")
-        } else {
-            PathBuf::from(".claude/byebyecode/config.toml")
-        }
+    /// Get the default config file path (`config.toml` in the byebyecode home,
+    /// `~/.claude/byebyecode/` by default; see `utils::paths`)
+    pub fn get_config_path() -> PathBuf {
+        crate::utils::paths::byebyecode_home_or_default().join("config.toml")
     }
 
     /// Initialize config directory and create default config
@@ -178,20 +202,53 @@ impl Config {
             return Err("No segments configured".into());
         }
 
-        // Validate segment IDs are unique
+        // Validate segment IDs are unique; custom segments are told apart by name
         let mut seen_ids = std::collections::HashSet::new();
+        let mut seen_custom = std::collections::HashSet::new();
         for segment in &self.segments {
-            if !seen_ids.insert(segment.id) {
+            if segment.id == SegmentId::Custom {
+                let name = segment.options.get("name").and_then(|v| v.as_str());
+                if !seen_custom.insert(name.unwrap_or_default()) {
+                    return Err(format!(
+                        "Duplicate custom segment name: {:?}",
+                        name.unwrap_or_default()
+                    )
+                    .into());
+                }
+                if segment
+                    .options
+                    .get("command")
+                    .and_then(|v| v.as_str())
+                    .is_none()
+                {
+                    return Err(format!(
+                        "Custom segment {:?} has no command",
+                        name.unwrap_or_default()
+                    )
+                    .into());
+                }
+            } else if !seen_ids.insert(segment.id) {
                 return Err(format!("Duplicate segment ID: {:?}", segment.id).into());
             }
         }
 
+        // Validate option values against the segment's schema
+        for segment in &self.segments {
+            for (key, value) in &segment.options {
+                if let Some(spec) = segment.id.option_spec(key) {
+                    spec.kind.validate(value).map_err(|e| {
+                        format!("{}.options.{}: {}", segment.id.config_name(), key, e)
+                    })?;
+                }
+            }
+        }
+
         Ok(())
     }
 
-    /// Print configuration as TOML
+    /// Print configuration as TOML, with literal API keys masked
     pub fn print(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let content = toml::to_string_pretty(self)?;
+        let content = toml::to_string_pretty(&super::secrets::redact_config(self))?;
         println!("{}", content);
         Ok(())
     }
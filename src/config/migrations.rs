@@ -0,0 +1,362 @@
+use super::rules::{default_subscription_rules, default_usage_rules, StyleRule};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Schema version written by this build; configs without a `version` key are version 0
+pub const CURRENT_CONFIG_VERSION: u32 = 3;
+
+/// One schema upgrade, applied to the raw TOML before deserialization
+pub struct Migration {
+    /// Version the config has after this step
+    pub to_version: u32,
+    pub description: &'static str,
+    apply: fn(&mut toml::Table),
+}
+
+/// All migrations in the order they are applied
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        to_version: 1,
+        description: "Rename legacy 88code segment IDs and option keys",
+        apply: rename_legacy_names,
+    },
+    Migration {
+        to_version: 2,
+        description: "Move relay api_key from segment options to [relay]",
+        apply: move_api_key_to_relay,
+    },
+    Migration {
+        to_version: 3,
+        description: "Add the default usage and plan color rules to relay segments",
+        apply: add_default_color_rules,
+    },
+];
+
+/// Legacy segment IDs: names from the 88code era and the unsplit spellings
+/// used in older docs, mapped to the serialized `SegmentId` names
+const SEGMENT_ID_RENAMES: &[(&str, &str)] = &[
+    ("88code_usage", "bye_bye_code_usage"),
+    ("88code_subscription", "bye_bye_code_subscription"),
+    ("88code_status", "bye_bye_code_status"),
+    ("byebyecode_usage", "bye_bye_code_usage"),
+    ("byebyecode_subscription", "bye_bye_code_subscription"),
+    ("byebyecode_status", "bye_bye_code_status"),
+];
+
+/// Option keys renamed per segment: (segment ID, old key, new key)
+const OPTION_KEY_RENAMES: &[(&str, &str, &str)] = &[
+    ("bye_bye_code_usage", "api_url", "usage_url"),
+    ("bye_bye_code_subscription", "api_url", "usage_url"),
+];
+
+/// Segments that used to carry their own `api_key` option
+const API_KEY_SEGMENTS: &[&str] = &["bye_bye_code_usage", "bye_bye_code_subscription"];
+
+/// Default `rules` of the segments whose colors used to be built in
+fn default_rules(segment_id: &str) -> Option<Vec<StyleRule>> {
+    match segment_id {
+        "bye_bye_code_usage" => Some(default_usage_rules()),
+        "bye_bye_code_subscription" => Some(default_subscription_rules()),
+        _ => None,
+    }
+}
+
+/// Current ID for a legacy segment ID
+pub fn renamed_segment_id(old: &str) -> Option<&'static str> {
+    SEGMENT_ID_RENAMES
+        .iter()
+        .find(|(from, _)| *from == old)
+        .map(|(_, to)| *to)
+}
+
+fn segments_mut(config: &mut toml::Table) -> impl Iterator<Item = &mut toml::Table> {
+    config
+        .get_mut("segments")
+        .and_then(|v| v.as_array_mut())
+        .into_iter()
+        .flatten()
+        .filter_map(|s| s.as_table_mut())
+}
+
+fn segment_id(segment: &toml::Table) -> Option<&str> {
+    segment.get("id").and_then(|v| v.as_str())
+}
+
+fn rename_legacy_names(config: &mut toml::Table) {
+    for segment in segments_mut(config) {
+        if let Some((_, new_id)) = SEGMENT_ID_RENAMES
+            .iter()
+            .find(|(old, _)| segment_id(segment) == Some(old))
+        {
+            segment.insert("id".to_string(), toml::Value::String(new_id.to_string()));
+        }
+
+        let id = segment_id(segment).unwrap_or_default().to_string();
+        let Some(options) = segment.get_mut("options").and_then(|v| v.as_table_mut()) else {
+            continue;
+        };
+        for (_, old_key, new_key) in OPTION_KEY_RENAMES.iter().filter(|(s, _, _)| *s == id) {
+            if let Some(value) = options.remove(*old_key) {
+                // An explicitly set new key wins over the legacy one
+                if !options.contains_key(*new_key) {
+                    options.insert(new_key.to_string(), value);
+                }
+            }
+        }
+    }
+}
+
+fn move_api_key_to_relay(config: &mut toml::Table) {
+    let existing = config
+        .get("relay")
+        .and_then(|r| r.get("api_key"))
+        .and_then(|v| v.as_str())
+        .filter(|k| !k.is_empty())
+        .map(str::to_string);
+    let mut relay_key = existing;
+
+    for segment in segments_mut(config) {
+        if !API_KEY_SEGMENTS.contains(&segment_id(segment).unwrap_or_default()) {
+            continue;
+        }
+        let Some(options) = segment.get_mut("options").and_then(|v| v.as_table_mut()) else {
+            continue;
+        };
+        let key = options
+            .get("api_key")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string();
+
+        // Keep a per-segment key only when it differs from the shared one
+        if key.is_empty() || relay_key.as_deref() == Some(key.as_str()) {
+            options.remove("api_key");
+        } else if relay_key.is_none() {
+            options.remove("api_key");
+            relay_key = Some(key);
+        }
+    }
+
+    if let Some(key) = relay_key {
+        let relay = config
+            .entry("relay")
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        if let Some(relay) = relay.as_table_mut() {
+            relay.insert("api_key".to_string(), toml::Value::String(key));
+        }
+    }
+}
+
+/// Segments used to color usage and plans in code unless `rules` were set; keep
+/// those colors by adding the equivalent rules where none are configured
+fn add_default_color_rules(config: &mut toml::Table) {
+    for segment in segments_mut(config) {
+        let Some(rules) = segment_id(segment).and_then(default_rules) else {
+            continue;
+        };
+        let Ok(rules) = toml::Value::try_from(rules) else {
+            continue;
+        };
+        let options = segment
+            .entry("options")
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        if let Some(options) = options.as_table_mut() {
+            options.entry("rules").or_insert(rules);
+        }
+    }
+}
+
+/// Schema version of a raw config; missing means the unversioned layout (0)
+pub fn config_version(config: &toml::Table) -> u32 {
+    config
+        .get("version")
+        .and_then(|v| v.as_integer())
+        .and_then(|v| u32::try_from(v).ok())
+        .unwrap_or(0)
+}
+
+/// Migrations a raw config still needs
+pub fn pending(config: &toml::Table) -> Vec<&'static Migration> {
+    let version = config_version(config);
+    MIGRATIONS
+        .iter()
+        .filter(|m| m.to_version > version)
+        .collect()
+}
+
+/// Upgrade a raw config to the current schema in place, returning the applied steps
+pub fn migrate(
+    config: &mut toml::Table,
+) -> Result<Vec<&'static Migration>, Box<dyn std::error::Error>> {
+    let version = config_version(config);
+    if version > CURRENT_CONFIG_VERSION {
+        return Err(format!(
+            "Config version {} is newer than this byebyecode supports ({}); please upgrade",
+            version, CURRENT_CONFIG_VERSION
+        )
+        .into());
+    }
+
+    let steps = pending(config);
+    for step in &steps {
+        (step.apply)(config);
+        config.insert(
+            "version".to_string(),
+            toml::Value::Integer(step.to_version as i64),
+        );
+    }
+    Ok(steps)
+}
+
+/// Copy the original file next to itself before rewriting it (config.toml.v0.bak)
+pub fn backup(path: &Path, from_version: u32) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "config.toml".to_string());
+
+    let mut backup_path = path.with_file_name(format!("{}.v{}.bak", file_name, from_version));
+    if backup_path.exists() {
+        // Never overwrite an earlier backup
+        let stamp = chrono::Local::now().format("%Y%m%d%H%M%S");
+        backup_path = path.with_file_name(format!("{}.v{}.{}.bak", file_name, from_version, stamp));
+    }
+
+    fs::copy(path, &backup_path)?;
+    Ok(backup_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(content: &str) -> toml::Table {
+        toml::from_str(content).unwrap()
+    }
+
+    fn segment(config: &toml::Table, index: usize) -> &toml::Value {
+        &config["segments"].as_array().unwrap()[index]
+    }
+
+    #[test]
+    fn upgrades_unversioned_config() {
+        let mut config = table(
+            r#"
+            [[segments]]
+            id = "88code_usage"
+            options = { api_url = "https://relay/api/usage", api_key = "sk-shared" }
+
+            [[segments]]
+            id = "byebyecode_subscription"
+            options = { api_key = "sk-shared" }
+            "#,
+        );
+
+        let applied = migrate(&mut config).unwrap();
+        assert_eq!(
+            applied.iter().map(|m| m.to_version).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        assert_eq!(config_version(&config), CURRENT_CONFIG_VERSION);
+
+        let usage = segment(&config, 0);
+        assert_eq!(usage["id"].as_str(), Some("bye_bye_code_usage"));
+        assert_eq!(
+            usage["options"]["usage_url"].as_str(),
+            Some("https://relay/api/usage")
+        );
+        assert!(usage["options"].get("api_url").is_none());
+        assert!(usage["options"].get("api_key").is_none());
+        assert_eq!(
+            segment(&config, 1)["id"].as_str(),
+            Some("bye_bye_code_subscription")
+        );
+        assert_eq!(config["relay"]["api_key"].as_str(), Some("sk-shared"));
+        assert!(pending(&config).is_empty());
+    }
+
+    #[test]
+    fn keeps_explicit_new_keys_and_differing_segment_keys() {
+        let mut config = table(
+            r#"
+            [relay]
+            api_key = "sk-relay"
+
+            [[segments]]
+            id = "88code_usage"
+            options = { api_url = "https://old", usage_url = "https://new", api_key = "sk-other" }
+            "#,
+        );
+
+        migrate(&mut config).unwrap();
+        let options = &segment(&config, 0)["options"];
+        assert_eq!(options["usage_url"].as_str(), Some("https://new"));
+        assert_eq!(options["api_key"].as_str(), Some("sk-other"));
+        assert_eq!(config["relay"]["api_key"].as_str(), Some("sk-relay"));
+    }
+
+    #[test]
+    fn skips_applied_steps_and_rejects_newer_versions() {
+        let mut config = table(
+            r#"
+            version = 1
+
+            [[segments]]
+            id = "bye_bye_code_usage"
+            options = { api_key = "sk-a" }
+            "#,
+        );
+        let applied = migrate(&mut config).unwrap();
+        assert_eq!(applied.len(), 2);
+        assert_eq!(applied[0].to_version, 2);
+
+        let mut newer = table("version = 99");
+        assert!(migrate(&mut newer).is_err());
+    }
+
+    #[test]
+    fn adds_default_color_rules_unless_rules_are_set() {
+        let mut config = table(
+            r#"
+            version = 2
+
+            [[segments]]
+            id = "bye_bye_code_usage"
+
+            [[segments]]
+            id = "bye_bye_code_subscription"
+            options = { rules = [] }
+
+            [[segments]]
+            id = "git"
+            "#,
+        );
+        migrate(&mut config).unwrap();
+
+        let rules = segment(&config, 0)["options"]["rules"].as_array().unwrap();
+        assert_eq!(rules.len(), default_usage_rules().len());
+        assert_eq!(rules[0]["colors"]["text"]["c256"].as_integer(), Some(114));
+        assert!(segment(&config, 1)["options"]["rules"]
+            .as_array()
+            .unwrap()
+            .is_empty());
+        assert!(segment(&config, 2).get("options").is_none());
+    }
+
+    #[test]
+    fn backups_never_overwrite_each_other() {
+        let dir = std::env::temp_dir().join(format!("byebyecode-migrate-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        fs::write(&path, "theme = \"nord\"").unwrap();
+
+        let first = backup(&path, 0).unwrap();
+        let second = backup(&path, 0).unwrap();
+        let first_content = fs::read_to_string(&first).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(first, dir.join("config.toml.v0.bak"));
+        assert_ne!(first, second);
+        assert_eq!(first_content, "theme = \"nord\"");
+    }
+}
@@ -1,8 +1,18 @@
 pub mod defaults;
+pub mod layers;
 pub mod loader;
+pub mod migrations;
 pub mod models;
+pub mod rules;
+pub mod schema;
+pub mod secrets;
 pub mod types;
+pub mod validator;
 
+pub use layers::{ConfigLayer, LayeredConfig};
 pub use loader::ConfigLoader;
+pub use migrations::CURRENT_CONFIG_VERSION;
 pub use models::*;
+pub use rules::StyleRule;
+pub use schema::{OptionKind, OptionSpec};
 pub use types::*;
@@ -1,6 +1,12 @@
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Last loaded model configuration and the modification times it was loaded at.
+/// Long-running processes (the statusline daemon) reuse it until a models.toml changes.
+static LOADED: Mutex<Option<(Vec<Option<SystemTime>>, ModelConfig)>> = Mutex::new(None);
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModelConfig {
@@ -13,6 +19,71 @@ pub struct ModelEntry {
     pub pattern: String,
     pub display_name: String,
     pub context_limit: u32,
+    /// Token prices used by the local cost ledger
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pricing: Option<ModelPricing>,
+}
+
+/// Token prices in USD per million tokens
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPricing {
+    pub input: f64,
+    pub output: f64,
+    /// Cache write (5-minute TTL); defaults to 1.25x input
+    #[serde(default)]
+    pub cache_write: Option<f64>,
+    /// Cache read; defaults to 0.1x input
+    #[serde(default)]
+    pub cache_read: Option<f64>,
+}
+
+impl ModelPricing {
+    const fn new(input: f64, output: f64, cache_write: f64, cache_read: f64) -> Self {
+        Self {
+            input,
+            output,
+            cache_write: Some(cache_write),
+            cache_read: Some(cache_read),
+        }
+    }
+
+    pub fn cache_write_price(&self) -> f64 {
+        self.cache_write.unwrap_or(self.input * 1.25)
+    }
+
+    pub fn cache_read_price(&self) -> f64 {
+        self.cache_read.unwrap_or(self.input * 0.1)
+    }
+
+    /// Cost in USD for the given token counts
+    pub fn cost(&self, input: u64, output: u64, cache_write: u64, cache_read: u64) -> f64 {
+        (input as f64 * self.input
+            + output as f64 * self.output
+            + cache_write as f64 * self.cache_write_price()
+            + cache_read as f64 * self.cache_read_price())
+            / 1_000_000.0
+    }
+
+    /// Built-in Anthropic list prices, matched by model ID substring (first match wins)
+    fn builtin(model_lower: &str) -> Option<Self> {
+        const PRICES: &[(&str, ModelPricing)] = &[
+            ("opus-4-6", ModelPricing::new(5.0, 25.0, 6.25, 0.5)),
+            ("opus-4-5", ModelPricing::new(5.0, 25.0, 6.25, 0.5)),
+            ("opus-4-1", ModelPricing::new(15.0, 75.0, 18.75, 1.5)),
+            ("opus-4", ModelPricing::new(15.0, 75.0, 18.75, 1.5)),
+            ("4-opus", ModelPricing::new(15.0, 75.0, 18.75, 1.5)),
+            ("3-opus", ModelPricing::new(15.0, 75.0, 18.75, 1.5)),
+            ("sonnet", ModelPricing::new(3.0, 15.0, 3.75, 0.3)),
+            ("haiku-4-5", ModelPricing::new(1.0, 5.0, 1.25, 0.1)),
+            ("3-5-haiku", ModelPricing::new(0.8, 4.0, 1.0, 0.08)),
+            ("3-haiku", ModelPricing::new(0.25, 1.25, 0.3, 0.03)),
+        ];
+
+        PRICES
+            .iter()
+            .find(|(pattern, _)| model_lower.contains(pattern))
+            .map(|(_, pricing)| *pricing)
+    }
 }
 
 impl ModelConfig {
@@ -23,27 +94,52 @@ impl ModelConfig {
         Ok(config)
     }
 
+    /// Candidate models.toml locations: user config directory first, then local
+    fn config_paths() -> Vec<PathBuf> {
+        crate::utils::paths::byebyecode_home()
+            .map(|d| d.join("models.toml"))
+            .into_iter()
+            .chain(std::iter::once(Path::new("models.toml").to_path_buf()))
+            .collect()
+    }
+
     /// Load model configuration with fallback locations
+    ///
+    /// The result is memoized per process and reloaded when a models.toml changes.
     pub fn load() -> Self {
-        let mut model_config = Self::default();
-
         // First, try to create default models.toml if it doesn't exist
-        if let Some(home_dir) = dirs::home_dir() {
-            let user_models_path = home_dir.join(".claude/byebyecode").join("models.toml");
+        if let Some(home_dir) = crate::utils::paths::byebyecode_home() {
+            let user_models_path = home_dir.join("models.toml");
             if !user_models_path.exists() {
                 let _ = Self::create_default_file(&user_models_path);
             }
         }
 
-        // Try loading from user config directory first, then local
-        let config_paths = [
-            dirs::home_dir().map(|d| d.join(".claude/byebyecode").join("models.toml")),
-            Some(Path::new("models.toml").to_path_buf()),
-        ];
+        let stamps: Vec<Option<SystemTime>> = Self::config_paths()
+            .iter()
+            .map(|p| fs::metadata(p).and_then(|m| m.modified()).ok())
+            .collect();
+
+        if let Ok(mut loaded) = LOADED.lock() {
+            if let Some((loaded_stamps, config)) = loaded.as_ref() {
+                if *loaded_stamps == stamps {
+                    return config.clone();
+                }
+            }
+            let config = Self::load_uncached();
+            *loaded = Some((stamps, config.clone()));
+            return config;
+        }
+
+        Self::load_uncached()
+    }
+
+    fn load_uncached() -> Self {
+        let mut model_config = Self::default();
 
-        for path in config_paths.iter().flatten() {
+        for path in Self::config_paths() {
             if path.exists() {
-                if let Ok(config) = Self::load_from_file(path) {
+                if let Ok(config) = Self::load_from_file(&path) {
                     // Prepend external models to built-in ones for priority
                     let mut merged_entries = config.model_entries;
                     merged_entries.extend(model_config.model_entries);
@@ -88,6 +184,22 @@ impl ModelConfig {
         None
     }
 
+    /// Get token pricing for a model based on ID pattern matching
+    /// Entries with a `pricing` table take priority over built-in Anthropic prices
+    pub fn get_pricing(&self, model_id: &str) -> Option<ModelPricing> {
+        let model_lower = model_id.to_lowercase();
+
+        for entry in &self.model_entries {
+            if let Some(pricing) = entry.pricing {
+                if model_lower.contains(&entry.pattern.to_lowercase()) {
+                    return Some(pricing);
+                }
+            }
+        }
+
+        ModelPricing::builtin(&model_lower)
+    }
+
     /// Create default model configuration file with minimal template
     pub fn create_default_file<P: AsRef<Path>>(path: P) -> Result<(), Box<dyn std::error::Error>> {
         // Create a minimal template config (not the full fallback config)
@@ -113,7 +225,15 @@ impl ModelConfig {
              # [[models]]\n\
              # pattern = \"glm-4.5\"\n\
              # display_name = \"GLM-4.5\"\n\
-             # context_limit = 128000\n",
+             # context_limit = 128000\n\
+             #\n\
+             # Optional token prices (USD per million tokens) for the local cost ledger.\n\
+             # Claude models have built-in prices; cache prices default to 1.25x / 0.1x input.\n\
+             # [models.pricing]\n\
+             # input = 0.6\n\
+             # output = 2.2\n\
+             # cache_write = 0.6\n\
+             # cache_read = 0.11\n",
             toml_content.trim()
         );
 
@@ -136,6 +256,7 @@ impl Default for ModelConfig {
                     pattern: "[1m]".to_string(),
                     display_name: "Sonnet 4.5 1M".to_string(),
                     context_limit: 1_000_000,
+                    pricing: None,
                 },
                 // ModelEntry {
                 //     pattern: "claude-sonnet-4-5".to_string(),
@@ -166,27 +287,32 @@ impl Default for ModelConfig {
                     pattern: "claude-3-7-sonnet".to_string(),
                     display_name: "Sonnet 3.7".to_string(),
                     context_limit: 200_000,
+                    pricing: None,
                 },
                 // Third-party models
                 ModelEntry {
                     pattern: "glm-4.5".to_string(),
                     display_name: "GLM-4.5".to_string(),
                     context_limit: 128_000,
+                    pricing: None,
                 },
                 ModelEntry {
                     pattern: "kimi-k2-turbo".to_string(),
                     display_name: "Kimi K2 Turbo".to_string(),
                     context_limit: 128_000,
+                    pricing: None,
                 },
                 ModelEntry {
                     pattern: "kimi-k2".to_string(),
                     display_name: "Kimi K2".to_string(),
                     context_limit: 128_000,
+                    pricing: None,
                 },
                 ModelEntry {
                     pattern: "qwen3-coder".to_string(),
                     display_name: "Qwen Coder".to_string(),
                     context_limit: 256_000,
+                    pricing: None,
                 },
             ],
         }
@@ -0,0 +1,335 @@
+use super::{AnsiColor, SegmentConfig};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Conditional style override, configured per segment as `[[segments.options.rules]]`
+///
+/// A rule matches when every condition it sets holds for the metadata `field`.
+/// All matching rules apply in order, so later rules win.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StyleRule {
+    /// Metadata key the conditions look at (`percentage`, `status`, ...)
+    pub field: String,
+    /// Numeric value is greater than or equal to this
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub above: Option<f64>,
+    /// Numeric value is less than this
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub below: Option<f64>,
+    /// Value equals this string (case-insensitive)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub equals: Option<String>,
+    /// Value matches this regular expression
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matches: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<RuleIcon>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub colors: Option<RuleColors>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text_bold: Option<bool>,
+
+    /// `matches` compiled on first use; None when the pattern is invalid
+    #[serde(skip)]
+    regex: OnceLock<Option<Regex>>,
+}
+
+/// Icon override; unset variants keep the segment's icon
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuleIcon {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plain: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nerd_font: Option<String>,
+}
+
+/// Color override; unset colors keep the segment's colors
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuleColors {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<AnsiColor>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<AnsiColor>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub background: Option<AnsiColor>,
+}
+
+impl StyleRule {
+    /// Rule that sets the text color when `field` equals `value`, used by the theme presets
+    pub fn text_color_when(field: &str, value: &str, color: AnsiColor) -> Self {
+        Self {
+            field: field.to_string(),
+            equals: Some(value.to_string()),
+            colors: Some(RuleColors {
+                text: Some(color),
+                ..RuleColors::default()
+            }),
+            ..Self::default()
+        }
+    }
+
+    /// Rule that sets the text color when the numeric `field` is in `[above, below)`
+    pub fn text_color_between(
+        field: &str,
+        above: Option<f64>,
+        below: Option<f64>,
+        color: AnsiColor,
+    ) -> Self {
+        Self {
+            field: field.to_string(),
+            above,
+            below,
+            colors: Some(RuleColors {
+                text: Some(color),
+                ..RuleColors::default()
+            }),
+            ..Self::default()
+        }
+    }
+
+    /// Rule that sets the text color when `field` matches the regex `pattern`
+    pub fn text_color_matching(field: &str, pattern: &str, color: AnsiColor) -> Self {
+        Self {
+            matches: Some(pattern.to_string()),
+            equals: None,
+            ..Self::text_color_when(field, "", color)
+        }
+    }
+
+    /// Check the rule itself: a field, at least one condition, a valid regex
+    pub fn validate(&self) -> Result<(), String> {
+        if self.field.trim().is_empty() {
+            return Err("rule without a field".to_string());
+        }
+        if self.above.is_none()
+            && self.below.is_none()
+            && self.equals.is_none()
+            && self.matches.is_none()
+        {
+            return Err(format!(
+                "rule for '{}' needs a condition (above, below, equals or matches)",
+                self.field
+            ));
+        }
+        if let Some(pattern) = &self.matches {
+            if self.regex().is_none() {
+                let error = Regex::new(pattern).err().map(|e| e.to_string());
+                return Err(format!(
+                    "invalid regex '{}': {}",
+                    pattern,
+                    error.unwrap_or_default()
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn regex(&self) -> Option<&Regex> {
+        self.regex
+            .get_or_init(|| self.matches.as_deref().and_then(|p| Regex::new(p).ok()))
+            .as_ref()
+    }
+
+    pub fn is_match(&self, metadata: &HashMap<String, String>) -> bool {
+        let Some(value) = metadata.get(&self.field).map(|v| v.trim()) else {
+            return false;
+        };
+
+        if self.above.is_some() || self.below.is_some() {
+            let Ok(number) = value.parse::<f64>() else {
+                return false;
+            };
+            if self.above.is_some_and(|above| number < above)
+                || self.below.is_some_and(|below| number >= below)
+            {
+                return false;
+            }
+        }
+        if let Some(expected) = &self.equals {
+            if !value.eq_ignore_ascii_case(expected) {
+                return false;
+            }
+        }
+        if self.matches.is_some() {
+            match self.regex() {
+                Some(regex) if regex.is_match(value) => {}
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    /// Override the segment's icon, colors and bold style with what this rule sets
+    pub fn apply(&self, segment: &mut SegmentConfig) {
+        if let Some(icon) = &self.icon {
+            if let Some(plain) = &icon.plain {
+                segment.icon.plain = plain.clone();
+            }
+            if let Some(nerd_font) = &icon.nerd_font {
+                segment.icon.nerd_font = nerd_font.clone();
+            }
+        }
+        if let Some(colors) = &self.colors {
+            if let Some(color) = &colors.icon {
+                segment.colors.icon = Some(color.clone());
+            }
+            if let Some(color) = &colors.text {
+                segment.colors.text = Some(color.clone());
+            }
+            if let Some(color) = &colors.background {
+                segment.colors.background = Some(color.clone());
+            }
+        }
+        if let Some(bold) = self.text_bold {
+            segment.styles.text_bold = bold;
+        }
+    }
+}
+
+impl SegmentConfig {
+    /// Style rules from the `rules` option; invalid entries are skipped here and
+    /// reported by `--check`
+    pub fn style_rules(&self) -> Vec<StyleRule> {
+        let Some(serde_json::Value::Array(rules)) = self.options.get("rules") else {
+            return Vec::new();
+        };
+        rules
+            .iter()
+            .filter_map(|rule| serde_json::from_value::<StyleRule>(rule.clone()).ok())
+            .filter(|rule| rule.validate().is_ok())
+            .collect()
+    }
+
+    pub fn has_style_rules(&self) -> bool {
+        self.options.contains_key("rules")
+    }
+}
+
+/// Default rules of the relay usage segment: green below 50% used, yellow below 80%,
+/// red above; the PAYGO fallback (`plan` = `PAYGO`) is blue
+pub fn default_usage_rules() -> Vec<StyleRule> {
+    vec![
+        StyleRule::text_color_between(
+            "percentage",
+            None,
+            Some(50.0),
+            AnsiColor::Color256 { c256: 114 },
+        ),
+        StyleRule::text_color_between(
+            "percentage",
+            Some(50.0),
+            Some(80.0),
+            AnsiColor::Color256 { c256: 179 },
+        ),
+        StyleRule::text_color_between(
+            "percentage",
+            Some(80.0),
+            None,
+            AnsiColor::Color256 { c256: 167 },
+        ),
+        StyleRule::text_color_when("plan", "PAYGO", AnsiColor::Color256 { c256: 39 }),
+    ]
+}
+
+/// Default rules of the relay subscription segment, colored by the first plan:
+/// orange for paid plans, blue for PAYGO, gray for FREE
+pub fn default_subscription_rules() -> Vec<StyleRule> {
+    vec![
+        StyleRule::text_color_matching(
+            "plan",
+            "(?i)^(plus|pro|max)$",
+            AnsiColor::Color256 { c256: 214 },
+        ),
+        StyleRule::text_color_when("plan", "PAYGO", AnsiColor::Color256 { c256: 39 }),
+        StyleRule::text_color_when("plan", "FREE", AnsiColor::Color256 { c256: 245 }),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(field: &str, value: &str) -> HashMap<String, String> {
+        HashMap::from([(field.to_string(), value.to_string())])
+    }
+
+    #[test]
+    fn invalid_rules_are_skipped_individually() {
+        let mut segment = crate::config::Config::default().segments[0].clone();
+        segment.options.insert(
+            "rules".to_string(),
+            serde_json::json!([
+                { "field": "status", "matches": "(" },
+                { "field": "status", "equals": "dirty" },
+                { "field": "status" },
+                { "field": "status", "unknown_key": 1 },
+                { "field": "percentage", "above": 80 },
+            ]),
+        );
+
+        let rules = segment.style_rules();
+        assert_eq!(rules.len(), 2);
+        assert!(rules[0].is_match(&metadata("status", "DIRTY")));
+        assert!(rules[1].is_match(&metadata("percentage", "80")));
+        assert!(!rules[1].is_match(&metadata("percentage", "79.9")));
+    }
+
+    fn text_color(rules: &[StyleRule], field: &str, value: &str) -> Option<AnsiColor> {
+        let mut segment = crate::config::Config::default().segments[0].clone();
+        segment.colors.text = None;
+        for rule in rules.iter().filter(|r| r.is_match(&metadata(field, value))) {
+            rule.apply(&mut segment);
+        }
+        segment.colors.text
+    }
+
+    #[test]
+    fn default_rules_color_usage_and_plans() {
+        let usage = default_usage_rules();
+        let c256 = |c256| Some(AnsiColor::Color256 { c256 });
+        assert_eq!(text_color(&usage, "percentage", "12.5"), c256(114));
+        assert_eq!(text_color(&usage, "percentage", "50.0"), c256(179));
+        assert_eq!(text_color(&usage, "percentage", "80.0"), c256(167));
+        assert_eq!(text_color(&usage, "plan", "PAYGO"), c256(39));
+
+        let plans = default_subscription_rules();
+        assert_eq!(text_color(&plans, "plan", "Pro"), c256(214));
+        assert_eq!(text_color(&plans, "plan", "free"), c256(245));
+        assert_eq!(text_color(&plans, "plan", "PROMO"), None);
+        assert!(usage.iter().chain(&plans).all(|r| r.validate().is_ok()));
+    }
+
+    #[test]
+    fn regex_is_compiled_once() {
+        let rule = StyleRule {
+            field: "branch".to_string(),
+            matches: Some("^(main|master)$".to_string()),
+            ..StyleRule::default()
+        };
+        assert!(rule.regex.get().is_none());
+        assert!(rule.is_match(&metadata("branch", "main")));
+        let compiled: *const Regex = rule.regex().unwrap();
+        assert!(!rule.is_match(&metadata("branch", "feature/main")));
+        assert!(std::ptr::eq(rule.regex().unwrap(), compiled));
+    }
+
+    #[test]
+    fn invalid_regex_never_matches() {
+        let rule = StyleRule {
+            field: "branch".to_string(),
+            matches: Some("[".to_string()),
+            ..StyleRule::default()
+        };
+        assert!(rule
+            .validate()
+            .unwrap_err()
+            .starts_with("invalid regex '['"));
+        assert!(!rule.is_match(&metadata("branch", "[")));
+    }
+}
@@ -0,0 +1,583 @@
+use super::SegmentId;
+use serde_json::Value;
+
+/// Value type of a segment option
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OptionKind {
+    Bool,
+    /// Non-negative integer
+    Integer,
+    /// Positive number
+    Number,
+    String,
+    /// API key or secret reference (`env:`, `file:`, `cmd:`), see `config::secrets`
+    Secret,
+    /// http(s) URL
+    Url,
+    /// One of a fixed set of strings (case-insensitive)
+    Enum(&'static [&'static str]),
+    /// Custom relay response schema (`[segments.options.relay_schema]`)
+    RelaySchema,
+    /// Segment format template, see `core::template`
+    Template,
+    /// Conditional style rules (`[[segments.options.rules]]`)
+    Rules,
+    /// Table of string values (`[segments.options.aliases]`)
+    StringMap,
+}
+
+impl OptionKind {
+    pub fn name(&self) -> String {
+        match self {
+            OptionKind::Bool => "bool".to_string(),
+            OptionKind::Integer => "integer".to_string(),
+            OptionKind::Number => "number".to_string(),
+            OptionKind::String => "string".to_string(),
+            OptionKind::Secret => "secret".to_string(),
+            OptionKind::Url => "url".to_string(),
+            OptionKind::Enum(values) => values.join(" | "),
+            OptionKind::RelaySchema => "table".to_string(),
+            OptionKind::Template => "template".to_string(),
+            OptionKind::Rules => "array of tables".to_string(),
+            OptionKind::StringMap => "table of strings".to_string(),
+        }
+    }
+
+    /// Check a configured value against this type
+    pub fn validate(&self, value: &Value) -> Result<(), String> {
+        match self {
+            OptionKind::Bool if value.is_boolean() => Ok(()),
+            OptionKind::Integer if value.as_u64().is_some() => Ok(()),
+            OptionKind::Number => match value.as_f64() {
+                Some(n) if n > 0.0 => Ok(()),
+                Some(_) => Err("must be greater than 0".to_string()),
+                None => Err(format!("expected number, found {}", type_name(value))),
+            },
+            OptionKind::String if value.is_string() => Ok(()),
+            OptionKind::Secret => match value.as_str() {
+                Some(secret) => super::secrets::SecretRef::parse(secret).validate(),
+                None => Err(format!("expected string, found {}", type_name(value))),
+            },
+            OptionKind::Template => match value.as_str() {
+                Some(template) => crate::core::template::Template::parse(template)
+                    .map(|_| ())
+                    .map_err(|e| format!("invalid template: {}", e)),
+                None => Err(format!(
+                    "expected template string, found {}",
+                    type_name(value)
+                )),
+            },
+            OptionKind::Url => match value.as_str() {
+                // Empty means "not configured" for the relay URLs
+                Some("") => Ok(()),
+                Some(url) => validate_url(url),
+                None => Err(format!("expected url string, found {}", type_name(value))),
+            },
+            OptionKind::Enum(values) => match value.as_str() {
+                Some("") => Ok(()),
+                Some(s) if values.iter().any(|v| v.eq_ignore_ascii_case(s.trim())) => Ok(()),
+                Some(s) => Err(format!(
+                    "invalid value '{}', expected one of: {}",
+                    s,
+                    values.join(", ")
+                )),
+                None => Err(format!("expected string, found {}", type_name(value))),
+            },
+            OptionKind::Rules if value.is_array() => {
+                let rules: Vec<super::StyleRule> = serde_json::from_value(value.clone())
+                    .map_err(|e| format!("invalid rules: {}", e))?;
+                for (i, rule) in rules.iter().enumerate() {
+                    rule.validate()
+                        .map_err(|e| format!("rules[{}]: {}", i, e))?;
+                }
+                Ok(())
+            }
+            OptionKind::StringMap if value.is_object() => {
+                match value
+                    .as_object()
+                    .into_iter()
+                    .flatten()
+                    .find(|(_, v)| !v.is_string())
+                {
+                    Some((key, v)) => Err(format!(
+                        "'{}': expected string, found {}",
+                        key,
+                        type_name(v)
+                    )),
+                    None => Ok(()),
+                }
+            }
+            OptionKind::RelaySchema if value.is_object() => {
+                crate::api::custom::CustomRelaySchema::from_value(value).map(|_| ())
+            }
+            _ => Err(format!(
+                "expected {}, found {}",
+                self.name(),
+                type_name(value)
+            )),
+        }
+    }
+
+    /// Parse text typed into the TUI options editor
+    pub fn parse_input(&self, input: &str) -> Result<Value, String> {
+        let input = input.trim();
+        let value = match self {
+            OptionKind::Bool => match input.to_lowercase().as_str() {
+                "true" | "yes" | "on" | "1" => Value::Bool(true),
+                "false" | "no" | "off" | "0" => Value::Bool(false),
+                _ => return Err("expected true or false".to_string()),
+            },
+            OptionKind::Integer => Value::from(
+                input
+                    .parse::<u64>()
+                    .map_err(|_| "expected a non-negative integer".to_string())?,
+            ),
+            OptionKind::Number => Value::from(
+                input
+                    .parse::<f64>()
+                    .map_err(|_| "expected a number".to_string())?,
+            ),
+            OptionKind::String
+            | OptionKind::Secret
+            | OptionKind::Url
+            | OptionKind::Enum(_)
+            | OptionKind::Template => Value::String(input.to_string()),
+            OptionKind::RelaySchema | OptionKind::Rules | OptionKind::StringMap => {
+                return Err("edit this option in config.toml".to_string());
+            }
+        };
+        self.validate(&value)?;
+        Ok(value)
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Number(n) if n.is_f64() => "float",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "table",
+    }
+}
+
+fn validate_url(url: &str) -> Result<(), String> {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .ok_or_else(|| format!("invalid url '{}': must start with http:// or https://", url))?;
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    if host.is_empty() || host.contains(char::is_whitespace) {
+        return Err(format!("invalid url '{}': missing or invalid host", url));
+    }
+    Ok(())
+}
+
+/// Declared option of a segment
+#[derive(Debug, Clone, Copy)]
+pub struct OptionSpec {
+    pub key: &'static str,
+    pub kind: OptionKind,
+    /// Default as a TOML literal; `None` when unset means "not configured"
+    pub default: Option<&'static str>,
+    pub description: &'static str,
+}
+
+impl OptionSpec {
+    const fn new(
+        key: &'static str,
+        kind: OptionKind,
+        default: Option<&'static str>,
+        description: &'static str,
+    ) -> Self {
+        Self {
+            key,
+            kind,
+            default,
+            description,
+        }
+    }
+
+    pub fn default_value(&self) -> Option<Value> {
+        let literal = self.default?;
+        let parsed: toml::Value = toml::from_str(&format!("v = {}", literal))
+            .ok()
+            .and_then(|t: toml::Table| t.get("v").cloned())?;
+        serde_json::to_value(parsed).ok()
+    }
+}
+
+/// Every segment accepts a `format` template
+const FORMAT: OptionSpec = OptionSpec::new(
+    "format",
+    OptionKind::Template,
+    None,
+    "Template for the segment text, e.g. \"{percentage:.0}% of {limit|k}\"",
+);
+
+/// Every segment accepts conditional style rules
+const RULES: OptionSpec = OptionSpec::new(
+    "rules",
+    OptionKind::Rules,
+    None,
+    "Override colors, icon or bold when a metadata value matches",
+);
+
+/// Every segment has a priority for width-limited layouts
+const PRIORITY: OptionSpec = OptionSpec::new(
+    "priority",
+    OptionKind::Integer,
+    Some("0"),
+    "Higher priorities are kept longer when the line is too wide",
+);
+
+/// Options of every segment, listed after the segment's own options
+const COMMON_OPTIONS: &[OptionSpec] = &[FORMAT, RULES, PRIORITY];
+
+const DIRECTORY_OPTIONS: &[OptionSpec] = &[
+    OptionSpec::new(
+        "mode",
+        OptionKind::Enum(&["name", "full", "home", "fish", "repo"]),
+        Some("\"name\""),
+        "Last component, full path, ~ path, fish-style abbreviation, or git-root relative",
+    ),
+    OptionSpec::new(
+        "max_depth",
+        OptionKind::Integer,
+        Some("0"),
+        "Keep only the last N components (0 = no limit)",
+    ),
+    OptionSpec::new(
+        "aliases",
+        OptionKind::StringMap,
+        None,
+        "Replace path prefixes, e.g. \"/mnt/work/acme\" = \"acme:\"",
+    ),
+];
+
+const CONTEXT_WINDOW_OPTIONS: &[OptionSpec] = &[
+    OptionSpec::new(
+        "show_forecast",
+        OptionKind::Bool,
+        Some("true"),
+        "Show growth per turn and turns left before auto-compaction",
+    ),
+    OptionSpec::new(
+        "history_turns",
+        OptionKind::Integer,
+        Some("5"),
+        "Recent turns the growth per turn is averaged over",
+    ),
+    OptionSpec::new(
+        "compact_threshold",
+        OptionKind::Number,
+        Some("95.0"),
+        "Context usage (%) at which Claude Code auto-compacts",
+    ),
+];
+
+const PROVIDERS: &[&str] = &["88code", "code88", "packy", "packyapi", "custom"];
+
+const GIT_OPTIONS: &[OptionSpec] = &[
+    OptionSpec::new(
+        "show_sha",
+        OptionKind::Bool,
+        Some("false"),
+        "Show the short commit SHA",
+    ),
+    OptionSpec::new(
+        "show_counts",
+        OptionKind::Bool,
+        Some("false"),
+        "Show conflicted, staged, modified and untracked counts instead of ●",
+    ),
+    OptionSpec::new(
+        "show_stash",
+        OptionKind::Bool,
+        Some("true"),
+        "Show the stash count when there are stashes",
+    ),
+    OptionSpec::new(
+        "show_operation",
+        OptionKind::Bool,
+        Some("true"),
+        "Show a rebase, merge, cherry-pick, revert or bisect in progress",
+    ),
+    OptionSpec::new(
+        "show_tag",
+        OptionKind::Bool,
+        Some("false"),
+        "Show the tag on HEAD (runs an extra git command)",
+    ),
+    OptionSpec::new(
+        "show_upstream",
+        OptionKind::Bool,
+        Some("false"),
+        "Show the upstream branch after the branch name",
+    ),
+];
+
+const USAGE_OPTIONS: &[OptionSpec] = &[
+    OptionSpec::new(
+        "api_base_url",
+        OptionKind::Url,
+        Some("\"https://api.anthropic.com\""),
+        "Anthropic API base URL for the OAuth usage endpoint",
+    ),
+    OptionSpec::new(
+        "cache_duration",
+        OptionKind::Integer,
+        Some("300"),
+        "Seconds to reuse the last usage response",
+    ),
+    OptionSpec::new(
+        "timeout",
+        OptionKind::Integer,
+        Some("2"),
+        "Request timeout in seconds",
+    ),
+];
+
+const COST_OPTIONS: &[OptionSpec] = &[OptionSpec::new(
+    "source",
+    OptionKind::Enum(&["claude", "ledger", "local", "both"]),
+    Some("\"claude\""),
+    "Cost reported by Claude Code, the local ledger, or both",
+)];
+
+const RELAY_USAGE_OPTIONS: &[OptionSpec] = &[
+    OptionSpec::new(
+        "usage_url",
+        OptionKind::Url,
+        None,
+        "Relay usage endpoint; defaults to Claude settings or 88code",
+    ),
+    OptionSpec::new(
+        "subscription_url",
+        OptionKind::Url,
+        None,
+        "Relay subscription endpoint; derived from usage_url when empty",
+    ),
+    OptionSpec::new(
+        "provider",
+        OptionKind::Enum(PROVIDERS),
+        None,
+        "Relay type; detected from usage_url when empty",
+    ),
+    OptionSpec::new(
+        "api_key",
+        OptionKind::Secret,
+        None,
+        "Relay API key or env:/file:/cmd: reference; overrides [relay] api_key",
+    ),
+    OptionSpec::new(
+        "relay_schema",
+        OptionKind::RelaySchema,
+        None,
+        "Request and field mapping for provider = \"custom\"",
+    ),
+];
+
+const RELAY_SUBSCRIPTION_OPTIONS: &[OptionSpec] = &[
+    OptionSpec::new(
+        "usage_url",
+        OptionKind::Url,
+        None,
+        "Relay usage endpoint; defaults to the bye_bye_code_usage segment",
+    ),
+    OptionSpec::new(
+        "provider",
+        OptionKind::Enum(PROVIDERS),
+        None,
+        "Relay type; detected from usage_url when empty",
+    ),
+    OptionSpec::new(
+        "api_key",
+        OptionKind::Secret,
+        None,
+        "Relay API key or env:/file:/cmd: reference; overrides [relay] api_key",
+    ),
+];
+
+const BURN_RATE_OPTIONS: &[OptionSpec] = &[
+    OptionSpec::new(
+        "window_hours",
+        OptionKind::Number,
+        Some("3.0"),
+        "Hours of usage history used to compute the rate",
+    ),
+    OptionSpec::new(
+        "usage_url",
+        OptionKind::Url,
+        None,
+        "Relay usage endpoint; defaults to the bye_bye_code_usage segment",
+    ),
+    OptionSpec::new(
+        "provider",
+        OptionKind::Enum(PROVIDERS),
+        None,
+        "Relay type; defaults to the bye_bye_code_usage segment",
+    ),
+    OptionSpec::new(
+        "api_key",
+        OptionKind::Secret,
+        None,
+        "Relay API key or env:/file:/cmd: reference; defaults to the bye_bye_code_usage segment",
+    ),
+];
+
+const CUSTOM_OPTIONS: &[OptionSpec] = &[
+    OptionSpec::new(
+        "name",
+        OptionKind::String,
+        None,
+        "Name telling several custom segments apart",
+    ),
+    OptionSpec::new(
+        "command",
+        OptionKind::String,
+        None,
+        "Shell command; the first line of its output is shown",
+    ),
+    OptionSpec::new(
+        "cwd",
+        OptionKind::String,
+        None,
+        "Working directory; relative to the workspace when not absolute",
+    ),
+    OptionSpec::new(
+        "timeout_ms",
+        OptionKind::Integer,
+        Some("1000"),
+        "Kill the command after this many milliseconds",
+    ),
+    OptionSpec::new(
+        "cache_ttl",
+        OptionKind::Integer,
+        Some("0"),
+        "Seconds to reuse the last output; 0 runs the command every render",
+    ),
+    OptionSpec::new(
+        "stdin",
+        OptionKind::Bool,
+        Some("false"),
+        "Pass the Claude Code status line JSON on stdin",
+    ),
+];
+
+impl SegmentId {
+    pub const ALL: [SegmentId; 14] = [
+        SegmentId::Model,
+        SegmentId::Directory,
+        SegmentId::Git,
+        SegmentId::ContextWindow,
+        SegmentId::Usage,
+        SegmentId::Cost,
+        SegmentId::Session,
+        SegmentId::OutputStyle,
+        SegmentId::Update,
+        SegmentId::ByeByeCodeUsage,
+        SegmentId::ByeByeCodeSubscription,
+        SegmentId::ByeByeCodeStatus,
+        SegmentId::BurnRate,
+        SegmentId::Custom,
+    ];
+
+    /// Name used for `id` in config files (`bye_bye_code_usage`, ...)
+    pub fn config_name(&self) -> String {
+        serde_json::to_value(self)
+            .ok()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default()
+    }
+
+    pub fn from_config_name(name: &str) -> Option<SegmentId> {
+        serde_json::from_value(Value::String(name.to_string())).ok()
+    }
+
+    /// Options this segment understands: its own, then the common ones
+    pub fn option_schema(&self) -> Vec<&'static OptionSpec> {
+        let own: &'static [OptionSpec] = match self {
+            SegmentId::Directory => DIRECTORY_OPTIONS,
+            SegmentId::Git => GIT_OPTIONS,
+            SegmentId::ContextWindow => CONTEXT_WINDOW_OPTIONS,
+            SegmentId::Usage => USAGE_OPTIONS,
+            SegmentId::Cost => COST_OPTIONS,
+            SegmentId::ByeByeCodeUsage => RELAY_USAGE_OPTIONS,
+            SegmentId::ByeByeCodeSubscription => RELAY_SUBSCRIPTION_OPTIONS,
+            SegmentId::BurnRate => BURN_RATE_OPTIONS,
+            SegmentId::Custom => CUSTOM_OPTIONS,
+            SegmentId::Model
+            | SegmentId::Session
+            | SegmentId::OutputStyle
+            | SegmentId::Update
+            | SegmentId::ByeByeCodeStatus => &[],
+        };
+        own.iter().chain(COMMON_OPTIONS).collect()
+    }
+
+    pub fn option_spec(&self, key: &str) -> Option<&'static OptionSpec> {
+        self.option_schema()
+            .into_iter()
+            .find(|spec| spec.key == key)
+    }
+}
+
+/// Closest candidate within a small edit distance, for "did you mean" hints
+pub fn suggest<'a>(input: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    candidates
+        .into_iter()
+        .map(|c| (edit_distance(input, c), c))
+        .filter(|(d, c)| *d <= 2.max(c.len() / 4))
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, c)| c)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            cur.push((prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1));
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_distance_counts_single_character_edits() {
+        assert_eq!(edit_distance("theme", "theme"), 0);
+        assert_eq!(edit_distance("thme", "theme"), 1);
+        assert_eq!(edit_distance("show_shaa", "show_sha"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn suggest_picks_the_closest_nearby_candidate() {
+        let keys = ["theme", "style", "segments"];
+        assert_eq!(suggest("thme", keys), Some("theme"));
+        assert_eq!(suggest("segmants", keys), Some("segments"));
+        assert_eq!(suggest("translation", keys), None);
+    }
+
+    #[test]
+    fn every_segment_has_the_common_options_last() {
+        for id in SegmentId::ALL {
+            let keys: Vec<&str> = id.option_schema().iter().map(|s| s.key).collect();
+            assert_eq!(keys[keys.len() - 3..], ["format", "rules", "priority"]);
+            assert!(id.option_spec("priority").is_some());
+        }
+        assert!(SegmentId::Git.option_spec("show_sha").is_some());
+        assert!(SegmentId::Model.option_spec("show_sha").is_none());
+    }
+}
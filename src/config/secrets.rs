@@ -0,0 +1,331 @@
+//! Secret references for `api_key` values
+//!
+//! Instead of a literal key, config files may contain a reference that is resolved at runtime:
+//! - `env:NAME`: environment variable
+//! - `file:PATH`: first line of a file, `~` expands to the home directory
+//! - `cmd:COMMAND`: first line of a shell command's output, e.g. `cmd:pass show relay/88code`
+
+use super::Config;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Password managers may need a moment to unlock; the statusline should not hang longer
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How long the output of a `cmd:` reference is reused before running the command again
+const COMMAND_CACHE_SECONDS: u64 = 3600;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecretRef<'a> {
+    Literal(&'a str),
+    Env(&'a str),
+    File(&'a str),
+    Command(&'a str),
+}
+
+impl<'a> SecretRef<'a> {
+    pub fn parse(value: &'a str) -> Self {
+        let value = value.trim();
+        if let Some(name) = value.strip_prefix("env:") {
+            SecretRef::Env(name.trim())
+        } else if let Some(path) = value.strip_prefix("file:") {
+            SecretRef::File(path.trim())
+        } else if let Some(command) = value.strip_prefix("cmd:") {
+            SecretRef::Command(command.trim())
+        } else {
+            SecretRef::Literal(value)
+        }
+    }
+
+    pub fn is_literal(&self) -> bool {
+        matches!(self, SecretRef::Literal(_))
+    }
+
+    /// Reject references without a target (`env:` with no variable name, ...)
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            SecretRef::Env("") => Err("env: reference needs a variable name".to_string()),
+            SecretRef::File("") => Err("file: reference needs a path".to_string()),
+            SecretRef::Command("") => Err("cmd: reference needs a command".to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Resolve to the secret; None when it is empty or cannot be read
+    pub fn resolve(&self) -> Option<String> {
+        let secret = match self {
+            SecretRef::Literal(value) => value.to_string(),
+            SecretRef::Env(name) => std::env::var(name).ok()?,
+            SecretRef::File(path) => first_line(&fs::read_to_string(expand_home(path)).ok()?),
+            SecretRef::Command(command) => resolve_command(command)?,
+        };
+        let secret = secret.trim().to_string();
+        (!secret.is_empty()).then_some(secret)
+    }
+}
+
+/// Resolve an `api_key` value that may be a literal key or a secret reference
+pub fn resolve(value: &str) -> Option<String> {
+    SecretRef::parse(value).resolve()
+}
+
+/// Text safe to show for an `api_key` value: references as-is, literal keys masked
+pub fn redact(value: &str) -> String {
+    let secret = SecretRef::parse(value);
+    match secret {
+        SecretRef::Literal("") => String::new(),
+        SecretRef::Literal(key) => {
+            let chars: Vec<char> = key.chars().collect();
+            if chars.len() >= 12 {
+                let tail: String = chars[chars.len() - 4..].iter().collect();
+                format!("****{}", tail)
+            } else {
+                "****".to_string()
+            }
+        }
+        _ => value.trim().to_string(),
+    }
+}
+
+/// Copy of `config` with every literal API key masked, for `--print`
+pub fn redact_config(config: &Config) -> Config {
+    let mut config = config.clone();
+    config.relay.api_key = redact(&config.relay.api_key);
+    config.translation.api_key = redact(&config.translation.api_key);
+    for segment in &mut config.segments {
+        if let Some(serde_json::Value::String(key)) = segment.options.get_mut("api_key") {
+            *key = redact(key);
+        }
+    }
+    config
+}
+
+/// Variable names of the `env:` references among the configured API keys
+pub fn env_references(config: &Config) -> Vec<String> {
+    let segment_keys = config
+        .segments
+        .iter()
+        .filter_map(|segment| segment.options.get("api_key").and_then(|v| v.as_str()));
+    [
+        config.relay.api_key.as_str(),
+        config.translation.api_key.as_str(),
+    ]
+    .into_iter()
+    .chain(segment_keys)
+    .filter_map(|value| match SecretRef::parse(value) {
+        SecretRef::Env(name) => Some(name.to_string()),
+        _ => None,
+    })
+    .collect()
+}
+
+/// `~/...` relative to the home directory
+pub fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/").or(path.strip_prefix("~\\")) {
+        Some(rest) => dirs::home_dir()
+            .map(|home| home.join(rest))
+            .unwrap_or_else(|| PathBuf::from(path)),
+        None if path == "~" => dirs::home_dir().unwrap_or_else(|| PathBuf::from(path)),
+        None => PathBuf::from(path),
+    }
+}
+
+fn first_line(content: &str) -> String {
+    content.lines().next().unwrap_or_default().to_string()
+}
+
+/// Run a `cmd:` reference, reusing the output while it is fresh: from memory in the daemon,
+/// otherwise from a file only the current user can read in the runtime directory (see
+/// `utils::paths::runtime_dir`), so a password manager is not asked on every render
+fn resolve_command(command: &str) -> Option<String> {
+    static RESOLVED: OnceLock<Mutex<HashMap<String, (String, Instant)>>> = OnceLock::new();
+    let resolved = RESOLVED.get_or_init(Default::default);
+
+    if let Ok(resolved) = resolved.lock() {
+        if let Some((secret, at)) = resolved.get(command) {
+            if at.elapsed().as_secs() < COMMAND_CACHE_SECONDS {
+                return Some(secret.clone());
+            }
+        }
+    }
+
+    let cache_file = command_cache_file(command);
+    let secret = match cache_file.as_deref().and_then(read_command_cache) {
+        Some(secret) => secret,
+        None => {
+            let secret = run_command(command)?;
+            if let Some(cache_file) = &cache_file {
+                write_private(cache_file, &secret);
+            }
+            secret
+        }
+    };
+
+    if let Ok(mut resolved) = resolved.lock() {
+        resolved.insert(command.to_string(), (secret.clone(), Instant::now()));
+    }
+    Some(secret)
+}
+
+fn command_cache_file(command: &str) -> Option<PathBuf> {
+    let mut hasher = Sha256::new();
+    hasher.update(command.as_bytes());
+    let key = format!("{:x}", hasher.finalize());
+
+    let dir = crate::utils::paths::runtime_dir()?.join("secrets");
+    create_private_dir(&dir).ok()?;
+    Some(dir.join(&key[..16]))
+}
+
+/// Cached command output while it is younger than `COMMAND_CACHE_SECONDS`
+fn read_command_cache(path: &Path) -> Option<String> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    let age = SystemTime::now().duration_since(modified).ok()?;
+    if age.as_secs() >= COMMAND_CACHE_SECONDS {
+        let _ = fs::remove_file(path);
+        return None;
+    }
+    let secret = first_line(&fs::read_to_string(path).ok()?);
+    (!secret.is_empty()).then_some(secret)
+}
+
+fn run_command(command: &str) -> Option<String> {
+    use crate::utils::command;
+    let output = command::run_with_timeout(&mut command::shell(command), None, COMMAND_TIMEOUT)?;
+    let secret = first_line(&output.stdout).trim().to_string();
+    (output.status.success() && !secret.is_empty()).then_some(secret)
+}
+
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(dir)
+}
+
+/// Write a file only the current user can read; written to a temporary file first so a
+/// concurrent render never reads a partial secret
+fn write_private(path: &Path, content: &str) {
+    let tmp = path.with_extension(format!("tmp{}", std::process::id()));
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let written = options.open(&tmp).and_then(|mut file| {
+        use std::io::Write;
+        file.write_all(content.as_bytes())
+    });
+    if written.is_err() || fs::rename(&tmp, path).is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+}
+
+/// Whether any user can read `path`; a literal key in such a file is visible to everyone
+pub fn is_world_readable(path: &Path) -> bool {
+    permission_bits(path).is_some_and(|mode| mode & 0o004 != 0)
+}
+
+/// Whether group or other users have any access to `path`, like ssh checks private keys
+pub fn is_shared(path: &Path) -> bool {
+    permission_bits(path).is_some_and(|mode| mode & 0o077 != 0)
+}
+
+/// Unix permission bits; None elsewhere, where the checks above do not apply
+fn permission_bits(path: &Path) -> Option<u32> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::metadata(path).ok().map(|m| m.permissions().mode())
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_references_and_literals() {
+        assert_eq!(
+            SecretRef::parse(" env:RELAY_KEY "),
+            SecretRef::Env("RELAY_KEY")
+        );
+        assert_eq!(SecretRef::parse("file: ~/key"), SecretRef::File("~/key"));
+        assert_eq!(
+            SecretRef::parse("cmd:pass show relay"),
+            SecretRef::Command("pass show relay")
+        );
+        assert_eq!(SecretRef::parse("sk-abc"), SecretRef::Literal("sk-abc"));
+        assert!(SecretRef::parse("sk-abc").is_literal());
+        assert!(!SecretRef::parse("env:X").is_literal());
+    }
+
+    #[test]
+    fn rejects_references_without_a_target() {
+        assert!(SecretRef::parse("env:").validate().is_err());
+        assert!(SecretRef::parse("file: ").validate().is_err());
+        assert!(SecretRef::parse("cmd:").validate().is_err());
+        assert!(SecretRef::parse("env:KEY").validate().is_ok());
+        assert!(SecretRef::parse("").validate().is_ok());
+    }
+
+    #[test]
+    fn redacts_literal_keys_only() {
+        assert_eq!(redact(""), "");
+        assert_eq!(redact("short"), "****");
+        assert_eq!(redact("sk-1234567890abcd"), "****abcd");
+        assert_eq!(redact(" env:RELAY_KEY "), "env:RELAY_KEY");
+        assert_eq!(redact("cmd:pass show relay"), "cmd:pass show relay");
+    }
+
+    #[test]
+    fn expands_home_prefix_only() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(expand_home("~"), home);
+        assert_eq!(expand_home("~/keys/relay"), home.join("keys/relay"));
+        assert_eq!(expand_home("/etc/~/x"), PathBuf::from("/etc/~/x"));
+        assert_eq!(expand_home("~other/x"), PathBuf::from("~other/x"));
+    }
+
+    #[test]
+    fn command_cache_expires() {
+        let dir = std::env::temp_dir().join(format!("byebyecode-secrets-{}", std::process::id()));
+        create_private_dir(&dir).unwrap();
+        let file = dir.join("entry");
+
+        write_private(&file, "sk-cached\n");
+        assert_eq!(read_command_cache(&file).as_deref(), Some("sk-cached"));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&file).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let stale = SystemTime::now() - Duration::from_secs(COMMAND_CACHE_SECONDS + 1);
+        fs::File::options()
+            .write(true)
+            .open(&file)
+            .unwrap()
+            .set_modified(stale)
+            .unwrap();
+        assert!(read_command_cache(&file).is_none());
+        assert!(!file.exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,12 +1,179 @@
+use crate::translation::TranslationConfig;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 // Main config structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
+    /// Schema version, see `config::migrations`
+    #[serde(default)]
+    pub version: u32,
     pub style: StyleConfig,
     pub segments: Vec<SegmentConfig>,
     pub theme: String,
+    #[serde(default)]
+    pub translation: TranslationConfig,
+    #[serde(default, skip_serializing_if = "RelayConfig::is_empty")]
+    pub relay: RelayConfig,
+    #[serde(default, skip_serializing_if = "HttpConfig::is_default")]
+    pub http: HttpConfig,
+    #[serde(default, skip_serializing_if = "LayoutConfig::is_default")]
+    pub layout: LayoutConfig,
+    /// Directories whose `.byebyecode.toml` may run commands, use `cmd:`/`file:` secrets
+    /// or change where requests are sent; only read from the global config
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trusted_projects: Vec<String>,
+}
+
+/// Relay credentials shared by the byebyecode_* segments
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RelayConfig {
+    /// Used when a segment has no `api_key` option; falls back to Claude settings
+    /// May be a secret reference (`env:`, `file:`, `cmd:`), see `config::secrets`
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub api_key: String,
+}
+
+impl RelayConfig {
+    pub fn is_empty(&self) -> bool {
+        self.api_key.is_empty()
+    }
+}
+
+/// Network settings shared by every HTTP client (relay APIs, Anthropic usage, translation,
+/// update checks), see `utils::http`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HttpConfig {
+    /// Proxy URL; when empty, HTTPS_PROXY / HTTP_PROXY from the `env` of Claude Code's
+    /// settings.json, then from the environment. "off" disables proxies
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub proxy: String,
+    /// PEM files with extra root certificates (e.g. a corporate CA); `~` expands to home
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ca_certs: Vec<String>,
+    /// Seconds to wait for a connection
+    #[serde(default = "default_connect_timeout_secs")]
+    pub connect_timeout_secs: u64,
+    /// Seconds to wait for the response, and again for each read of its body
+    #[serde(default = "default_read_timeout_secs")]
+    pub read_timeout_secs: u64,
+    /// Seconds for a whole request; short because the statusline waits on cache misses
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_connect_timeout_secs() -> u64 {
+    2
+}
+
+fn default_read_timeout_secs() -> u64 {
+    2
+}
+
+fn default_timeout_secs() -> u64 {
+    3
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            proxy: String::new(),
+            ca_certs: Vec::new(),
+            connect_timeout_secs: default_connect_timeout_secs(),
+            read_timeout_secs: default_read_timeout_secs(),
+            timeout_secs: default_timeout_secs(),
+        }
+    }
+}
+
+impl HttpConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Statusline lines and width; the default is one line with every segment in config order
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LayoutConfig {
+    /// Target width in columns; 0 uses $COLUMNS, and no limit when that is unset too
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub width: usize,
+    /// Lines top to bottom; enabled segments not listed anywhere go to the end of the first line
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lines: Vec<LayoutLine>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LayoutLine {
+    #[serde(default)]
+    pub left: Vec<LayoutEntry>,
+    /// Right-aligned group; needs a known width, otherwise it follows the left group
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub right: Vec<LayoutEntry>,
+}
+
+/// A segment placed by the layout: its ID, or `custom:<name>` for one of several custom
+/// segments (the same key project overlays use); a bare `custom` takes the next one
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct LayoutEntry {
+    pub id: SegmentId,
+    pub name: Option<String>,
+}
+
+impl LayoutEntry {
+    pub fn matches(&self, segment: &SegmentConfig) -> bool {
+        segment.id == self.id
+            && self.name.as_deref().is_none_or(|name| {
+                segment.options.get("name").and_then(|v| v.as_str()) == Some(name)
+            })
+    }
+}
+
+impl From<SegmentId> for LayoutEntry {
+    fn from(id: SegmentId) -> Self {
+        Self { id, name: None }
+    }
+}
+
+impl TryFrom<String> for LayoutEntry {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let (id, name) = match value.split_once(':') {
+            Some(("custom", name)) if !name.trim().is_empty() => {
+                ("custom", Some(name.trim().to_string()))
+            }
+            Some(_) => return Err(format!("'{}': only custom segments take a :name", value)),
+            None => (value.as_str(), None),
+        };
+        let id = serde_json::from_value(serde_json::Value::String(id.to_string()))
+            .map_err(|_| format!("unknown segment '{}' in layout", id))?;
+        Ok(Self { id, name })
+    }
+}
+
+impl From<LayoutEntry> for String {
+    fn from(entry: LayoutEntry) -> Self {
+        let id = serde_json::to_value(entry.id)
+            .ok()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default();
+        match entry.name {
+            Some(name) => format!("{}:{}", id, name),
+            None => id,
+        }
+    }
+}
+
+fn is_zero(value: &usize) -> bool {
+    *value == 0
+}
+
+impl LayoutConfig {
+    pub fn is_default(&self) -> bool {
+        self.width == 0 && self.lines.is_empty()
+    }
 }
 
 // Default implementation moved to ui/themes/presets.rs
@@ -76,6 +243,9 @@ pub enum SegmentId {
     ByeByeCodeUsage,
     ByeByeCodeSubscription,
     ByeByeCodeStatus,
+    BurnRate,
+    /// User command; may appear several times, told apart by `options.name`
+    Custom,
 }
 
 // Legacy compatibility structure
@@ -97,6 +267,7 @@ pub struct Model {
 #[derive(Deserialize)]
 pub struct Workspace {
     pub current_dir: String,
+    pub project_dir: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -115,11 +286,19 @@ pub struct OutputStyle {
 
 #[derive(Deserialize)]
 pub struct InputData {
+    pub session_id: Option<String>,
     pub model: Model,
     pub workspace: Workspace,
     pub transcript_path: String,
     pub cost: Option<Cost>,
     pub output_style: Option<OutputStyle>,
+    /// Raw JSON as received on stdin, passed on to custom command segments
+    #[serde(skip)]
+    pub raw_json: String,
+    /// Environment of the statusline process when the daemon renders for it; custom
+    /// commands run with it instead of the daemon's own environment
+    #[serde(skip)]
+    pub env: Option<HashMap<String, String>>,
 }
 
 // OpenAI-style nested token details
@@ -403,17 +582,56 @@ pub type Usage = RawUsage;
 
 #[derive(Deserialize)]
 pub struct Message {
+    pub id: Option<String>,
+    pub model: Option<String>,
     pub usage: Option<Usage>,
+    pub content: Option<MessageContent>,
+}
+
+/// Message content; only the block types are kept, enough to tell prompts from tool results
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum MessageContent {
+    Blocks(Vec<ContentBlock>),
+    Other(serde::de::IgnoredAny),
+}
+
+#[derive(Deserialize)]
+pub struct ContentBlock {
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
 }
 
 #[derive(Deserialize)]
 pub struct TranscriptEntry {
     pub r#type: Option<String>,
     pub message: Option<Message>,
+    #[serde(rename = "sessionId")]
+    pub session_id: Option<String>,
+    pub cwd: Option<String>,
+    pub timestamp: Option<String>,
     #[serde(rename = "leafUuid")]
     pub leaf_uuid: Option<String>,
     pub uuid: Option<String>,
     #[serde(rename = "parentUuid")]
     pub parent_uuid: Option<String>,
     pub summary: Option<String>,
+    /// Entries of subagent conversations, which have their own context
+    #[serde(rename = "isSidechain", default)]
+    pub is_sidechain: bool,
+}
+
+impl TranscriptEntry {
+    /// A prompt typed by the user, as opposed to tool results that are logged as user entries
+    pub fn is_user_prompt(&self) -> bool {
+        if self.r#type.as_deref() != Some("user") || self.is_sidechain {
+            return false;
+        }
+        match self.message.as_ref().and_then(|m| m.content.as_ref()) {
+            Some(MessageContent::Blocks(blocks)) => !blocks
+                .iter()
+                .any(|b| b.kind.as_deref() == Some("tool_result")),
+            _ => true,
+        }
+    }
 }
@@ -0,0 +1,622 @@
+use super::migrations;
+use super::schema::{suggest, OptionKind};
+use super::secrets::{self, SecretRef};
+use super::SegmentId;
+use crate::ui::themes::ThemePresets;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use toml_edit::{ImDocument, Item, TableLike};
+
+const BUILTIN_THEMES: &[&str] = &[
+    "cometix",
+    "default",
+    "minimal",
+    "gruvbox",
+    "nord",
+    "powerline-dark",
+    "powerline-light",
+    "powerline-rose-pine",
+    "powerline-tokyo-night",
+];
+
+const TOP_LEVEL_KEYS: &[&str] = &[
+    "version",
+    "style",
+    "segments",
+    "theme",
+    "translation",
+    "relay",
+    "http",
+    "layout",
+    "trusted_projects",
+];
+const SEGMENT_KEYS: &[&str] = &["id", "enabled", "icon", "colors", "styles", "options"];
+const COLOR_KEYS: &[&str] = &["icon", "text", "background"];
+const HTTP_KEYS: &[&str] = &[
+    "proxy",
+    "ca_certs",
+    "connect_timeout_secs",
+    "read_timeout_secs",
+    "timeout_secs",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// One problem found in a config file
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub file: PathBuf,
+    /// 1-based line, when the location is known
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        match self.line {
+            Some(line) => write!(
+                f,
+                "{}:{}: {}: {}",
+                self.file.display(),
+                line,
+                severity,
+                self.message
+            ),
+            None => write!(f, "{}: {}: {}", self.file.display(), severity, self.message),
+        }
+    }
+}
+
+struct Validator<'a> {
+    file: &'a Path,
+    content: &'a str,
+    diagnostics: Vec<Diagnostic>,
+    partial: bool,
+    /// Other users can read the file, so literal API keys in it are exposed
+    world_readable: bool,
+}
+
+impl Validator<'_> {
+    fn line_of(&self, span: Option<Range<usize>>) -> Option<usize> {
+        let start = span?.start.min(self.content.len());
+        Some(self.content[..start].matches('\n').count() + 1)
+    }
+
+    fn report(&mut self, severity: Severity, span: Option<Range<usize>>, message: String) {
+        self.diagnostics.push(Diagnostic {
+            severity,
+            file: self.file.to_path_buf(),
+            line: self.line_of(span),
+            message,
+        });
+    }
+
+    /// Report keys of `table` that are not in `known`
+    fn check_keys(&mut self, table: &dyn TableLike, known: &[&str], context: &str) {
+        for (key, _) in table.iter() {
+            if known.contains(&key) {
+                continue;
+            }
+            let span = table.get_key_value(key).and_then(|(k, _)| k.span());
+            let hint = suggest(key, known.iter().copied())
+                .map(|s| format!(" (did you mean '{}'?)", s))
+                .unwrap_or_default();
+            self.report(
+                Severity::Warning,
+                span,
+                format!("{}: unknown key '{}'{}", context, key, hint),
+            );
+        }
+    }
+
+    fn check_theme(&mut self, table: &dyn TableLike) {
+        let Some((key, item)) = table.get_key_value("theme") else {
+            return;
+        };
+        let span = key.span();
+        let Some(theme) = item.as_str() else {
+            self.report(Severity::Error, span, "theme: expected string".to_string());
+            return;
+        };
+
+        let theme_path = ThemePresets::get_themes_path().join(format!("{}.toml", theme));
+        if theme_path.exists() {
+            if let Err(e) = ThemePresets::load_theme_from_file(theme) {
+                self.report(
+                    Severity::Error,
+                    span,
+                    format!(
+                        "theme '{}': {} is invalid: {}",
+                        theme,
+                        theme_path.display(),
+                        e
+                    ),
+                );
+            }
+        } else if !BUILTIN_THEMES.contains(&theme) {
+            self.report(
+                Severity::Error,
+                span,
+                format!(
+                    "theme '{}' not found: no built-in theme and no {}",
+                    theme,
+                    theme_path.display()
+                ),
+            );
+        }
+    }
+
+    fn check_color(&mut self, item: &Item, span: Option<Range<usize>>, context: &str) {
+        let Some(color) = item.as_table_like() else {
+            self.report(
+                Severity::Error,
+                span,
+                format!(
+                    "{}: expected a color table ({{ c16 }}, {{ c256 }} or {{ r, g, b }})",
+                    context
+                ),
+            );
+            return;
+        };
+
+        let value = |key: &str| color.get(key).and_then(|v| v.as_integer());
+        let mut keys: Vec<&str> = color.iter().map(|(k, _)| k).collect();
+        keys.sort_unstable();
+
+        let result = match keys.as_slice() {
+            ["c16"] => match value("c16") {
+                Some(0..=15) => Ok(()),
+                _ => Err("c16 must be an integer from 0 to 15".to_string()),
+            },
+            ["c256"] => match value("c256") {
+                Some(0..=255) => Ok(()),
+                _ => Err("c256 must be an integer from 0 to 255".to_string()),
+            },
+            ["b", "g", "r"] => {
+                if ["r", "g", "b"]
+                    .iter()
+                    .all(|k| matches!(value(k), Some(0..=255)))
+                {
+                    Ok(())
+                } else {
+                    Err("r, g and b must be integers from 0 to 255".to_string())
+                }
+            }
+            _ => Err(format!(
+                "expected exactly one of {{ c16 }}, {{ c256 }} or {{ r, g, b }}, found {{ {} }}",
+                keys.join(", ")
+            )),
+        };
+
+        if let Err(message) = result {
+            self.report(Severity::Error, span, format!("{}: {}", context, message));
+        }
+    }
+
+    /// Warn about API keys that other users can read
+    fn check_secret(&mut self, value: &str, span: Option<Range<usize>>, context: &str) {
+        match SecretRef::parse(value) {
+            SecretRef::Literal(key) if !key.is_empty() && self.world_readable => self.report(
+                Severity::Warning,
+                span,
+                format!(
+                    "{}: literal API key in a world-readable file; \
+                     use an env:, file: or cmd: reference, or chmod 600 this file",
+                    context
+                ),
+            ),
+            SecretRef::File(path) if !path.is_empty() => {
+                let path = secrets::expand_home(path);
+                if !path.is_file() {
+                    self.report(
+                        Severity::Warning,
+                        span,
+                        format!("{}: secret file {} not found", context, path.display()),
+                    );
+                } else if secrets::is_shared(&path) {
+                    self.report(
+                        Severity::Warning,
+                        span,
+                        format!(
+                            "{}: secret file {} is accessible by other users; chmod 600 it",
+                            context,
+                            path.display()
+                        ),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Proxy URL and CA certificate files of the `[http]` table
+    fn check_http(&mut self, http: &dyn TableLike) {
+        self.check_keys(http, HTTP_KEYS, "http");
+
+        if let Some((key, Some(proxy))) = http
+            .get_key_value("proxy")
+            .map(|(k, v)| (k, v.as_str().map(str::trim)))
+        {
+            if !proxy.is_empty() && proxy != "off" {
+                if let Err(e) = reqwest::Proxy::all(proxy) {
+                    self.report(
+                        Severity::Error,
+                        key.span(),
+                        format!("http.proxy: invalid proxy URL {}: {}", proxy, e),
+                    );
+                }
+            }
+        }
+
+        if let Some(Item::Value(toml_edit::Value::Array(certs))) = http.get("ca_certs") {
+            for cert in certs.iter() {
+                let Some(path) = cert.as_str() else { continue };
+                let file = secrets::expand_home(path);
+                let message = match fs::read(&file) {
+                    Err(_) => format!("{} not found", file.display()),
+                    Ok(pem) => match reqwest::Certificate::from_pem_bundle(&pem) {
+                        Ok(certs) if !certs.is_empty() => continue,
+                        _ => format!("no PEM certificates in {}", file.display()),
+                    },
+                };
+                self.report(
+                    Severity::Error,
+                    cert.span(),
+                    format!("http.ca_certs: {}", message),
+                );
+            }
+        }
+    }
+
+    fn check_options(&mut self, id: SegmentId, options: &dyn TableLike, context: &str) {
+        let schema = id.option_schema();
+        for (key, item) in options.iter() {
+            let span = options.get_key_value(key).and_then(|(k, _)| k.span());
+            match id.option_spec(key) {
+                Some(spec) => {
+                    if let Err(message) = spec.kind.validate(&to_json(item)) {
+                        self.report(
+                            Severity::Error,
+                            span,
+                            format!("{}.options.{}: {}", context, key, message),
+                        );
+                    } else if let (OptionKind::Secret, Some(value)) = (spec.kind, item.as_str()) {
+                        self.check_secret(value, span, &format!("{}.options.{}", context, key));
+                    }
+                }
+                None => {
+                    let hint = suggest(key, schema.iter().map(|s| s.key))
+                        .map(|s| format!(" (did you mean '{}'?)", s))
+                        .unwrap_or_default();
+                    let known = if schema.is_empty() {
+                        " (this segment has no options)".to_string()
+                    } else {
+                        String::new()
+                    };
+                    self.report(
+                        Severity::Warning,
+                        span,
+                        format!(
+                            "{}.options: unknown option '{}'{}{}",
+                            context, key, hint, known
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_segment(&mut self, segment: &dyn TableLike, seen: &mut HashSet<String>) {
+        let id_entry = segment.get_key_value("id");
+        let id_span = id_entry.and_then(|(k, _)| k.span());
+        let Some(raw_id) = id_entry.and_then(|(_, v)| v.as_str()) else {
+            self.report(
+                Severity::Error,
+                id_span.clone(),
+                "segment without an id".to_string(),
+            );
+            return;
+        };
+
+        let name = migrations::renamed_segment_id(raw_id).unwrap_or(raw_id);
+        let Some(id) = SegmentId::from_config_name(name) else {
+            let names: Vec<String> = SegmentId::ALL.iter().map(|id| id.config_name()).collect();
+            let hint = suggest(raw_id, names.iter().map(String::as_str))
+                .map(|s| format!(" (did you mean '{}'?)", s))
+                .unwrap_or_default();
+            self.report(
+                Severity::Error,
+                id_span.clone(),
+                format!("unknown segment id '{}'{}", raw_id, hint),
+            );
+            return;
+        };
+        if name != raw_id {
+            self.report(
+                Severity::Warning,
+                id_span.clone(),
+                format!(
+                    "legacy segment id '{}' is migrated to '{}' on load",
+                    raw_id, name
+                ),
+            );
+        }
+        let options = segment.get("options").and_then(|o| o.as_table_like());
+        let context = if id == SegmentId::Custom {
+            let custom_name = options
+                .and_then(|o| o.get("name"))
+                .and_then(|v| v.as_str())
+                .unwrap_or_default();
+            if !seen.insert(format!("custom:{}", custom_name)) {
+                self.report(
+                    Severity::Error,
+                    id_span.clone(),
+                    format!(
+                        "duplicate custom segment name '{}' (set a unique options.name)",
+                        custom_name
+                    ),
+                );
+            }
+            // Overlays may only override options of a custom segment from the global config
+            let has_command = options
+                .and_then(|o| o.get("command"))
+                .and_then(|v| v.as_str())
+                .is_some_and(|c| !c.trim().is_empty());
+            if !has_command && !self.partial {
+                self.report(
+                    Severity::Error,
+                    id_span,
+                    format!("custom segment '{}' needs options.command", custom_name),
+                );
+            }
+            format!("segments[custom:{}]", custom_name)
+        } else {
+            if !seen.insert(name.to_string()) {
+                self.report(
+                    Severity::Error,
+                    id_span,
+                    format!("duplicate segment id '{}'", name),
+                );
+            }
+            format!("segments[{}]", name)
+        };
+        self.check_keys(segment, SEGMENT_KEYS, &context);
+
+        if let Some(colors) = segment.get("colors").and_then(|c| c.as_table_like()) {
+            self.check_keys(colors, COLOR_KEYS, &format!("{}.colors", context));
+            for key in COLOR_KEYS {
+                if let Some((k, item)) = colors.get_key_value(key) {
+                    self.check_color(item, k.span(), &format!("{}.colors.{}", context, key));
+                }
+            }
+        }
+
+        if let Some(options) = options {
+            self.check_options(id, options, &context);
+        }
+    }
+}
+
+/// Convert a parsed TOML item to the JSON form used for segment options
+fn to_json(item: &Item) -> Value {
+    match item {
+        Item::Value(value) => value_to_json(value),
+        Item::Table(table) => Value::Object(
+            table
+                .iter()
+                .map(|(k, v)| (k.to_string(), to_json(v)))
+                .collect(),
+        ),
+        Item::ArrayOfTables(tables) => Value::Array(
+            tables
+                .iter()
+                .map(|t| to_json(&Item::Table(t.clone())))
+                .collect(),
+        ),
+        Item::None => Value::Null,
+    }
+}
+
+fn value_to_json(value: &toml_edit::Value) -> Value {
+    match value {
+        toml_edit::Value::String(s) => Value::String(s.value().clone()),
+        toml_edit::Value::Integer(i) => Value::from(*i.value()),
+        toml_edit::Value::Float(f) => Value::from(*f.value()),
+        toml_edit::Value::Boolean(b) => Value::Bool(*b.value()),
+        toml_edit::Value::Datetime(d) => Value::String(d.value().to_string()),
+        toml_edit::Value::Array(a) => Value::Array(a.iter().map(value_to_json).collect()),
+        toml_edit::Value::InlineTable(t) => Value::Object(
+            t.iter()
+                .map(|(k, v)| (k.to_string(), value_to_json(v)))
+                .collect(),
+        ),
+    }
+}
+
+/// Validate a config file (global config, or a project overlay when `partial`)
+///
+/// Project overlays only contain the keys they override, so required keys are not checked.
+pub fn validate_file(path: &Path, partial: bool) -> Vec<Diagnostic> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => {
+            return vec![Diagnostic {
+                severity: Severity::Error,
+                file: path.to_path_buf(),
+                line: None,
+                message: format!("cannot read file: {}", e),
+            }]
+        }
+    };
+
+    let mut validator = Validator {
+        file: path,
+        content: &content,
+        diagnostics: Vec::new(),
+        partial,
+        world_readable: secrets::is_world_readable(path),
+    };
+
+    let document = match ImDocument::parse(content.as_str()) {
+        Ok(document) => document,
+        Err(e) => {
+            let message = e.message().to_string();
+            validator.report(Severity::Error, e.span(), message);
+            return validator.diagnostics;
+        }
+    };
+    let root = document.as_table();
+
+    validator.check_keys(root, TOP_LEVEL_KEYS, "config");
+    validator.check_theme(root);
+    for table in ["relay", "translation"] {
+        let api_key = root
+            .get(table)
+            .and_then(|t| t.as_table_like())
+            .and_then(|t| t.get_key_value("api_key"));
+        if let Some((key, Some(value))) = api_key.map(|(k, v)| (k, v.as_str())) {
+            validator.check_secret(value, key.span(), &format!("{}.api_key", table));
+        }
+    }
+
+    if let Some(http) = root.get("http").and_then(|t| t.as_table_like()) {
+        validator.check_http(http);
+    }
+
+    let mut seen = HashSet::new();
+    match root.get("segments") {
+        Some(Item::ArrayOfTables(segments)) => {
+            for segment in segments.iter() {
+                validator.check_segment(segment, &mut seen);
+            }
+        }
+        Some(Item::Value(toml_edit::Value::Array(segments))) => {
+            for segment in segments.iter() {
+                match segment.as_inline_table() {
+                    Some(segment) => validator.check_segment(segment, &mut seen),
+                    None => validator.report(
+                        Severity::Error,
+                        segment.span(),
+                        "segments: expected tables".to_string(),
+                    ),
+                }
+            }
+        }
+        Some(item) => validator.report(
+            Severity::Error,
+            item.span(),
+            "segments: expected [[segments]] tables".to_string(),
+        ),
+        None if !partial => {
+            validator.report(Severity::Error, None, "no segments configured".to_string())
+        }
+        None => {}
+    }
+
+    // Anything the structural checks missed (missing required keys, wrong types)
+    if !partial
+        && validator
+            .diagnostics
+            .iter()
+            .all(|d| d.severity != Severity::Error)
+    {
+        let result = toml::from_str::<toml::Table>(&content)
+            .map_err(|e| e.to_string())
+            .and_then(|mut table| {
+                migrations::migrate(&mut table).map_err(|e| e.to_string())?;
+                toml::Value::Table(table)
+                    .try_into::<super::Config>()
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            });
+        if let Err(message) = result {
+            validator.report(Severity::Error, None, message.trim().to_string());
+        }
+    }
+
+    validator
+        .diagnostics
+        .sort_by_key(|d| d.line.unwrap_or(usize::MAX));
+    validator.diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(name: &str, content: &str) -> Vec<Diagnostic> {
+        let dir = std::env::temp_dir().join(format!("byebyecode-validate-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, content).unwrap();
+        let diagnostics = validate_file(&path, true);
+        let _ = fs::remove_file(&path);
+        diagnostics
+    }
+
+    #[test]
+    fn diagnostics_point_at_the_offending_line() {
+        let diagnostics = check(
+            "lines.toml",
+            r#"theme = "nord"
+thme = "x"
+
+[[segments]]
+id = "git"
+
+[segments.options]
+show_shaa = true
+show_tag = "yes"
+priority = 2
+"#,
+        );
+        let lines: Vec<(Option<usize>, Severity, &str)> = diagnostics
+            .iter()
+            .map(|d| (d.line, d.severity, d.message.as_str()))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                (
+                    Some(2),
+                    Severity::Warning,
+                    "config: unknown key 'thme' (did you mean 'theme'?)"
+                ),
+                (
+                    Some(8),
+                    Severity::Warning,
+                    "segments[git].options: unknown option 'show_shaa' (did you mean 'show_sha'?)"
+                ),
+                (
+                    Some(9),
+                    Severity::Error,
+                    "segments[git].options.show_tag: expected bool, found string"
+                ),
+            ]
+        );
+        assert!(diagnostics[0].to_string().ends_with(
+            "lines.toml:2: warning: config: unknown key 'thme' (did you mean 'theme'?)"
+        ));
+    }
+
+    #[test]
+    fn syntax_errors_report_their_line() {
+        let diagnostics = check("syntax.toml", "theme = \"nord\"\n[style\nmode = 1\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[0].line, Some(2));
+    }
+}
@@ -0,0 +1,505 @@
+use super::ledger;
+use super::segments::git::find_git_dir;
+use super::segments::SegmentData;
+use super::statusline::{collect_segment, StatusLineGenerator};
+use crate::config::layers::find_project_overlay;
+use crate::config::{secrets, Config, ConfigLoader, InputData, SegmentConfig, SegmentId};
+use crate::ui::themes::ThemePresets;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{Read, Write};
+use std::net::Shutdown;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Git data is reused while .git metadata is unchanged, but at most this long,
+/// because editing a tracked file does not touch the index until git refreshes it
+const GIT_CACHE_MAX_AGE: Duration = Duration::from_secs(5);
+
+/// Cached git entries unused for this long are dropped
+const GIT_CACHE_EVICT_AGE: Duration = Duration::from_secs(600);
+
+/// Files in the git directory whose modification marks a branch, commit, index,
+/// stash or in-progress operation change
+const GIT_WATCHED_FILES: &[&str] = &[
+    "HEAD",
+    "index",
+    "logs/HEAD",
+    "FETCH_HEAD",
+    "packed-refs",
+    "logs/refs/stash",
+    "MERGE_HEAD",
+    "CHERRY_PICK_HEAD",
+    "REVERT_HEAD",
+    "BISECT_LOG",
+    "rebase-merge",
+    "rebase-apply",
+];
+
+/// Environment variables that decide which files are read and how requests are sent;
+/// the daemon only renders for clients whose values match its own
+const SHARED_ENV: &[&str] = &[
+    "HOME",
+    "CLAUDE_CONFIG_DIR",
+    "BYEBYECODE_HOME",
+    "XDG_CONFIG_HOME",
+    "XDG_CACHE_HOME",
+    "XDG_RUNTIME_DIR",
+    "HTTP_PROXY",
+    "HTTPS_PROXY",
+    "ALL_PROXY",
+    "NO_PROXY",
+    "http_proxy",
+    "https_proxy",
+    "all_proxy",
+    "no_proxy",
+];
+
+/// How long the daemon waits for a client to send its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long the client waits for a rendered statusline before falling back.
+/// Generous because usage segments may still hit the network on a cache miss.
+const RENDER_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Request {
+    Render {
+        theme: Option<String>,
+        /// Raw statusline JSON as received from Claude Code on stdin
+        input: String,
+        /// Client terminal width ($COLUMNS), used for the layout
+        #[serde(default)]
+        columns: Option<usize>,
+        /// Client environment, see `SHARED_ENV`; custom commands run with it
+        #[serde(default)]
+        env: Option<HashMap<String, String>>,
+    },
+    Ping,
+    Stop,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Response {
+    Statusline {
+        text: String,
+    },
+    Pong {
+        pid: u32,
+        uptime_secs: u64,
+        requests: u64,
+        cached_repos: usize,
+    },
+    Stopping,
+    Error {
+        message: String,
+    },
+}
+
+/// Daemon socket path (`daemon.sock` in the byebyecode home)
+pub fn socket_path() -> Option<PathBuf> {
+    let dir = crate::utils::paths::byebyecode_home()?;
+    fs::create_dir_all(&dir).ok()?;
+    Some(dir.join("daemon.sock"))
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Modification times of the watched git files; `None` outside a repository
+fn git_fingerprint(working_dir: &str) -> Option<Vec<Option<SystemTime>>> {
+    let git_dir = find_git_dir(Path::new(working_dir))?;
+    Some(
+        GIT_WATCHED_FILES
+            .iter()
+            .map(|file| modified(&git_dir.join(file)))
+            .collect(),
+    )
+}
+
+struct CachedConfig {
+    stamps: Vec<Option<SystemTime>>,
+    config: Config,
+}
+
+struct CachedGit {
+    fingerprint: Option<Vec<Option<SystemTime>>>,
+    fetched_at: Instant,
+    data: Option<SegmentData>,
+}
+
+/// State shared by all daemon connections
+struct DaemonState {
+    started: Instant,
+    requests: u64,
+    /// Loaded configuration per theme override and project overlay, reloaded when a file changes
+    configs: HashMap<(Option<String>, Option<PathBuf>), CachedConfig>,
+    /// Git segment data keyed by working directory and segment options
+    git: HashMap<String, CachedGit>,
+}
+
+impl DaemonState {
+    fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            requests: 0,
+            configs: HashMap::new(),
+            git: HashMap::new(),
+        }
+    }
+
+    fn config(
+        &mut self,
+        theme: Option<&str>,
+        working_dir: &str,
+    ) -> Result<Config, Box<dyn std::error::Error>> {
+        // A theme override replaces the configuration, so overlays only apply without one
+        let overlay = match theme {
+            Some(_) => None,
+            None => find_project_overlay(Path::new(working_dir)),
+        };
+
+        let mut stamps = vec![modified(&Config::get_config_path())];
+        if let Some(theme) = theme {
+            stamps.push(modified(
+                &ConfigLoader::get_themes_path().join(format!("{}.toml", theme)),
+            ));
+        }
+        if let Some(overlay) = &overlay {
+            stamps.push(modified(overlay));
+        }
+
+        let key = (theme.map(str::to_string), overlay);
+        if let Some(cached) = self.configs.get(&key) {
+            if cached.stamps == stamps {
+                return Ok(cached.config.clone());
+            }
+        }
+
+        // Same rules as the in-process path in main; load errors are returned so the
+        // client renders in-process and reports them
+        let config = match theme {
+            Some(theme) => ThemePresets::apply_theme(&Config::load()?, theme),
+            None => Config::load_for_workspace(Path::new(working_dir))?,
+        };
+        self.configs.insert(
+            key,
+            CachedConfig {
+                stamps,
+                config: config.clone(),
+            },
+        );
+        Ok(config)
+    }
+
+    fn cached_git(
+        &self,
+        key: &str,
+        fingerprint: &Option<Vec<Option<SystemTime>>>,
+    ) -> Option<Option<SegmentData>> {
+        let cached = self.git.get(key)?;
+        (cached.fingerprint == *fingerprint && cached.fetched_at.elapsed() < GIT_CACHE_MAX_AGE)
+            .then(|| cached.data.clone())
+    }
+
+    fn store_git(
+        &mut self,
+        key: String,
+        fingerprint: Option<Vec<Option<SystemTime>>>,
+        data: Option<SegmentData>,
+    ) {
+        self.git
+            .retain(|_, cached| cached.fetched_at.elapsed() < GIT_CACHE_EVICT_AGE);
+        self.git.insert(
+            key,
+            CachedGit {
+                fingerprint,
+                fetched_at: Instant::now(),
+                data,
+            },
+        );
+    }
+}
+
+fn lock(state: &Mutex<DaemonState>) -> std::sync::MutexGuard<'_, DaemonState> {
+    // A panicking request must not take the daemon down with it
+    state.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Git segment data from the cache, re-running git only when the repository changed
+fn collect_git(
+    state: &Mutex<DaemonState>,
+    config: &Config,
+    segment_config: &SegmentConfig,
+    input: &InputData,
+) -> Option<SegmentData> {
+    let working_dir = &input.workspace.current_dir;
+    let options: BTreeMap<_, _> = segment_config.options.iter().collect();
+    let key = format!(
+        "{}\0{}",
+        working_dir,
+        serde_json::to_string(&options).unwrap_or_default()
+    );
+    let fingerprint = git_fingerprint(working_dir);
+
+    if let Some(data) = lock(state).cached_git(&key, &fingerprint) {
+        return data;
+    }
+
+    // Run git without holding the lock so other sessions are not blocked
+    let data = collect_segment(config, segment_config, input);
+    lock(state).store_git(key, fingerprint, data.clone());
+    data
+}
+
+/// First of `names` whose value in the client environment differs from the daemon's
+fn env_mismatch<'a>(
+    client: &HashMap<String, String>,
+    names: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    names
+        .into_iter()
+        .find(|name| client.get(*name).cloned() != std::env::var(name).ok())
+}
+
+fn render(
+    state: &Mutex<DaemonState>,
+    theme: Option<&str>,
+    input: &str,
+    columns: Option<usize>,
+    env: Option<HashMap<String, String>>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    // Paths, proxies and env: secrets would otherwise come from the daemon's environment;
+    // the error makes the client render in-process instead
+    if let Some(name) = env
+        .as_ref()
+        .and_then(|env| env_mismatch(env, SHARED_ENV.iter().copied()))
+    {
+        return Err(format!("client environment differs from the daemon's: {}", name).into());
+    }
+
+    let raw_json = input;
+    let mut input: InputData = serde_json::from_str(raw_json)?;
+    input.raw_json = raw_json.to_string();
+    let config = {
+        let mut state = lock(state);
+        state.requests += 1;
+        state.config(theme, &input.workspace.current_dir)?
+    };
+
+    if let Some(env) = &env {
+        let references = secrets::env_references(&config);
+        if let Some(name) = env_mismatch(env, references.iter().map(String::as_str)) {
+            return Err(format!("client environment differs from the daemon's: {}", name).into());
+        }
+    }
+    input.env = env;
+
+    // Update the local cost ledger before segments read it
+    if ledger::is_used_by(&config) {
+        let _ = ledger::record_session(&input);
+    }
+
+    let segments_data = config
+        .segments
+        .iter()
+        .filter_map(|segment_config| {
+            // Only git data is cached here; other segments, including the usage and
+            // subscription ones, are collected as in-process and rely on their own disk cache
+            let data = if segment_config.id == SegmentId::Git {
+                collect_git(state, &config, segment_config, &input)
+            } else {
+                collect_segment(&config, segment_config, &input)
+            };
+            data.map(|data| (segment_config.clone(), data))
+        })
+        .collect();
+
+    Ok(StatusLineGenerator::new(config)
+        .with_columns(columns)
+        .generate(segments_data))
+}
+
+fn write_response(stream: &mut UnixStream, response: &Response) {
+    if let Ok(json) = serde_json::to_string(response) {
+        let _ = stream.write_all(json.as_bytes());
+    }
+}
+
+fn read_request(stream: &mut UnixStream) -> Result<Request, Box<dyn std::error::Error>> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut buf = String::new();
+    stream.read_to_string(&mut buf)?;
+    Ok(serde_json::from_str(&buf)?)
+}
+
+fn handle_connection(
+    mut stream: UnixStream,
+    state: &Mutex<DaemonState>,
+    stopping: &AtomicBool,
+    path: &Path,
+) {
+    let request = match read_request(&mut stream) {
+        Ok(request) => request,
+        Err(e) => {
+            write_response(
+                &mut stream,
+                &Response::Error {
+                    message: e.to_string(),
+                },
+            );
+            return;
+        }
+    };
+
+    match request {
+        Request::Render {
+            theme,
+            input,
+            columns,
+            env,
+        } => {
+            let response = match render(state, theme.as_deref(), &input, columns, env) {
+                Ok(text) => Response::Statusline { text },
+                Err(e) => Response::Error {
+                    message: e.to_string(),
+                },
+            };
+            write_response(&mut stream, &response);
+        }
+        Request::Ping => {
+            let response = {
+                let state = lock(state);
+                Response::Pong {
+                    pid: std::process::id(),
+                    uptime_secs: state.started.elapsed().as_secs(),
+                    requests: state.requests,
+                    cached_repos: state.git.len(),
+                }
+            };
+            write_response(&mut stream, &response);
+        }
+        Request::Stop => {
+            write_response(&mut stream, &Response::Stopping);
+            stopping.store(true, Ordering::SeqCst);
+            // Wake the accept loop so it sees the flag
+            let _ = UnixStream::connect(path);
+        }
+    }
+}
+
+/// `byebyecode daemon`: serve statusline requests on the Unix socket until stopped
+pub fn run_daemon() -> Result<(), Box<dyn std::error::Error>> {
+    let path = socket_path().ok_or("Cannot determine daemon socket path")?;
+
+    if path.exists() {
+        if UnixStream::connect(&path).is_ok() {
+            return Err(format!("Daemon already running at {}", path.display()).into());
+        }
+        // Left behind by a daemon that did not shut down cleanly
+        fs::remove_file(&path)?;
+    }
+
+    let listener = UnixListener::bind(&path)?;
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+    eprintln!("byebyecode daemon listening on {}", path.display());
+
+    let state = Arc::new(Mutex::new(DaemonState::new()));
+    let stopping = Arc::new(AtomicBool::new(false));
+
+    for stream in listener.incoming() {
+        if stopping.load(Ordering::SeqCst) {
+            break;
+        }
+        let Ok(stream) = stream else {
+            continue;
+        };
+
+        // One thread per connection, so a client that stalls while sending its request
+        // cannot hold up other sessions
+        let state = Arc::clone(&state);
+        let stopping = Arc::clone(&stopping);
+        let path = path.clone();
+        std::thread::spawn(move || handle_connection(stream, &state, &stopping, &path));
+    }
+
+    let _ = fs::remove_file(&path);
+    eprintln!("byebyecode daemon stopped");
+    Ok(())
+}
+
+fn send(request: &Request, timeout: Duration) -> Result<Response, Box<dyn std::error::Error>> {
+    let path = socket_path().ok_or("Cannot determine daemon socket path")?;
+    let mut stream = UnixStream::connect(path)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+
+    stream.write_all(serde_json::to_string(request)?.as_bytes())?;
+    stream.shutdown(Shutdown::Write)?;
+
+    let mut buf = String::new();
+    stream.read_to_string(&mut buf)?;
+    Ok(serde_json::from_str(&buf)?)
+}
+
+/// Render the statusline through a running daemon
+///
+/// Returns `None` when no daemon is running, it fails, its environment differs from ours
+/// (see `SHARED_ENV`) or `BYEBYECODE_NO_DAEMON` is set, so the caller falls back to
+/// rendering in-process.
+pub fn render_via_daemon(input: &str, theme: Option<&str>) -> Option<String> {
+    if std::env::var_os("BYEBYECODE_NO_DAEMON").is_some() {
+        return None;
+    }
+
+    let request = Request::Render {
+        theme: theme.map(str::to_string),
+        input: input.to_string(),
+        columns: std::env::var("COLUMNS")
+            .ok()
+            .and_then(|c| c.trim().parse().ok()),
+        env: Some(std::env::vars().collect()),
+    };
+    match send(&request, RENDER_TIMEOUT).ok()? {
+        Response::Statusline { text } => Some(text),
+        _ => None,
+    }
+}
+
+/// `byebyecode daemon --status`
+pub fn print_status() -> Result<(), Box<dyn std::error::Error>> {
+    match send(&Request::Ping, REQUEST_TIMEOUT) {
+        Ok(Response::Pong {
+            pid,
+            uptime_secs,
+            requests,
+            cached_repos,
+        }) => {
+            println!("Daemon running (pid {})", pid);
+            println!("  uptime:       {}s", uptime_secs);
+            println!("  requests:     {}", requests);
+            println!("  cached repos: {}", cached_repos);
+        }
+        Ok(_) => println!("Daemon returned an unexpected response"),
+        Err(_) => println!("Daemon not running"),
+    }
+    Ok(())
+}
+
+/// `byebyecode daemon --stop`
+pub fn stop_daemon() -> Result<(), Box<dyn std::error::Error>> {
+    match send(&Request::Stop, REQUEST_TIMEOUT) {
+        Ok(Response::Stopping) => println!("Daemon stopped"),
+        Ok(_) => println!("Daemon returned an unexpected response"),
+        Err(_) => println!("Daemon not running"),
+    }
+    Ok(())
+}
@@ -0,0 +1,432 @@
+use crate::config::{Config, InputData, ModelConfig, NormalizedUsage, SegmentId, TranscriptEntry};
+use crate::core::segments::cost::CostSource;
+use crate::core::transcript::{self, BillableUsage};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// Token counts by billing category
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TokenCounts {
+    pub input: u64,
+    pub output: u64,
+    pub cache_write: u64,
+    pub cache_read: u64,
+}
+
+impl TokenCounts {
+    pub(crate) fn from_usage(usage: &NormalizedUsage) -> Self {
+        Self {
+            input: usage.input_tokens as u64,
+            output: usage.output_tokens as u64,
+            cache_write: usage.cache_creation_input_tokens as u64,
+            cache_read: usage.cache_read_input_tokens as u64,
+        }
+    }
+
+    /// Field-wise maximum; streamed messages repeat their usage with growing output counts
+    pub(crate) fn max(self, other: Self) -> Self {
+        Self {
+            input: self.input.max(other.input),
+            output: self.output.max(other.output),
+            cache_write: self.cache_write.max(other.cache_write),
+            cache_read: self.cache_read.max(other.cache_read),
+        }
+    }
+
+    pub fn add(&mut self, other: &Self) {
+        self.input += other.input;
+        self.output += other.output;
+        self.cache_write += other.cache_write;
+        self.cache_read += other.cache_read;
+    }
+
+    pub fn total(&self) -> u64 {
+        self.input + self.output + self.cache_write + self.cache_read
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total() == 0
+    }
+}
+
+/// Accumulated usage and cost for one model
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModelCost {
+    pub tokens: TokenCounts,
+    pub cost_usd: f64,
+    pub messages: u64,
+    /// False when no price is known for this model; its cost is counted as 0
+    pub priced: bool,
+}
+
+impl ModelCost {
+    fn for_message(model: &str, tokens: TokenCounts, models: &ModelConfig) -> Self {
+        let pricing = models.get_pricing(model);
+        Self {
+            tokens,
+            cost_usd: pricing
+                .map(|p| {
+                    p.cost(
+                        tokens.input,
+                        tokens.output,
+                        tokens.cache_write,
+                        tokens.cache_read,
+                    )
+                })
+                .unwrap_or(0.0),
+            messages: 1,
+            priced: pricing.is_some(),
+        }
+    }
+
+    pub fn merge(&mut self, other: &ModelCost) {
+        self.priced = if self.messages == 0 {
+            other.priced
+        } else {
+            self.priced && other.priced
+        };
+        self.tokens.add(&other.tokens);
+        self.cost_usd += other.cost_usd;
+        self.messages += other.messages;
+    }
+}
+
+/// The most recent assistant message; kept open until a different message ID appears
+/// because Claude Code writes one transcript entry per content block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PendingMessage {
+    id: String,
+    model: String,
+    cost: ModelCost,
+}
+
+/// Per-session cost record (~/.claude/byebyecode/ledger/<session_id>.json)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionCost {
+    pub session_id: String,
+    pub project: String,
+    pub transcript_path: String,
+    #[serde(default)]
+    pub first_seen: Option<String>,
+    #[serde(default)]
+    pub last_seen: Option<String>,
+    #[serde(default)]
+    pub models: BTreeMap<String, ModelCost>,
+    /// Totals from transcript files the session used before `transcript_path`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    earlier_transcripts: BTreeMap<String, ModelCost>,
+    /// Bytes of the transcript already processed
+    #[serde(default)]
+    offset: u64,
+    #[serde(default)]
+    pending: Option<PendingMessage>,
+}
+
+impl SessionCost {
+    /// Per-model totals including the still-open last message
+    pub fn model_totals(&self) -> BTreeMap<String, ModelCost> {
+        let mut totals = self.models.clone();
+        for (model, cost) in &self.earlier_transcripts {
+            totals.entry(model.clone()).or_default().merge(cost);
+        }
+        if let Some(pending) = &self.pending {
+            totals
+                .entry(pending.model.clone())
+                .or_default()
+                .merge(&pending.cost);
+        }
+        totals
+    }
+
+    pub fn total_cost(&self) -> f64 {
+        self.model_totals().values().map(|m| m.cost_usd).sum()
+    }
+
+    pub fn total_tokens(&self) -> TokenCounts {
+        let mut tokens = TokenCounts::default();
+        for model in self.model_totals().values() {
+            tokens.add(&model.tokens);
+        }
+        tokens
+    }
+
+    /// Models seen in this session without a known price
+    pub fn unpriced_models(&self) -> Vec<String> {
+        self.model_totals()
+            .into_iter()
+            .filter(|(_, m)| !m.priced)
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    fn finalize_pending(&mut self) {
+        if let Some(pending) = self.pending.take() {
+            self.models
+                .entry(pending.model)
+                .or_default()
+                .merge(&pending.cost);
+        }
+    }
+
+    /// Continue the session in another transcript file, keeping what was already counted
+    fn switch_transcript(&mut self, transcript_path: &str) {
+        self.finalize_pending();
+        for (model, cost) in std::mem::take(&mut self.models) {
+            self.earlier_transcripts
+                .entry(model)
+                .or_default()
+                .merge(&cost);
+        }
+        self.transcript_path = transcript_path.to_string();
+        self.offset = 0;
+    }
+
+    fn apply_entry(&mut self, entry: &TranscriptEntry, models: &ModelConfig) {
+        if let Some(timestamp) = &entry.timestamp {
+            if self.first_seen.is_none() {
+                self.first_seen = Some(timestamp.clone());
+            }
+            self.last_seen = Some(timestamp.clone());
+        }
+
+        let Some(BillableUsage {
+            message_id,
+            model,
+            tokens,
+        }) = transcript::billable_usage(entry)
+        else {
+            return;
+        };
+
+        let id = message_id.unwrap_or_default();
+        match &mut self.pending {
+            Some(pending) if !id.is_empty() && pending.id == id => {
+                let tokens = pending.cost.tokens.max(tokens);
+                pending.cost = ModelCost::for_message(&pending.model, tokens, models);
+            }
+            _ => {
+                self.finalize_pending();
+                let cost = ModelCost::for_message(&model, tokens, models);
+                self.pending = Some(PendingMessage { id, model, cost });
+            }
+        }
+    }
+
+    /// Parse transcript lines appended since the last update
+    fn update_from_transcript(&mut self, models: &ModelConfig) -> std::io::Result<()> {
+        let mut file = fs::File::open(&self.transcript_path)?;
+        let len = file.metadata()?.len();
+
+        // Transcript was rewritten; start over
+        if len < self.offset {
+            *self = Self {
+                session_id: std::mem::take(&mut self.session_id),
+                project: std::mem::take(&mut self.project),
+                transcript_path: std::mem::take(&mut self.transcript_path),
+                earlier_transcripts: std::mem::take(&mut self.earlier_transcripts),
+                ..Self::default()
+            };
+        }
+        if len == self.offset {
+            return Ok(());
+        }
+
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut buf = Vec::new();
+        file.read_to_end(&mut buf)?;
+
+        // Only consume complete lines; a partially written line is picked up next time
+        let Some(last_newline) = buf.iter().rposition(|b| *b == b'\n') else {
+            return Ok(());
+        };
+        let complete = &buf[..=last_newline];
+
+        for entry in String::from_utf8_lossy(complete)
+            .lines()
+            .filter_map(transcript::parse_line)
+        {
+            self.apply_entry(&entry, models);
+        }
+
+        self.offset += complete.len() as u64;
+        Ok(())
+    }
+}
+
+/// Aggregated cost for one project across sessions
+#[derive(Debug, Clone, Default)]
+pub struct ProjectCost {
+    pub project: String,
+    pub sessions: usize,
+    pub models: BTreeMap<String, ModelCost>,
+    pub last_seen: Option<String>,
+}
+
+impl ProjectCost {
+    pub fn total_cost(&self) -> f64 {
+        self.models.values().map(|m| m.cost_usd).sum()
+    }
+}
+
+/// Get the ledger directory (`ledger/` in the byebyecode home)
+fn get_ledger_dir() -> Option<PathBuf> {
+    let dir = crate::utils::paths::byebyecode_home()?.join("ledger");
+    fs::create_dir_all(&dir).ok()?;
+    Some(dir)
+}
+
+fn session_file(session_id: &str) -> Option<PathBuf> {
+    let safe_id: String = session_id
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+        .collect();
+    if safe_id.is_empty() {
+        return None;
+    }
+    Some(get_ledger_dir()?.join(format!("{}.json", safe_id)))
+}
+
+/// Session ID from the statusline input, falling back to the transcript file name
+pub fn session_id_for(input: &InputData) -> Option<String> {
+    input
+        .session_id
+        .clone()
+        .filter(|s| !s.is_empty())
+        .or_else(|| {
+            Path::new(&input.transcript_path)
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+        })
+        .filter(|s| !s.is_empty())
+}
+
+/// Load a recorded session without updating it
+pub fn load_session(session_id: &str) -> Option<SessionCost> {
+    let content = fs::read_to_string(session_file(session_id)?).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Load every recorded session
+pub fn load_all_sessions() -> Vec<SessionCost> {
+    let Some(dir) = get_ledger_dir() else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|e| fs::read_to_string(e.path()).ok())
+        .filter_map(|content| serde_json::from_str(&content).ok())
+        .collect()
+}
+
+/// Aggregate sessions by project directory
+pub fn project_totals(sessions: &[SessionCost]) -> Vec<ProjectCost> {
+    let mut projects: BTreeMap<String, ProjectCost> = BTreeMap::new();
+
+    for session in sessions {
+        let project = projects
+            .entry(session.project.clone())
+            .or_insert_with(|| ProjectCost {
+                project: session.project.clone(),
+                ..ProjectCost::default()
+            });
+        project.sessions += 1;
+        for (model, cost) in session.model_totals() {
+            project.models.entry(model).or_default().merge(&cost);
+        }
+        if session.last_seen > project.last_seen {
+            project.last_seen = session.last_seen.clone();
+        }
+    }
+
+    projects.into_values().collect()
+}
+
+/// Whether an enabled cost segment shows the ledger, so renders need to keep it updated
+pub fn is_used_by(config: &Config) -> bool {
+    config.segments.iter().any(|segment| {
+        segment.enabled
+            && segment.id == SegmentId::Cost
+            && CostSource::of_segment(segment).uses_ledger()
+    })
+}
+
+/// Update the ledger for the current statusline session and return its record
+pub fn record_session(input: &InputData) -> Result<SessionCost, Box<dyn std::error::Error>> {
+    let session_id = session_id_for(input).ok_or("No session ID in statusline input")?;
+    let path = session_file(&session_id).ok_or("Ledger directory unavailable")?;
+
+    let mut session = load_session(&session_id).unwrap_or_else(|| SessionCost {
+        session_id: session_id.clone(),
+        ..SessionCost::default()
+    });
+
+    session.project = input
+        .workspace
+        .project_dir
+        .clone()
+        .unwrap_or_else(|| input.workspace.current_dir.clone());
+    if session.transcript_path != input.transcript_path {
+        // Resumed sessions may move to a new transcript file
+        session.switch_transcript(&input.transcript_path);
+    }
+
+    let before = session.offset;
+    session.update_from_transcript(&ModelConfig::load())?;
+
+    if session.offset != before || !path.exists() {
+        fs::write(&path, serde_json::to_string(&session)?)?;
+    }
+
+    Ok(session)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assistant_line(id: &str, output_tokens: u64) -> String {
+        format!(
+            r#"{{"type":"assistant","timestamp":"2026-01-01T00:00:00Z","message":{{"id":"{}","model":"claude-sonnet-4","usage":{{"input_tokens":10,"output_tokens":{}}}}}}}"#,
+            id, output_tokens
+        ) + "\n"
+    }
+
+    #[test]
+    fn switching_transcripts_keeps_earlier_totals() {
+        let dir = std::env::temp_dir().join(format!("byebyecode-ledger-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let first = dir.join("first.jsonl");
+        let second = dir.join("second.jsonl");
+        fs::write(
+            &first,
+            assistant_line("msg_1", 100) + &assistant_line("msg_2", 50),
+        )
+        .unwrap();
+        fs::write(&second, assistant_line("msg_3", 25)).unwrap();
+
+        let models = ModelConfig::default();
+        let mut session = SessionCost {
+            transcript_path: first.to_string_lossy().to_string(),
+            ..SessionCost::default()
+        };
+        session.update_from_transcript(&models).unwrap();
+        let before = session.total_cost();
+        assert_eq!(session.total_tokens().output, 150);
+
+        session.switch_transcript(&second.to_string_lossy());
+        session.update_from_transcript(&models).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(session.total_tokens().output, 175);
+        assert!(session.total_cost() > before);
+        assert_eq!(session.model_totals()["claude-sonnet-4"].messages, 3);
+    }
+}
@@ -1,4 +1,10 @@
+#[cfg(unix)]
+pub mod daemon;
+pub mod ledger;
+pub mod report;
 pub mod segments;
 pub mod statusline;
+pub mod template;
+pub mod transcript;
 
 pub use statusline::{collect_all_segments, StatusLineGenerator};
@@ -0,0 +1,405 @@
+use super::ledger::TokenCounts;
+use super::transcript;
+use crate::config::ModelConfig;
+use chrono::{DateTime, Local, NaiveDate};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+/// Output format of `byebyecode report`
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum ReportFormat {
+    Table,
+    Json,
+    Csv,
+}
+
+/// Dimensions a report can be grouped by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum)]
+pub enum ReportGroup {
+    Day,
+    Month,
+    Project,
+    Model,
+    Session,
+}
+
+impl ReportGroup {
+    fn column(&self) -> &'static str {
+        match self {
+            ReportGroup::Day => "day",
+            ReportGroup::Month => "month",
+            ReportGroup::Project => "project",
+            ReportGroup::Model => "model",
+            ReportGroup::Session => "session",
+        }
+    }
+}
+
+/// One assistant message's billable usage, deduplicated by message ID
+#[derive(Debug, Clone)]
+struct MessageUsage {
+    date: NaiveDate,
+    project: String,
+    session: String,
+    model: String,
+    tokens: TokenCounts,
+}
+
+impl MessageUsage {
+    fn key(&self, group: ReportGroup) -> String {
+        match group {
+            ReportGroup::Day => self.date.format("%Y-%m-%d").to_string(),
+            ReportGroup::Month => self.date.format("%Y-%m").to_string(),
+            ReportGroup::Project => self.project.clone(),
+            ReportGroup::Model => self.model.clone(),
+            ReportGroup::Session => self.session.clone(),
+        }
+    }
+}
+
+/// One aggregated report row
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReportRow {
+    /// Group values keyed by column name (day, project, model, ...)
+    #[serde(flatten)]
+    pub keys: BTreeMap<&'static str, String>,
+    pub sessions: usize,
+    pub messages: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub total_tokens: u64,
+    pub cost_usd: f64,
+    /// False when some messages used a model without a known price
+    pub priced: bool,
+}
+
+/// Report filters and grouping
+#[derive(Debug, Clone)]
+pub struct ReportOptions {
+    pub since: Option<NaiveDate>,
+    pub until: Option<NaiveDate>,
+    pub group_by: Vec<ReportGroup>,
+}
+
+impl ReportOptions {
+    /// Restrict the report to one calendar month given as `YYYY-MM`
+    pub fn with_month(mut self, month: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let first = NaiveDate::parse_from_str(&format!("{}-01", month.trim()), "%Y-%m-%d")
+            .map_err(|_| format!("Invalid month '{}', expected YYYY-MM", month))?;
+        let next = first
+            .checked_add_months(chrono::Months::new(1))
+            .ok_or("Month out of range")?;
+        self.since = Some(first);
+        self.until = next.pred_opt();
+        Ok(self)
+    }
+}
+
+/// Get the Claude Code projects directory (`projects/` in the Claude Code directory)
+fn get_projects_dir() -> Option<PathBuf> {
+    crate::utils::paths::claude_projects_dir()
+}
+
+/// Collect assistant message usage from one transcript into `messages`
+///
+/// Claude Code writes one entry per content block and copies earlier messages into
+/// resumed sessions, so usage is keyed by message ID and keeps the field-wise maximum.
+fn scan_transcript(projects_dir: &Path, path: &Path, messages: &mut HashMap<String, MessageUsage>) {
+    let Ok(file) = fs::File::open(path) else {
+        return;
+    };
+
+    // The top-level directory name encodes the project path; prefer the recorded cwd
+    let fallback_project = path
+        .strip_prefix(projects_dir)
+        .ok()
+        .and_then(|p| p.components().next())
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .unwrap_or_default();
+    let fallback_session = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut project: Option<String> = None;
+
+    for entry in BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| transcript::parse_line(&line))
+    {
+        if project.is_none() {
+            project = entry.cwd.clone();
+        }
+
+        let Some(usage) = transcript::billable_usage(&entry) else {
+            continue;
+        };
+        let Some(timestamp) = entry
+            .timestamp
+            .as_deref()
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+        else {
+            continue;
+        };
+        let Some(key) = usage.message_id.or(entry.uuid) else {
+            continue;
+        };
+        let tokens = usage.tokens;
+
+        messages
+            .entry(key)
+            .and_modify(|existing| existing.tokens = existing.tokens.max(tokens))
+            .or_insert_with(|| MessageUsage {
+                date: timestamp.with_timezone(&Local).date_naive(),
+                project: project.clone().unwrap_or_else(|| fallback_project.clone()),
+                session: entry
+                    .session_id
+                    .clone()
+                    .unwrap_or_else(|| fallback_session.clone()),
+                model: usage.model,
+                tokens,
+            });
+    }
+}
+
+/// Scan every transcript under ~/.claude/projects and aggregate usage
+pub fn build_report(options: &ReportOptions) -> Vec<ReportRow> {
+    let Some(projects_dir) = get_projects_dir() else {
+        return Vec::new();
+    };
+
+    let mut messages = HashMap::new();
+    for path in transcript::find_transcripts(&projects_dir) {
+        scan_transcript(&projects_dir, &path, &mut messages);
+    }
+
+    let models = ModelConfig::load();
+    let mut groups: BTreeMap<Vec<String>, (ReportRow, BTreeSet<String>)> = BTreeMap::new();
+
+    for message in messages.values() {
+        if options.since.is_some_and(|since| message.date < since)
+            || options.until.is_some_and(|until| message.date > until)
+        {
+            continue;
+        }
+
+        let key: Vec<String> = options.group_by.iter().map(|g| message.key(*g)).collect();
+        let (row, sessions) = groups.entry(key.clone()).or_insert_with(|| {
+            let row = ReportRow {
+                keys: options
+                    .group_by
+                    .iter()
+                    .zip(key)
+                    .map(|(g, k)| (g.column(), k))
+                    .collect(),
+                priced: true,
+                ..ReportRow::default()
+            };
+            (row, BTreeSet::new())
+        });
+
+        let tokens = &message.tokens;
+        sessions.insert(message.session.clone());
+        row.messages += 1;
+        row.input_tokens += tokens.input;
+        row.output_tokens += tokens.output;
+        row.cache_creation_tokens += tokens.cache_write;
+        row.cache_read_tokens += tokens.cache_read;
+        row.total_tokens += tokens.total();
+        match models.get_pricing(&message.model) {
+            Some(pricing) => {
+                row.cost_usd += pricing.cost(
+                    tokens.input,
+                    tokens.output,
+                    tokens.cache_write,
+                    tokens.cache_read,
+                )
+            }
+            None => row.priced = false,
+        }
+    }
+
+    groups
+        .into_values()
+        .map(|(mut row, sessions)| {
+            row.sessions = sessions.len();
+            row
+        })
+        .collect()
+}
+
+fn format_tokens(tokens: u64) -> String {
+    if tokens >= 1_000_000 {
+        format!("{:.1}M", tokens as f64 / 1_000_000.0)
+    } else if tokens >= 1000 {
+        format!("{:.1}k", tokens as f64 / 1000.0)
+    } else {
+        tokens.to_string()
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn print_table(rows: &[ReportRow], group_by: &[ReportGroup]) {
+    let key_widths: Vec<usize> = group_by
+        .iter()
+        .map(|g| {
+            rows.iter()
+                .map(|r| r.keys.get(g.column()).map_or(0, |k| k.chars().count()))
+                .max()
+                .unwrap_or(0)
+                .max(g.column().len())
+                .min(48)
+        })
+        .collect();
+
+    let mut header = String::new();
+    for (group, width) in group_by.iter().zip(&key_widths) {
+        header.push_str(&format!("{:<w$}  ", group.column(), w = width));
+    }
+    header.push_str(&format!(
+        "{:>8} {:>8} {:>9} {:>9} {:>11} {:>10} {:>10}",
+        "sessions", "messages", "input", "output", "cache_write", "cache_read", "cost ($)"
+    ));
+    println!("{}", header);
+    println!("{}", "-".repeat(header.chars().count()));
+
+    let mut total = ReportRow {
+        priced: true,
+        ..ReportRow::default()
+    };
+    for row in rows {
+        let mut line = String::new();
+        for (group, width) in group_by.iter().zip(&key_widths) {
+            let value = row.keys.get(group.column()).map_or("", |k| k.as_str());
+            // Keep the tail of long project paths, which is the distinctive part
+            let count = value.chars().count();
+            let value: String = if count > *width {
+                let tail: String = value.chars().skip(count - (width - 1)).collect();
+                format!("…{}", tail)
+            } else {
+                value.to_string()
+            };
+            line.push_str(&format!("{:<w$}  ", value, w = width));
+        }
+        line.push_str(&format!(
+            "{:>8} {:>8} {:>9} {:>9} {:>11} {:>10} {:>10}",
+            row.sessions,
+            row.messages,
+            format_tokens(row.input_tokens),
+            format_tokens(row.output_tokens),
+            format_tokens(row.cache_creation_tokens),
+            format_tokens(row.cache_read_tokens),
+            format!("{:.2}{}", row.cost_usd, if row.priced { "" } else { "*" })
+        ));
+        println!("{}", line);
+
+        total.sessions += row.sessions;
+        total.messages += row.messages;
+        total.input_tokens += row.input_tokens;
+        total.output_tokens += row.output_tokens;
+        total.cache_creation_tokens += row.cache_creation_tokens;
+        total.cache_read_tokens += row.cache_read_tokens;
+        total.cost_usd += row.cost_usd;
+        total.priced &= row.priced;
+    }
+
+    println!("{}", "-".repeat(header.chars().count()));
+    let key_width: usize = key_widths.iter().map(|w| w + 2).sum();
+    println!(
+        "{:<kw$}{:>8} {:>8} {:>9} {:>9} {:>11} {:>10} {:>10}",
+        "Total",
+        "",
+        total.messages,
+        format_tokens(total.input_tokens),
+        format_tokens(total.output_tokens),
+        format_tokens(total.cache_creation_tokens),
+        format_tokens(total.cache_read_tokens),
+        format!("{:.2}", total.cost_usd),
+        kw = key_width
+    );
+
+    if !total.priced {
+        println!();
+        println!("* Includes models without a known price (counted as $0).");
+        println!(
+            "  Add [models.pricing] entries to {}.",
+            crate::utils::paths::byebyecode_home_or_default()
+                .join("models.toml")
+                .display()
+        );
+    }
+}
+
+fn print_csv(rows: &[ReportRow], group_by: &[ReportGroup]) {
+    let mut header: Vec<&str> = group_by.iter().map(|g| g.column()).collect();
+    header.extend([
+        "sessions",
+        "messages",
+        "input_tokens",
+        "output_tokens",
+        "cache_creation_tokens",
+        "cache_read_tokens",
+        "total_tokens",
+        "cost_usd",
+        "priced",
+    ]);
+    println!("{}", header.join(","));
+
+    for row in rows {
+        let mut fields: Vec<String> = group_by
+            .iter()
+            .map(|g| csv_field(row.keys.get(g.column()).map_or("", |k| k.as_str())))
+            .collect();
+        fields.extend([
+            row.sessions.to_string(),
+            row.messages.to_string(),
+            row.input_tokens.to_string(),
+            row.output_tokens.to_string(),
+            row.cache_creation_tokens.to_string(),
+            row.cache_read_tokens.to_string(),
+            row.total_tokens.to_string(),
+            format!("{:.6}", row.cost_usd),
+            row.priced.to_string(),
+        ]);
+        println!("{}", fields.join(","));
+    }
+}
+
+/// `byebyecode report`: print aggregated usage in the requested format
+pub fn print_report(
+    options: &ReportOptions,
+    format: ReportFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let rows = build_report(options);
+
+    match format {
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&rows)?),
+        ReportFormat::Csv => print_csv(&rows, &options.group_by),
+        ReportFormat::Table => {
+            if rows.is_empty() {
+                println!(
+                    "No Claude Code usage found in {} for this period.",
+                    get_projects_dir()
+                        .map(|p| p.display().to_string())
+                        .unwrap_or_else(|| "~/.claude/projects".to_string())
+                );
+            } else {
+                print_table(&rows, &options.group_by);
+            }
+        }
+    }
+
+    Ok(())
+}
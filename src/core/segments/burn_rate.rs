@@ -0,0 +1,187 @@
+use super::{byebyecode_usage, Segment, SegmentData};
+use crate::api::{cache, history, ApiConfig};
+use crate::config::{Config, InputData, SegmentConfig, SegmentId};
+use chrono::{DateTime, Duration, Local, NaiveDateTime, TimeZone};
+use std::collections::HashMap;
+
+/// 额度消耗速率与耗尽预测（基于 `usage history` 记录的本地样本）
+///
+/// 预测状态写入 `state` 元数据（ok / warning / critical），颜色由主题的 rules 控制
+pub struct BurnRateSegment {
+    window_hours: f64,
+    /// 样本和到期时间所属的中转站账号，未配置时不显示
+    api_config: Option<ApiConfig>,
+}
+
+/// 段落可覆盖的中转站选项，未配置的沿用 ByeByeCodeUsage 段
+const RELAY_OPTIONS: &[&str] = &["usage_url", "provider", "api_key"];
+
+/// 解析 burn_rate 段对应的中转站配置，与用量段记录样本时使用的账号一致
+pub fn resolve_api_config(config: &Config, segment: &SegmentConfig) -> Option<ApiConfig> {
+    let mut relay_segment = config
+        .segments
+        .iter()
+        .find(|s| matches!(s.id, SegmentId::ByeByeCodeUsage))
+        .cloned()
+        .unwrap_or_else(|| segment.clone());
+    for key in RELAY_OPTIONS {
+        if let Some(value) = segment.options.get(*key) {
+            relay_segment.options.insert(key.to_string(), value.clone());
+        }
+    }
+    byebyecode_usage::resolve_api_config(config, &relay_segment)
+}
+
+impl Default for BurnRateSegment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BurnRateSegment {
+    pub fn new() -> Self {
+        Self {
+            window_hours: 3.0,
+            api_config: None,
+        }
+    }
+
+    pub fn with_window_hours(mut self, window_hours: f64) -> Self {
+        if window_hours > 0.0 {
+            self.window_hours = window_hours;
+        }
+        self
+    }
+
+    pub fn with_api_config(mut self, api_config: Option<ApiConfig>) -> Self {
+        self.api_config = api_config;
+        self
+    }
+
+    fn format_duration(hours: f64) -> String {
+        if hours >= 48.0 {
+            format!("{:.1}d", hours / 24.0)
+        } else if hours >= 1.0 {
+            format!("{:.1}h", hours)
+        } else {
+            format!("{:.0}m", (hours * 60.0).max(1.0))
+        }
+    }
+
+    /// 解析到期时间：Unix 时间戳（秒/毫秒）、RFC 3339 或 `YYYY-MM-DD HH:MM:SS`
+    fn parse_time(value: &str) -> Option<DateTime<Local>> {
+        let value = value.trim();
+        if let Ok(ts) = value.parse::<i64>() {
+            let ts = if ts > 1_000_000_000_000 {
+                ts / 1000
+            } else {
+                ts
+            };
+            return Local.timestamp_opt(ts, 0).single();
+        }
+        if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+            return Some(dt.with_timezone(&Local));
+        }
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+            .ok()
+            .and_then(|dt| Local.from_local_datetime(&dt).earliest())
+    }
+
+    /// 额度需要撑到的时间点：当前套餐的剩余天数或用量接口返回的到期/重置时间，取最早者
+    /// 只读当前账号和模型的本地缓存，不发起网络请求
+    fn plan_deadline(
+        api_config: &ApiConfig,
+        model: &str,
+        plan_name: Option<&str>,
+    ) -> Option<DateTime<Local>> {
+        let now = Local::now();
+        let mut deadlines = Vec::new();
+
+        if let (Some(subs), _) = cache::get_cached_subscriptions(api_config, Some(model)) {
+            let active: Vec<_> = subs.iter().filter(|s| s.is_active).collect();
+            let current = active
+                .iter()
+                .find(|s| Some(s.plan_name.as_str()) == plan_name)
+                .or_else(|| active.first());
+            if let Some(sub) = current {
+                if sub.remaining_days > 0 {
+                    deadlines.push(now + Duration::days(sub.remaining_days as i64));
+                }
+                if let Some(end) = sub.expires_at.as_deref().and_then(Self::parse_time) {
+                    deadlines.push(end);
+                }
+            }
+        }
+
+        if let (Some(usage), _) = cache::get_cached_usage(api_config, Some(model)) {
+            if let Some(end) = usage.get_expires_at().as_deref().and_then(Self::parse_time) {
+                deadlines.push(end);
+            }
+        }
+
+        deadlines.into_iter().filter(|d| *d > now).min()
+    }
+}
+
+impl Segment for BurnRateSegment {
+    fn collect(&self, input: &InputData) -> Option<SegmentData> {
+        let api_config = self.api_config.as_ref().filter(|c| !c.api_key.is_empty())?;
+        let window = Duration::minutes((self.window_hours * 60.0).round() as i64);
+        let samples = history::load_samples(Local::now() - window);
+        // 只看本段账号的样本，其他账号或切换密钥前后的样本不能互相抵扣
+        let samples = history::account_samples(&samples, &api_config.account_key());
+        let latest = samples.last()?;
+
+        let mut metadata = HashMap::new();
+        metadata.insert("window_hours".to_string(), self.window_hours.to_string());
+
+        let Some(rate) = history::burn_rate(&samples, window) else {
+            // 样本不足，等待下一次用量刷新
+            return Some(SegmentData {
+                primary: "采样中...".to_string(),
+                secondary: String::new(),
+                metadata,
+            });
+        };
+
+        metadata.insert("per_hour".to_string(), format!("{:.2}", rate.per_hour));
+        metadata.insert("remaining".to_string(), format!("{:.2}", rate.remaining));
+
+        let Some(hours_left) = rate.hours_left else {
+            return Some(SegmentData {
+                primary: format!("${:.2}/h", rate.per_hour),
+                secondary: String::new(),
+                metadata,
+            });
+        };
+
+        let exhausted_at = Local::now() + Duration::minutes((hours_left * 60.0) as i64);
+        metadata.insert("hours_left".to_string(), format!("{:.2}", hours_left));
+        metadata.insert("exhausted_at".to_string(), exhausted_at.to_rfc3339());
+
+        // 一小时内耗尽为 critical，在套餐到期/重置前耗尽为 warning
+        let deadline =
+            Self::plan_deadline(api_config, &input.model.id, latest.plan_name.as_deref());
+        let state = if hours_left < 1.0 {
+            "critical"
+        } else if deadline.is_some_and(|d| exhausted_at < d) {
+            "warning"
+        } else {
+            "ok"
+        };
+        metadata.insert("state".to_string(), state.to_string());
+        if let Some(deadline) = deadline {
+            metadata.insert("deadline".to_string(), deadline.to_rfc3339());
+        }
+
+        Some(SegmentData {
+            primary: format!("${:.2}/h", rate.per_hour),
+            secondary: format!("~{}耗尽", Self::format_duration(hours_left)),
+            metadata,
+        })
+    }
+
+    fn id(&self) -> SegmentId {
+        SegmentId::BurnRate
+    }
+}
@@ -1,26 +1,9 @@
-use crate::api::{client::ApiClient, ApiConfig};
+use crate::api::{cache, ApiConfig};
 use crate::config::Config;
 use crate::config::InputData;
 use crate::core::segments::SegmentData;
 use std::collections::HashMap;
 
-/// 根据套餐类型获取语义化颜色
-/// - PLUS/PRO/MAX: 橙色（高级套餐）
-/// - PAYGO: 蓝色（按需套餐）
-/// - FREE: 灰色（免费套餐）
-/// - 其他: 白色
-fn get_plan_color(plan_name: &str) -> &'static str {
-    match plan_name.to_uppercase().as_str() {
-        "PLUS" | "PRO" | "MAX" => "\x1b[38;5;214m", // 橙色
-        "PAYGO" => "\x1b[38;5;39m",                 // 蓝色
-        "FREE" => "\x1b[38;5;245m",                 // 灰色
-        _ => "\x1b[38;5;255m",                      // 白色
-    }
-}
-
-/// ANSI 重置代码
-const RESET: &str = "\x1b[0m";
-
 pub fn collect(config: &Config, input: &InputData) -> Option<SegmentData> {
     // Get API config from segment options
     let segment = config
@@ -58,17 +41,47 @@ pub fn collect(config: &Config, input: &InputData) -> Option<SegmentData> {
         .or_else(crate::api::get_usage_url_from_claude_settings)
         .unwrap_or_else(|| "https://www.88code.ai/api/usage".to_string());
 
-    if usage_url.contains("packyapi.com") {
+    // 中转站类型：优先当前段配置，其次 ByeByeCodeUsage 段配置，未配置时根据 usage_url 推断
+    let provider_name = [
+        Some(segment),
+        config
+            .segments
+            .iter()
+            .find(|s| matches!(s.id, crate::config::SegmentId::ByeByeCodeUsage)),
+    ]
+    .into_iter()
+    .flatten()
+    .find_map(|s| {
+        s.options
+            .get("provider")
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string())
+    });
+
+    let api_config = ApiConfig {
+        enabled: true,
+        usage_url: usage_url.clone(),
+        provider: provider_name,
+        http: config.http.clone(),
+        ..ApiConfig::default()
+    };
+
+    // 不支持订阅接口的中转站（如 Packy）不显示
+    let relay = api_config.provider()?;
+    if !relay.supports_subscriptions() {
         return None;
     }
 
-    // Try to get API key from segment options first, then from Claude settings
+    // Try to get API key from segment options first, then [relay], then Claude settings
     let api_key = segment
         .options
         .get("api_key")
         .and_then(|v| v.as_str())
         .filter(|s| !s.is_empty())
-        .map(|s| s.to_string())
+        .or(Some(config.relay.api_key.as_str()).filter(|s| !s.is_empty()))
+        // 密钥可以是 env:/file:/cmd: 引用
+        .and_then(crate::config::secrets::resolve)
         .or_else(crate::api::get_api_key_from_claude_settings);
 
     let api_key = match api_key {
@@ -82,34 +95,19 @@ pub fn collect(config: &Config, input: &InputData) -> Option<SegmentData> {
         }
     };
 
-    // 实时获取数据，不使用缓存
+    // 缓存优先，过期时由后台子进程按当前配置刷新
     // 传入 model 参数以获取正确的套餐信息
     let model_id = &input.model.id;
-    let subscriptions = fetch_subscriptions_sync(&api_key, &usage_url, Some(model_id))?;
-
-    fn fetch_subscriptions_sync(
-        api_key: &str,
-        usage_url: &str,
-        model: Option<&str>,
-    ) -> Option<Vec<crate::api::SubscriptionData>> {
-        // 根据 usage_url 自动推断 subscription_url
-        let subscription_url = if usage_url.contains("88code") {
-            usage_url.replace("/usage", "/subscription")
-        } else {
-            "https://www.88code.ai/api/subscription".to_string()
-        };
-
-        let api_config = ApiConfig {
-            enabled: true,
-            api_key: api_key.to_string(),
-            usage_url: usage_url.to_string(),
-            subscription_url,
-        };
-
-        let client = ApiClient::new(api_config).ok()?;
-        let subs = client.get_subscriptions(model).ok()?;
-        Some(subs)
-    }
+    let subscription_url = relay
+        .default_subscription_url(&usage_url)
+        .unwrap_or_else(|| "https://www.88code.ai/api/subscription".to_string());
+    let api_config = ApiConfig {
+        api_key,
+        subscription_url,
+        provider: Some(relay.name().to_string()),
+        ..api_config
+    };
+    let subscriptions = cache::subscriptions_with_refresh(&api_config, Some(model_id))?;
 
     // 过滤掉已禁用的订阅和已过期的订阅（剩余天数 <= 0）
     let mut active_subscriptions: Vec<_> = subscriptions
@@ -141,27 +139,24 @@ pub fn collect(config: &Config, input: &InputData) -> Option<SegmentData> {
     let mut metadata = HashMap::new();
 
     for (idx, sub) in active_subscriptions.iter().enumerate() {
-        // 语义化颜色
-        let color = get_plan_color(&sub.plan_name);
-
         // 精简格式：PLUS ¥198/月 重置×2 53天
         // 只有 PAYGO 不显示重置次数，其他套餐（包括 FREE）都显示（如果有）
         let short_price = sub.plan_price.replace("付", "");
         let plan_upper = sub.plan_name.to_uppercase();
         let subscription_text = if plan_upper != "PAYGO" && sub.reset_times > 0 {
             format!(
-                "{}{} {} 重置×{} {}天{}",
-                color, sub.plan_name, short_price, sub.reset_times, sub.remaining_days, RESET
+                "{} {} 重置×{} {}天",
+                sub.plan_name, short_price, sub.reset_times, sub.remaining_days
             )
         } else {
-            format!(
-                "{}{} {} {}天{}",
-                color, sub.plan_name, short_price, sub.remaining_days, RESET
-            )
+            format!("{} {} {}天", sub.plan_name, short_price, sub.remaining_days)
         };
         subscription_texts.push(subscription_text);
 
-        // 保存元数据
+        // 保存元数据（plan 为第一个订阅，主题规则按它给整个段落着色）
+        if idx == 0 {
+            metadata.insert("plan".to_string(), sub.plan_name.clone());
+        }
         metadata.insert(format!("plan_{}", idx), sub.plan_name.clone());
         metadata.insert(format!("price_{}", idx), sub.plan_price.clone());
         metadata.insert(format!("status_{}", idx), sub.status.clone());
@@ -1,24 +1,67 @@
-use crate::api::{cache, client::ApiClient, ApiConfig};
-use crate::config::Config;
+use crate::api::{cache, custom::CustomRelaySchema, ApiConfig};
 use crate::config::InputData;
+use crate::config::{Config, SegmentConfig};
 use crate::core::segments::SegmentData;
 use std::collections::HashMap;
 
-/// ANSI 重置代码
-const RESET: &str = "\x1b[0m";
-
-/// 根据百分比获取状态色（柔和色调）
-/// - 0-50%: 柔和绿 (充足)
-/// - 50-80%: 柔和黄 (注意)
-/// - 80%+: 柔和红 (紧急)
-fn get_status_color(percentage: f64) -> &'static str {
-    if percentage <= 50.0 {
-        "\x1b[38;5;114m" // 柔和绿 (256色 #114)
-    } else if percentage <= 80.0 {
-        "\x1b[38;5;179m" // 柔和黄/橙 (256色 #179)
-    } else {
-        "\x1b[38;5;167m" // 柔和红 (256色 #167)
-    }
+/// 按段落选项解析中转站配置：usage_url、provider、relay_schema、subscription_url 和密钥
+/// 密钥依次取段落选项、[relay]、Claude 设置，都没有时为空；无法识别中转站类型时返回 None
+pub fn resolve_api_config(config: &Config, segment: &SegmentConfig) -> Option<ApiConfig> {
+    let usage_url = segment
+        .options
+        .get("usage_url")
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+        .or_else(crate::api::get_usage_url_from_claude_settings)
+        .unwrap_or_else(|| "https://www.88code.ai/api/usage".to_string());
+
+    // 显式配置的中转站类型，未配置时根据 usage_url 推断
+    let provider_name = segment
+        .options
+        .get("provider")
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string());
+
+    let mut api_config = ApiConfig {
+        enabled: true,
+        usage_url: usage_url.clone(),
+        provider: provider_name,
+        // 格式错误的 relay_schema 不能回退为按地址推断的中转站，由 collect 提示错误
+        relay_schema: CustomRelaySchema::from_options(&segment.options).ok()?,
+        http: config.http.clone(),
+        ..ApiConfig::default()
+    };
+
+    // 无法识别的中转站不支持额度显示，需要显式配置 provider
+    let relay = api_config.provider()?;
+
+    // Try to get API key from segment options first, then [relay], then Claude settings
+    api_config.api_key = segment
+        .options
+        .get("api_key")
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+        .or(Some(config.relay.api_key.as_str()).filter(|s| !s.is_empty()))
+        // 密钥可以是 env:/file:/cmd: 引用
+        .and_then(crate::config::secrets::resolve)
+        .or_else(crate::api::get_api_key_from_claude_settings)
+        .unwrap_or_default();
+
+    api_config.subscription_url = segment
+        .options
+        .get("subscription_url")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| {
+            // 根据 usage_url 自动推断 subscription_url
+            relay
+                .default_subscription_url(&usage_url)
+                .unwrap_or_else(|| "https://www.88code.ai/api/subscription".to_string())
+        });
+
+    Some(api_config)
 }
 
 pub fn collect(config: &Config, input: &InputData) -> Option<SegmentData> {
@@ -32,22 +75,18 @@ pub fn collect(config: &Config, input: &InputData) -> Option<SegmentData> {
         return None;
     }
 
-    let usage_url = segment
-        .options
-        .get("usage_url")
-        .and_then(|v| v.as_str())
-        .filter(|s| !s.is_empty())
-        .map(|s| s.to_string())
-        .or_else(crate::api::get_usage_url_from_claude_settings)
-        .unwrap_or_else(|| "https://www.88code.ai/api/usage".to_string());
+    if let Err(error) = CustomRelaySchema::from_options(&segment.options) {
+        let mut metadata = HashMap::new();
+        metadata.insert("dynamic_icon".to_string(), "relay".to_string());
+        metadata.insert("error".to_string(), error);
+        return Some(SegmentData {
+            primary: "relay_schema 无效".to_string(),
+            secondary: "运行 byebyecode --check 查看详情".to_string(),
+            metadata,
+        });
+    }
 
-    // 根据 usage_url 判断是哪个服务，并设置动态图标
-    let service_name = if usage_url.contains("88code") || usage_url.contains("rainapp.top") {
-        "88code"
-    } else if usage_url.contains("packyapi.com") {
-        "packy"
-    } else {
-        // 其他中转站不支持额度显示，因为 API 返回的数据格式不正确
+    let Some(api_config) = resolve_api_config(config, segment) else {
         let mut metadata = HashMap::new();
         metadata.insert("dynamic_icon".to_string(), "88code".to_string());
         return Some(SegmentData {
@@ -57,53 +96,26 @@ pub fn collect(config: &Config, input: &InputData) -> Option<SegmentData> {
         });
     };
 
-    // Try to get API key from segment options first, then from Claude settings
-    let api_key = segment
-        .options
-        .get("api_key")
-        .and_then(|v| v.as_str())
-        .filter(|s| !s.is_empty())
-        .map(|s| s.to_string())
-        .or_else(crate::api::get_api_key_from_claude_settings);
+    // 根据中转站类型设置动态图标
+    let relay = api_config.provider()?;
+    let service_name = relay.name();
 
-    let api_key = match api_key {
-        Some(key) if !key.is_empty() => key,
-        _ => {
-            let mut metadata = HashMap::new();
-            metadata.insert("dynamic_icon".to_string(), service_name.to_string());
-            return Some(SegmentData {
-                primary: "未配置密钥".to_string(),
-                secondary: String::new(),
-                metadata,
-            });
-        }
-    };
-
-    let subscription_url = segment
-        .options
-        .get("subscription_url")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string())
-        .unwrap_or_else(|| {
-            // 根据 usage_url 自动推断 subscription_url
-            if usage_url.contains("88code") {
-                usage_url.replace("/usage", "/subscription")
-            } else {
-                "https://www.88code.ai/api/subscription".to_string()
-            }
+    if api_config.api_key.is_empty() {
+        let mut metadata = HashMap::new();
+        metadata.insert("dynamic_icon".to_string(), service_name.to_string());
+        return Some(SegmentData {
+            primary: "未配置密钥".to_string(),
+            secondary: String::new(),
+            metadata,
         });
+    }
 
     // 从输入数据获取当前使用的模型
     let model_id = &input.model.id;
 
-    // 优先使用缓存，API 失败时降级
-    let usage = fetch_usage_with_cache(
-        &api_key,
-        &usage_url,
-        &subscription_url,
-        Some(model_id),
-        service_name,
-    );
+    // 缓存优先，过期时由后台子进程按当前配置刷新
+    let usage = cache::usage_with_refresh(&api_config, Some(model_id));
+    let refresh_error = cache::last_refresh_error(&api_config, Some(model_id));
 
     let usage = match usage {
         Some(u) => u,
@@ -119,38 +131,6 @@ pub fn collect(config: &Config, input: &InputData) -> Option<SegmentData> {
         }
     };
 
-    /// 带缓存的用量获取
-    /// 1. 尝试从 API 获取最新数据
-    /// 2. 成功则更新缓存并返回
-    /// 3. 失败则尝试使用缓存降级
-    fn fetch_usage_with_cache(
-        api_key: &str,
-        usage_url: &str,
-        subscription_url: &str,
-        model: Option<&str>,
-        _service_name: &str,
-    ) -> Option<crate::api::UsageData> {
-        let api_config = ApiConfig {
-            enabled: true,
-            api_key: api_key.to_string(),
-            usage_url: usage_url.to_string(),
-            subscription_url: subscription_url.to_string(),
-        };
-
-        // 尝试从 API 获取
-        if let Ok(client) = ApiClient::new(api_config) {
-            if let Ok(usage) = client.get_usage(model) {
-                // API 成功，保存缓存
-                let _ = cache::save_cached_usage(&usage);
-                return Some(usage);
-            }
-        }
-
-        // API 失败，尝试使用缓存降级
-        let (cached, _) = cache::get_cached_usage();
-        cached
-    }
-
     // 处理使用数据
     let used_dollars = usage.get_used_tokens() as f64 / 100.0;
     let remaining_dollars = (usage.get_remaining_tokens() as f64 / 100.0).max(0.0);
@@ -160,15 +140,27 @@ pub fn collect(config: &Config, input: &InputData) -> Option<SegmentData> {
     metadata.insert("used".to_string(), format!("{:.2}", used_dollars));
     metadata.insert("total".to_string(), format!("{:.2}", total_dollars));
     metadata.insert("remaining".to_string(), format!("{:.2}", remaining_dollars));
+    if total_dollars > 0.0 {
+        metadata.insert(
+            "percentage".to_string(),
+            format!("{:.1}", (used_dollars / total_dollars * 100.0).max(0.0)),
+        );
+    }
     metadata.insert("service".to_string(), service_name.to_string());
     metadata.insert("dynamic_icon".to_string(), service_name.to_string());
+    if let Some(expires_at) = usage.get_expires_at() {
+        metadata.insert("expires_at".to_string(), expires_at);
+    }
+    if let Some(error) = &refresh_error {
+        metadata.insert("refresh_error".to_string(), error.message.clone());
+    }
 
     // 对于 88code：如果只有 FREE 套餐（没有 PLUS/PRO/MAX），检查是否有 PAYGO 可用
     // 场景：用户只有 FREE + PAYGO，没有 PLUS
     // Usage API 不返回 PAYGO，所以 fallback 到 FREE 数据，但 CC 不使用 FREE 额度
     if service_name == "88code" && usage.has_only_free() {
         let model_id = &input.model.id;
-        let subscriptions = fetch_subscriptions_sync(&api_key, &subscription_url, Some(model_id));
+        let subscriptions = fetch_subscriptions_sync(&api_config, Some(model_id));
 
         if let Some(subs) = subscriptions {
             // 查找有余额的 PAYGO 套餐
@@ -179,13 +171,10 @@ pub fn collect(config: &Config, input: &InputData) -> Option<SegmentData> {
                 .find(|s| s.current_credits > 0.0);
 
             if let Some(paygo_sub) = paygo {
-                // 显示 PAYGO 剩余额度（蓝色）
-                let paygo_color = "\x1b[38;5;39m"; // 蓝色
+                // 显示 PAYGO 剩余额度（主题规则按 plan 字段着色）
+                metadata.insert("plan".to_string(), "PAYGO".to_string());
                 return Some(SegmentData {
-                    primary: format!(
-                        "{}PAYGO{} ${:.2}",
-                        paygo_color, RESET, paygo_sub.current_credits
-                    ),
+                    primary: format!("PAYGO ${:.2}", paygo_sub.current_credits),
                     secondary: String::new(),
                     metadata,
                 });
@@ -197,7 +186,7 @@ pub fn collect(config: &Config, input: &InputData) -> Option<SegmentData> {
     if usage.is_exhausted() {
         // 实时获取订阅信息，传入 model 以获取正确的套餐
         let model_id = &input.model.id;
-        let subscriptions = fetch_subscriptions_sync(&api_key, &subscription_url, Some(model_id));
+        let subscriptions = fetch_subscriptions_sync(&api_config, Some(model_id));
 
         if let Some(subs) = subscriptions {
             // 仅 88code 服务支持 PAYGO 回退逻辑
@@ -210,32 +199,27 @@ pub fn collect(config: &Config, input: &InputData) -> Option<SegmentData> {
                     .find(|s| s.current_credits > 0.0);
 
                 if let Some(paygo_sub) = paygo {
-                    // 显示 PAYGO 剩余额度（蓝色）
-                    let paygo_color = "\x1b[38;5;39m"; // 蓝色
+                    // 显示 PAYGO 剩余额度（主题规则按 plan 字段着色）
+                    metadata.insert("plan".to_string(), "PAYGO".to_string());
 
                     // 如果有总额度信息，显示进度条
                     if paygo_sub.credit_limit > 0.0 {
                         let used = paygo_sub.credit_limit - paygo_sub.current_credits;
                         let percentage = (used / paygo_sub.credit_limit * 100.0).clamp(0.0, 100.0);
+                        // 规则按显示的 PAYGO 额度匹配
+                        metadata.insert("percentage".to_string(), format!("{:.1}", percentage));
 
                         // 生成进度条（10格）
                         let bar_length = 10;
                         let filled = ((percentage / 100.0) * bar_length as f64).round() as usize;
                         let empty = bar_length - filled;
 
-                        // PAYGO 使用蓝色进度条
-                        let progress_bar = format!(
-                            "{}{}{}{}",
-                            paygo_color,
-                            "▓".repeat(filled),
-                            "░".repeat(empty),
-                            RESET
-                        );
+                        let progress_bar = format!("{}{}", "▓".repeat(filled), "░".repeat(empty));
 
                         return Some(SegmentData {
                             primary: format!(
-                                "{}PAYGO{} ${:.2}/${:.0} {}",
-                                paygo_color, RESET, used, paygo_sub.credit_limit, progress_bar
+                                "PAYGO ${:.2}/${:.0} {}",
+                                used, paygo_sub.credit_limit, progress_bar
                             ),
                             secondary: String::new(),
                             metadata,
@@ -244,10 +228,7 @@ pub fn collect(config: &Config, input: &InputData) -> Option<SegmentData> {
 
                     // 无总额度信息，只显示剩余额度
                     return Some(SegmentData {
-                        primary: format!(
-                            "{}PAYGO{} ${:.2}",
-                            paygo_color, RESET, paygo_sub.current_credits
-                        ),
+                        primary: format!("PAYGO ${:.2}", paygo_sub.current_credits),
                         secondary: String::new(),
                         metadata,
                     });
@@ -298,64 +279,44 @@ pub fn collect(config: &Config, input: &InputData) -> Option<SegmentData> {
         0.0
     };
 
-    // 生成进度条（10格）+ 状态色
+    // 生成进度条（10格），颜色由主题规则按 percentage 字段控制
     let bar_length = 10;
     let filled = ((percentage / 100.0) * bar_length as f64).round() as usize;
     let empty = bar_length - filled;
+    let progress_bar = format!("{}{}", "▓".repeat(filled), "░".repeat(empty));
 
-    // 根据百分比获取状态色
-    let status_color = get_status_color(percentage);
-    let progress_bar = format!(
-        "{}{}{}{}",
-        status_color,
-        "▓".repeat(filled),
-        "░".repeat(empty),
-        RESET
-    );
+    // 后台刷新失败时提示数据可能已过期，详细信息在 refresh_error 元数据中
+    let secondary = if refresh_error.is_some() {
+        "⚠".to_string()
+    } else {
+        String::new()
+    };
 
     Some(SegmentData {
         primary: format!(
             "${:.2}/${:.0} {}",
             used_dollars, total_dollars, progress_bar
         ),
-        secondary: String::new(),
+        secondary,
         metadata,
     })
 }
 
 /// 带缓存的订阅数据获取
 /// 1. 先尝试使用缓存（5分钟有效期）
-/// 2. 缓存过期或不存在时调用 API
-/// 3. API 成功后更新缓存
+/// 2. 缓存过期时返回旧缓存并在后台刷新
+/// 3. 没有缓存时同步调用 API
 fn fetch_subscriptions_sync(
-    api_key: &str,
-    subscription_url: &str,
+    api_config: &ApiConfig,
     model: Option<&str>,
 ) -> Option<Vec<crate::api::SubscriptionData>> {
-    // 先检查缓存
-    let (cached, needs_refresh) = cache::get_cached_subscriptions();
-
-    // 缓存新鲜且存在，直接返回
-    if cached.is_some() && !needs_refresh {
-        return cached;
-    }
-
-    // 缓存过期或不存在，调用 API
-    let api_config = ApiConfig {
-        enabled: true,
-        api_key: api_key.to_string(),
-        usage_url: String::new(),
-        subscription_url: subscription_url.to_string(),
-    };
-
-    if let Ok(client) = ApiClient::new(api_config) {
-        if let Ok(subs) = client.get_subscriptions(model) {
-            // 保存到缓存
-            let _ = cache::save_cached_subscriptions(&subs);
-            return Some(subs);
-        }
+    // 不支持订阅接口的中转站直接跳过
+    if !api_config
+        .provider()
+        .is_some_and(|p| p.supports_subscriptions())
+    {
+        return None;
     }
 
-    // API 失败，返回过期缓存（降级处理）
-    cached
+    cache::subscriptions_with_refresh(api_config, model)
 }
@@ -1,16 +1,90 @@
 use super::{Segment, SegmentData};
-use crate::config::{InputData, ModelConfig, SegmentId, TranscriptEntry};
+use crate::config::{InputData, ModelConfig, NormalizedUsage, SegmentId, TranscriptEntry};
 use std::collections::HashMap;
 use std::fs;
 use std::io::{BufRead, BufReader};
 use std::path::{Path, PathBuf};
 
-#[derive(Default)]
-pub struct ContextWindowSegment;
+/// Turns (user prompts) used to estimate context growth by default
+const DEFAULT_HISTORY_TURNS: usize = 5;
+
+/// Claude Code auto-compacts when the context reaches about this share of the window
+const DEFAULT_COMPACT_THRESHOLD: f64 = 95.0;
+
+pub struct ContextWindowSegment {
+    /// Number of recent turns the growth rate is averaged over
+    history_turns: usize,
+    /// Context usage (percent) at which auto-compaction is expected
+    compact_threshold: f64,
+    /// Show growth per turn and turns left before compaction
+    show_forecast: bool,
+}
+
+impl Default for ContextWindowSegment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Usage of the last assistant response plus the context size at the end of recent turns,
+/// oldest first; a turn is one user prompt and every response and tool call it leads to
+struct TranscriptUsage {
+    last: NormalizedUsage,
+    history: Vec<u32>,
+}
+
+impl TranscriptUsage {
+    fn single(last: NormalizedUsage) -> Self {
+        Self {
+            last,
+            history: Vec::new(),
+        }
+    }
+
+    /// Average context growth per turn since the last compaction within the history
+    fn growth_per_turn(&self) -> Option<f64> {
+        // A shrinking context means a compaction or /clear; only measure after it
+        let start = self
+            .history
+            .windows(2)
+            .rposition(|pair| pair[1] < pair[0])
+            .map_or(0, |i| i + 1);
+        let recent = &self.history[start..];
+        if recent.len() < 2 {
+            return None;
+        }
+        let first = *recent.first()? as f64;
+        let last = *recent.last()? as f64;
+        Some((last - first) / (recent.len() - 1) as f64)
+    }
+}
 
 impl ContextWindowSegment {
     pub fn new() -> Self {
-        Self
+        Self {
+            history_turns: DEFAULT_HISTORY_TURNS,
+            compact_threshold: DEFAULT_COMPACT_THRESHOLD,
+            show_forecast: true,
+        }
+    }
+
+    pub fn from_options(options: &HashMap<String, serde_json::Value>) -> Self {
+        let defaults = Self::new();
+        Self {
+            history_turns: options
+                .get("history_turns")
+                .and_then(|v| v.as_u64())
+                .map_or(defaults.history_turns, |n| n.max(1) as usize),
+            compact_threshold: options
+                .get("compact_threshold")
+                .and_then(|v| v.as_f64())
+                .filter(|n| *n > 0.0)
+                .unwrap_or(defaults.compact_threshold),
+            show_forecast: options
+                .get("show_forecast")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(defaults.show_forecast),
+        }
     }
 
     /// Get context limit for the specified model
@@ -25,7 +99,8 @@ impl Segment for ContextWindowSegment {
         // Dynamically determine context limit based on current model ID
         let context_limit = Self::get_context_limit_for_model(&input.model.id);
 
-        let context_used_token_opt = parse_transcript_usage(&input.transcript_path);
+        let usage = parse_transcript_usage(&input.transcript_path, self.history_turns + 1);
+        let context_used_token_opt = usage.as_ref().map(|u| u.last.display_tokens());
 
         let (percentage_display, tokens_display) = match context_used_token_opt {
             Some(context_used_token) => {
@@ -37,18 +112,7 @@ impl Segment for ContextWindowSegment {
                     format!("{:.1}%", context_used_rate)
                 };
 
-                let tokens = if context_used_token >= 1000 {
-                    let k_value = context_used_token as f64 / 1000.0;
-                    if k_value.fract() == 0.0 {
-                        format!("{}k", k_value as u32)
-                    } else {
-                        format!("{:.1}k", k_value)
-                    }
-                } else {
-                    context_used_token.to_string()
-                };
-
-                (percentage, tokens)
+                (percentage, format_tokens(context_used_token))
             }
             None => {
                 // No usage data available
@@ -70,10 +134,47 @@ impl Segment for ContextWindowSegment {
         }
         metadata.insert("limit".to_string(), context_limit.to_string());
         metadata.insert("model".to_string(), input.model.id.clone());
+        metadata.insert(
+            "compact_threshold".to_string(),
+            self.compact_threshold.to_string(),
+        );
+
+        let mut secondary = String::new();
+        if let (Some(usage), Some(context_used_token)) = (&usage, context_used_token_opt) {
+            let last = &usage.last;
+            metadata.insert("input_tokens".to_string(), last.input_tokens.to_string());
+            metadata.insert(
+                "cache_read_tokens".to_string(),
+                last.cache_read_input_tokens.to_string(),
+            );
+            metadata.insert(
+                "cache_creation_tokens".to_string(),
+                last.cache_creation_input_tokens.to_string(),
+            );
+            metadata.insert("output_tokens".to_string(), last.output_tokens.to_string());
+
+            if let Some(growth) = usage.growth_per_turn() {
+                metadata.insert("growth_per_turn".to_string(), format!("{:.0}", growth));
+
+                let compact_at = context_limit as f64 * self.compact_threshold / 100.0;
+                let remaining = compact_at - context_used_token as f64;
+                if growth > 0.0 {
+                    let turns_left = (remaining / growth).ceil().max(0.0) as u64;
+                    metadata.insert("turns_left".to_string(), turns_left.to_string());
+                    if self.show_forecast {
+                        secondary = format!(
+                            "+{}/turn · ~{} turns",
+                            format_tokens(growth.round() as u32),
+                            turns_left
+                        );
+                    }
+                }
+            }
+        }
 
         Some(SegmentData {
             primary: format!("{} · {} tokens", percentage_display, tokens_display),
-            secondary: String::new(),
+            secondary,
             metadata,
         })
     }
@@ -83,17 +184,34 @@ impl Segment for ContextWindowSegment {
     }
 }
 
-fn parse_transcript_usage<P: AsRef<Path>>(transcript_path: P) -> Option<u32> {
+fn format_tokens(tokens: u32) -> String {
+    if tokens >= 1000 {
+        let k_value = tokens as f64 / 1000.0;
+        if k_value.fract() == 0.0 {
+            format!("{}k", k_value as u32)
+        } else {
+            format!("{:.1}k", k_value)
+        }
+    } else {
+        tokens.to_string()
+    }
+}
+
+/// Last response's usage and the context size of up to `max_turns` recent turns
+fn parse_transcript_usage<P: AsRef<Path>>(
+    transcript_path: P,
+    max_turns: usize,
+) -> Option<TranscriptUsage> {
     let path = transcript_path.as_ref();
 
     // Try to parse from current transcript file
-    if let Some(usage) = try_parse_transcript_file(path) {
+    if let Some(usage) = try_parse_transcript_file(path, max_turns) {
         return Some(usage);
     }
 
     // If file doesn't exist, try to find usage from project history
     if !path.exists() {
-        if let Some(usage) = try_find_usage_from_project_history(path) {
+        if let Some(usage) = try_find_usage_from_project_history(path, max_turns) {
             return Some(usage);
         }
     }
@@ -101,7 +219,7 @@ fn parse_transcript_usage<P: AsRef<Path>>(transcript_path: P) -> Option<u32> {
     None
 }
 
-fn try_parse_transcript_file(path: &Path) -> Option<u32> {
+fn try_parse_transcript_file(path: &Path, max_turns: usize) -> Option<TranscriptUsage> {
     let file = fs::File::open(path).ok()?;
     let reader = BufReader::new(file);
     let lines: Vec<String> = reader
@@ -120,34 +238,63 @@ fn try_parse_transcript_file(path: &Path) -> Option<u32> {
             // Handle summary case: find usage by leafUuid
             if let Some(leaf_uuid) = &entry.leaf_uuid {
                 let project_dir = path.parent()?;
-                return find_usage_by_leaf_uuid(leaf_uuid, project_dir);
+                return find_usage_by_leaf_uuid(leaf_uuid, project_dir)
+                    .map(TranscriptUsage::single);
             }
         }
     }
 
-    // Normal case: find the last assistant message in current file
+    usage_from_lines(&lines, max_turns)
+}
+
+/// Walk back over the main conversation, keeping the context size of the last response
+/// in each of the last `max_turns` turns; subagent (sidechain) entries are skipped
+fn usage_from_lines(lines: &[String], max_turns: usize) -> Option<TranscriptUsage> {
+    let mut last = None;
+    let mut history = Vec::new();
+    // Whether the latest response of the turn being walked is still to be recorded
+    let mut turn_open = true;
     for line in lines.iter().rev() {
         let line = line.trim();
         if line.is_empty() {
             continue;
         }
+        let Ok(entry) = serde_json::from_str::<TranscriptEntry>(line) else {
+            continue;
+        };
+        if entry.is_sidechain {
+            continue;
+        }
+        if entry.is_user_prompt() {
+            turn_open = true;
+            continue;
+        }
+        if entry.r#type.as_deref() != Some("assistant") {
+            continue;
+        }
+        let Some(raw_usage) = entry.message.and_then(|m| m.usage) else {
+            continue;
+        };
 
-        if let Ok(entry) = serde_json::from_str::<TranscriptEntry>(line) {
-            if entry.r#type.as_deref() == Some("assistant") {
-                if let Some(message) = &entry.message {
-                    if let Some(raw_usage) = &message.usage {
-                        let normalized = raw_usage.clone().normalize();
-                        return Some(normalized.display_tokens());
-                    }
-                }
-            }
+        let normalized = raw_usage.normalize();
+        if turn_open {
+            history.push(normalized.display_tokens());
+            turn_open = false;
+        }
+        last.get_or_insert(normalized);
+        if history.len() >= max_turns {
+            break;
         }
     }
 
-    None
+    history.reverse();
+    Some(TranscriptUsage {
+        last: last?,
+        history,
+    })
 }
 
-fn find_usage_by_leaf_uuid(leaf_uuid: &str, project_dir: &Path) -> Option<u32> {
+fn find_usage_by_leaf_uuid(leaf_uuid: &str, project_dir: &Path) -> Option<NormalizedUsage> {
     // Search for the leafUuid across all session files in the project directory
     let entries = fs::read_dir(project_dir).ok()?;
 
@@ -167,7 +314,7 @@ fn find_usage_by_leaf_uuid(leaf_uuid: &str, project_dir: &Path) -> Option<u32> {
     None
 }
 
-fn search_uuid_in_file(path: &Path, target_uuid: &str) -> Option<u32> {
+fn search_uuid_in_file(path: &Path, target_uuid: &str) -> Option<NormalizedUsage> {
     let file = fs::File::open(path).ok()?;
     let reader = BufReader::new(file);
     let lines: Vec<String> = reader
@@ -190,8 +337,7 @@ fn search_uuid_in_file(path: &Path, target_uuid: &str) -> Option<u32> {
                         // Direct assistant message with usage
                         if let Some(message) = &entry.message {
                             if let Some(raw_usage) = &message.usage {
-                                let normalized = raw_usage.clone().normalize();
-                                return Some(normalized.display_tokens());
+                                return Some(raw_usage.clone().normalize());
                             }
                         }
                     } else if entry.r#type.as_deref() == Some("user") {
@@ -209,7 +355,7 @@ fn search_uuid_in_file(path: &Path, target_uuid: &str) -> Option<u32> {
     None
 }
 
-fn find_assistant_message_by_uuid(lines: &[String], target_uuid: &str) -> Option<u32> {
+fn find_assistant_message_by_uuid(lines: &[String], target_uuid: &str) -> Option<NormalizedUsage> {
     for line in lines {
         let line = line.trim();
         if line.is_empty() {
@@ -221,8 +367,7 @@ fn find_assistant_message_by_uuid(lines: &[String], target_uuid: &str) -> Option
                 if uuid == target_uuid && entry.r#type.as_deref() == Some("assistant") {
                     if let Some(message) = &entry.message {
                         if let Some(raw_usage) = &message.usage {
-                            let normalized = raw_usage.clone().normalize();
-                            return Some(normalized.display_tokens());
+                            return Some(raw_usage.clone().normalize());
                         }
                     }
                 }
@@ -233,7 +378,10 @@ fn find_assistant_message_by_uuid(lines: &[String], target_uuid: &str) -> Option
     None
 }
 
-fn try_find_usage_from_project_history(transcript_path: &Path) -> Option<u32> {
+fn try_find_usage_from_project_history(
+    transcript_path: &Path,
+    max_turns: usize,
+) -> Option<TranscriptUsage> {
     let project_dir = transcript_path.parent()?;
 
     // Find the most recent session file in the project directory
@@ -263,10 +411,81 @@ fn try_find_usage_from_project_history(transcript_path: &Path) -> Option<u32> {
 
     // Try to find usage from the most recent session
     for session_path in &session_files {
-        if let Some(usage) = try_parse_transcript_file(session_path) {
+        if let Some(usage) = try_parse_transcript_file(session_path, max_turns) {
             return Some(usage);
         }
     }
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(history: Vec<u32>) -> TranscriptUsage {
+        TranscriptUsage {
+            last: NormalizedUsage::default(),
+            history,
+        }
+    }
+
+    #[test]
+    fn growth_is_averaged_over_turns() {
+        assert_eq!(
+            usage(vec![1000, 1500, 3000]).growth_per_turn(),
+            Some(1000.0)
+        );
+        assert_eq!(usage(vec![1000]).growth_per_turn(), None);
+        assert_eq!(usage(Vec::new()).growth_per_turn(), None);
+    }
+
+    #[test]
+    fn growth_is_measured_after_the_last_compaction() {
+        assert_eq!(
+            usage(vec![5000, 9000, 2000, 2600]).growth_per_turn(),
+            Some(600.0)
+        );
+        assert_eq!(usage(vec![5000, 9000, 2000]).growth_per_turn(), None);
+    }
+
+    fn assistant(id: &str, input_tokens: u32, sidechain: bool) -> String {
+        format!(
+            r#"{{"type":"assistant","isSidechain":{},"message":{{"id":"{}","usage":{{"input_tokens":{},"output_tokens":0}}}}}}"#,
+            sidechain, id, input_tokens
+        )
+    }
+
+    fn prompt() -> String {
+        r#"{"type":"user","message":{"role":"user","content":"next step"}}"#.to_string()
+    }
+
+    fn tool_result() -> String {
+        r#"{"type":"user","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"t","content":"ok"}]}}"#.to_string()
+    }
+
+    #[test]
+    fn turns_end_at_user_prompts_and_skip_sidechains() {
+        let lines = vec![
+            prompt(),
+            assistant("a", 1000, false),
+            prompt(),
+            assistant("b", 1200, false),
+            tool_result(),
+            assistant("c", 1500, false),
+            assistant("sub", 90000, true),
+            prompt(),
+            assistant("d", 2000, false),
+            tool_result(),
+            assistant("e", 2500, false),
+        ];
+
+        let usage = usage_from_lines(&lines, 10).unwrap();
+        assert_eq!(usage.history, vec![1000, 1500, 2500]);
+        assert_eq!(usage.last.input_tokens, 2500);
+        assert_eq!(usage.growth_per_turn(), Some(750.0));
+
+        let recent = usage_from_lines(&lines, 2).unwrap();
+        assert_eq!(recent.history, vec![1500, 2500]);
+    }
+}
@@ -1,38 +1,100 @@
 use super::{Segment, SegmentData};
-use crate::config::{InputData, SegmentId};
+use crate::config::{InputData, SegmentConfig, SegmentId};
+use crate::core::ledger;
 use std::collections::HashMap;
 
+/// Where the displayed cost comes from
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CostSource {
+    /// `cost.total_cost_usd` reported by Claude Code
+    #[default]
+    Claude,
+    /// Computed locally from the transcript and models.toml prices
+    Ledger,
+    /// Claude Code's figure with the local estimate alongside for cross-checking
+    Both,
+}
+
+impl CostSource {
+    pub fn from_option(value: &str) -> Self {
+        match value {
+            "ledger" | "local" => CostSource::Ledger,
+            "both" => CostSource::Both,
+            _ => CostSource::Claude,
+        }
+    }
+
+    /// Source configured by the segment's `source` option
+    pub fn of_segment(config: &SegmentConfig) -> Self {
+        config
+            .options
+            .get("source")
+            .and_then(|v| v.as_str())
+            .map(Self::from_option)
+            .unwrap_or_default()
+    }
+
+    pub fn uses_ledger(&self) -> bool {
+        matches!(self, CostSource::Ledger | CostSource::Both)
+    }
+}
+
 #[derive(Default)]
-pub struct CostSegment;
+pub struct CostSegment {
+    source: CostSource,
+}
 
 impl CostSegment {
     pub fn new() -> Self {
-        Self
+        Self::default()
+    }
+
+    pub fn with_source(mut self, source: CostSource) -> Self {
+        self.source = source;
+        self
+    }
+
+    fn format_cost(cost: f64) -> String {
+        if cost == 0.0 || cost < 0.01 {
+            "$0".to_string()
+        } else {
+            format!("${:.2}", cost)
+        }
     }
 }
 
 impl Segment for CostSegment {
     fn collect(&self, input: &InputData) -> Option<SegmentData> {
-        let cost_data = input.cost.as_ref()?;
+        let claude_cost = input.cost.as_ref().and_then(|c| c.total_cost_usd);
+        let ledger_cost = match self.source {
+            CostSource::Claude => None,
+            CostSource::Ledger | CostSource::Both => ledger::session_id_for(input)
+                .and_then(|id| ledger::load_session(&id))
+                .map(|session| session.total_cost()),
+        };
 
         // Primary display: total cost
-        let primary = if let Some(cost) = cost_data.total_cost_usd {
-            if cost == 0.0 || cost < 0.01 {
-                "$0".to_string()
-            } else {
-                format!("${:.2}", cost)
-            }
-        } else {
-            return None;
+        let (primary, secondary) = match self.source {
+            CostSource::Claude => (Self::format_cost(claude_cost?), String::new()),
+            CostSource::Ledger => (Self::format_cost(ledger_cost?), String::new()),
+            CostSource::Both => match (claude_cost, ledger_cost) {
+                (Some(claude), Some(local)) => (
+                    Self::format_cost(claude),
+                    format!("≈{}", Self::format_cost(local)),
+                ),
+                (Some(claude), None) => (Self::format_cost(claude), String::new()),
+                (None, Some(local)) => (format!("≈{}", Self::format_cost(local)), String::new()),
+                (None, None) => return None,
+            },
         };
 
-        // Secondary display: empty for cost segment
-        let secondary = String::new();
-
         let mut metadata = HashMap::new();
-        if let Some(cost) = cost_data.total_cost_usd {
+        if let Some(cost) = claude_cost {
             metadata.insert("cost".to_string(), cost.to_string());
         }
+        if let Some(cost) = ledger_cost {
+            metadata.insert("ledger_cost".to_string(), cost.to_string());
+        }
 
         Some(SegmentData {
             primary,
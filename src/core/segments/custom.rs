@@ -0,0 +1,260 @@
+use super::{Segment, SegmentData};
+use crate::config::{InputData, SegmentId};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// 默认超时：1 秒，避免拖慢状态栏
+const DEFAULT_TIMEOUT_MS: u64 = 1000;
+
+/// 自定义命令段落：运行 shell 命令，显示输出的第一行
+pub struct CustomSegment {
+    name: String,
+    command: String,
+    cwd: Option<String>,
+    timeout: Duration,
+    /// 缓存有效期（秒），0 表示每次都运行
+    cache_ttl: u64,
+    /// 是否把 Claude Code 的输入 JSON 写入命令的 stdin
+    pass_input: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedOutput {
+    output: String,
+    exit_code: i32,
+}
+
+impl CustomSegment {
+    pub fn from_options(options: &HashMap<String, serde_json::Value>) -> Option<Self> {
+        let str_option = |key: &str| {
+            options
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+        };
+        let command = str_option("command")?;
+
+        Some(Self {
+            name: str_option("name").unwrap_or_else(|| "custom".to_string()),
+            command,
+            cwd: str_option("cwd"),
+            timeout: Duration::from_millis(
+                options
+                    .get("timeout_ms")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(DEFAULT_TIMEOUT_MS),
+            ),
+            cache_ttl: options
+                .get("cache_ttl")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(0),
+            pass_input: options
+                .get("stdin")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+        })
+    }
+
+    /// 工作目录：默认为 workspace，相对路径基于 workspace，支持 `~`
+    fn working_dir(&self, input: &InputData) -> PathBuf {
+        let workspace = Path::new(&input.workspace.current_dir);
+        match self.cwd.as_deref() {
+            None => workspace.to_path_buf(),
+            // 绝对路径（包括展开后的 `~`）会替换 workspace
+            Some(dir) => workspace.join(crate::config::secrets::expand_home(dir)),
+        }
+    }
+
+    fn cache_file(&self, cwd: &Path) -> Option<PathBuf> {
+        let mut hasher = Sha256::new();
+        hasher.update(self.command.as_bytes());
+        hasher.update([0]);
+        hasher.update(cwd.to_string_lossy().as_bytes());
+        let key = format!("{:x}", hasher.finalize());
+
+        let dir = crate::utils::paths::cache_dir()?.join("custom");
+        fs::create_dir_all(&dir).ok()?;
+        Some(dir.join(format!("{}.json", &key[..16])))
+    }
+
+    fn read_cache(&self, path: &Path) -> Option<CachedOutput> {
+        let modified = fs::metadata(path).ok()?.modified().ok()?;
+        let age = SystemTime::now().duration_since(modified).ok()?;
+        if age.as_secs() >= self.cache_ttl {
+            return None;
+        }
+        serde_json::from_str(&fs::read_to_string(path).ok()?).ok()
+    }
+
+    /// 运行命令；超时后终止进程并返回 None
+    fn run(&self, cwd: &Path, input: &InputData) -> Option<CachedOutput> {
+        let mut command = crate::utils::command::shell(&self.command);
+        command.current_dir(cwd);
+        // 由守护进程渲染时使用调用方的环境变量
+        if let Some(env) = &input.env {
+            command.env_clear().envs(env);
+        }
+        let stdin = self.pass_input.then(|| input.raw_json.clone());
+        let output = crate::utils::command::run_with_timeout(&mut command, stdin, self.timeout)?;
+
+        let first_line = output
+            .stdout
+            .lines()
+            .map(str::trim_end)
+            .find(|line| !line.trim().is_empty())
+            .unwrap_or_default()
+            .to_string();
+        Some(CachedOutput {
+            output: first_line,
+            exit_code: output.status.code().unwrap_or(-1),
+        })
+    }
+}
+
+impl Segment for CustomSegment {
+    fn collect(&self, input: &InputData) -> Option<SegmentData> {
+        let cwd = self.working_dir(input);
+        let cache_file = if self.cache_ttl > 0 {
+            self.cache_file(&cwd)
+        } else {
+            None
+        };
+
+        let result = match cache_file.as_deref().and_then(|path| self.read_cache(path)) {
+            Some(cached) => cached,
+            None => {
+                let result = self.run(&cwd, input)?;
+                if let Some(path) = &cache_file {
+                    if let Ok(json) = serde_json::to_string(&result) {
+                        let _ = fs::write(path, json);
+                    }
+                }
+                result
+            }
+        };
+
+        // 没有输出时隐藏段落；非零退出码通过 exit_code 元数据交给 rules 处理
+        if result.output.is_empty() {
+            return None;
+        }
+
+        let mut metadata = HashMap::new();
+        metadata.insert("name".to_string(), self.name.clone());
+        metadata.insert("output".to_string(), result.output.clone());
+        metadata.insert("exit_code".to_string(), result.exit_code.to_string());
+
+        Some(SegmentData {
+            primary: result.output,
+            secondary: String::new(),
+            metadata,
+        })
+    }
+
+    fn id(&self) -> SegmentId {
+        SegmentId::Custom
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn options(value: serde_json::Value) -> HashMap<String, serde_json::Value> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn input(dir: &str) -> InputData {
+        serde_json::from_value(json!({
+            "model": { "id": "m", "display_name": "M" },
+            "workspace": { "current_dir": dir },
+            "transcript_path": "",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn parses_options_with_defaults() {
+        assert!(CustomSegment::from_options(&options(json!({ "name": "x" }))).is_none());
+        assert!(CustomSegment::from_options(&options(json!({ "command": "  " }))).is_none());
+
+        let segment = CustomSegment::from_options(&options(json!({ "command": "date" }))).unwrap();
+        assert_eq!(segment.name, "custom");
+        assert_eq!(segment.timeout, Duration::from_millis(DEFAULT_TIMEOUT_MS));
+        assert_eq!(segment.cache_ttl, 0);
+        assert!(!segment.pass_input);
+
+        let segment = CustomSegment::from_options(&options(json!({
+            "name": "todo",
+            "command": "todo count",
+            "cwd": "sub",
+            "timeout_ms": 250,
+            "cache_ttl": 30,
+            "stdin": true,
+        })))
+        .unwrap();
+        assert_eq!(segment.name, "todo");
+        assert_eq!(segment.timeout, Duration::from_millis(250));
+        assert_eq!(segment.cache_ttl, 30);
+        assert!(segment.pass_input);
+        assert_eq!(
+            segment.working_dir(&input("/work")),
+            Path::new("/work").join("sub")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn shows_first_non_empty_line_and_exit_code() {
+        let segment = CustomSegment::from_options(&options(json!({
+            "command": "echo; echo first; echo second; exit 2",
+        })))
+        .unwrap();
+        let data = segment.collect(&input("/")).unwrap();
+        assert_eq!(data.primary, "first");
+        assert_eq!(data.metadata["exit_code"], "2");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hides_commands_that_time_out() {
+        let segment = CustomSegment::from_options(&options(json!({
+            "command": "sleep 5; echo late",
+            "timeout_ms": 100,
+        })))
+        .unwrap();
+        let started = std::time::Instant::now();
+        assert!(segment.collect(&input("/")).is_none());
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn cached_output_expires_after_ttl() {
+        let segment = CustomSegment::from_options(&options(json!({
+            "command": "date",
+            "cache_ttl": 60,
+        })))
+        .unwrap();
+        let path =
+            std::env::temp_dir().join(format!("byebyecode-custom-{}.json", std::process::id()));
+        fs::write(&path, r#"{"output":"cached","exit_code":0}"#).unwrap();
+        assert_eq!(segment.read_cache(&path).unwrap().output, "cached");
+
+        let stale = SystemTime::now() - Duration::from_secs(61);
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(stale)
+            .unwrap();
+        let expired = segment.read_cache(&path).is_none();
+        let _ = fs::remove_file(&path);
+        assert!(expired);
+    }
+}
@@ -1,13 +1,72 @@
 use super::{Segment, SegmentData};
 use crate::config::{InputData, SegmentId};
 use std::collections::HashMap;
+use std::path::Path;
+
+/// How the directory segment renders the workspace path
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DirectoryMode {
+    /// Last path component only
+    #[default]
+    Name,
+    /// Full path
+    Full,
+    /// Full path with the home directory shown as `~`
+    Home,
+    /// `~` path with every component but the last abbreviated (`~/w/p/byebyecode`)
+    Fish,
+    /// Path relative to the git root, starting with the repository name
+    Repo,
+}
+
+impl DirectoryMode {
+    fn parse(value: &str) -> Self {
+        match value.trim().to_lowercase().as_str() {
+            "full" => Self::Full,
+            "home" => Self::Home,
+            "fish" => Self::Fish,
+            "repo" => Self::Repo,
+            _ => Self::Name,
+        }
+    }
+}
 
 #[derive(Default)]
-pub struct DirectorySegment;
+pub struct DirectorySegment {
+    mode: DirectoryMode,
+    /// Keep only this many trailing components; 0 keeps all
+    max_depth: usize,
+    /// (path prefix, replacement), longest prefix first
+    aliases: Vec<(String, String)>,
+}
 
 impl DirectorySegment {
     pub fn new() -> Self {
-        Self
+        Self::default()
+    }
+
+    pub fn from_options(options: &HashMap<String, serde_json::Value>) -> Self {
+        let mut aliases: Vec<(String, String)> = options
+            .get("aliases")
+            .and_then(|v| v.as_object())
+            .into_iter()
+            .flatten()
+            .filter_map(|(path, alias)| Some((path.clone(), alias.as_str()?.to_string())))
+            .collect();
+        aliases.sort_by_key(|(path, _)| std::cmp::Reverse(components(path).len()));
+
+        Self {
+            mode: options
+                .get("mode")
+                .and_then(|v| v.as_str())
+                .map(DirectoryMode::parse)
+                .unwrap_or_default(),
+            max_depth: options
+                .get("max_depth")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(0) as usize,
+            aliases,
+        }
     }
 
     /// Extract directory name from path, handling both Unix and Windows separators
@@ -34,6 +93,141 @@ impl DirectorySegment {
             result.to_string()
         }
     }
+
+    /// First configured alias whose path is a prefix of `parts`
+    fn apply_alias<'a>(&self, parts: &[&'a str], sep: char) -> Option<(String, Vec<&'a str>)> {
+        self.aliases.iter().find_map(|(path, alias)| {
+            let rest = strip_components(parts, &components(path), sep == '\\')?;
+            let prefix = if alias.is_empty() || alias.ends_with([':', '/', '\\']) {
+                alias.clone()
+            } else {
+                format!("{}{}", alias, sep)
+            };
+            Some((prefix, rest.to_vec()))
+        })
+    }
+
+    /// `~` in place of the home directory
+    fn apply_home<'a>(parts: &[&'a str], sep: char) -> Option<(String, Vec<&'a str>)> {
+        let home = dirs::home_dir()?;
+        let home = home.to_string_lossy();
+        let rest = strip_components(parts, &components(&home), sep == '\\')?;
+        Some((format!("~{}", sep), rest.to_vec()))
+    }
+
+    /// Repository name followed by the path inside the repository
+    fn apply_repo<'a>(path: &str, parts: &[&'a str], sep: char) -> Option<(String, Vec<&'a str>)> {
+        let root = Path::new(path)
+            .ancestors()
+            .find(|dir| dir.join(".git").exists())?;
+        let root = root.to_string_lossy();
+        let root_parts = components(&root);
+        let rest = strip_components(parts, &root_parts, sep == '\\')?;
+        let name = root_parts.last()?;
+        Some((format!("{}{}", name, sep), rest.to_vec()))
+    }
+
+    fn format_path(&self, path: &str) -> String {
+        let sep = separator(path);
+        let parts = components(path);
+        let absolute = || {
+            let root = if path.starts_with(['/', '\\']) {
+                sep.to_string()
+            } else {
+                String::new()
+            };
+            (root, parts.clone())
+        };
+
+        let (prefix, mut rest) = match self.mode {
+            DirectoryMode::Name => {
+                // An alias for exactly this directory replaces its name
+                return match self.apply_alias(&parts, sep) {
+                    Some((alias, rest)) if rest.is_empty() && !alias.is_empty() => alias
+                        .strip_suffix(sep)
+                        .filter(|a| !a.is_empty())
+                        .map(str::to_string)
+                        .unwrap_or(alias),
+                    _ => Self::extract_directory_name(path),
+                };
+            }
+            DirectoryMode::Full => self.apply_alias(&parts, sep).unwrap_or_else(absolute),
+            DirectoryMode::Home | DirectoryMode::Fish => self
+                .apply_alias(&parts, sep)
+                .or_else(|| Self::apply_home(&parts, sep))
+                .unwrap_or_else(absolute),
+            DirectoryMode::Repo => Self::apply_repo(path, &parts, sep)
+                .or_else(|| self.apply_alias(&parts, sep))
+                .or_else(|| Self::apply_home(&parts, sep))
+                .unwrap_or_else(absolute),
+        };
+
+        let mut prefix = prefix;
+        if self.max_depth > 0 && rest.len() > self.max_depth {
+            rest.drain(..rest.len() - self.max_depth);
+            prefix.push('…');
+            prefix.push(sep);
+        }
+
+        let mut rest: Vec<String> = rest.into_iter().map(str::to_string).collect();
+        if self.mode == DirectoryMode::Fish {
+            let last = rest.len().saturating_sub(1);
+            for part in &mut rest[..last] {
+                *part = abbreviate(part);
+            }
+        }
+
+        if rest.is_empty() {
+            if prefix.len() > sep.len_utf8() && prefix.ends_with(sep) {
+                prefix.pop();
+            }
+            return prefix;
+        }
+        format!("{}{}", prefix, rest.join(&sep.to_string()))
+    }
+}
+
+/// Separator used for output: backslash for Windows-style paths, slash otherwise
+fn separator(path: &str) -> char {
+    if path.contains('\\') && !path.contains('/') {
+        '\\'
+    } else {
+        '/'
+    }
+}
+
+/// Path components split on both separators, without empty parts
+fn components(path: &str) -> Vec<&str> {
+    path.split(['/', '\\']).filter(|p| !p.is_empty()).collect()
+}
+
+/// Remainder of `parts` after `prefix`; Windows paths compare case-insensitively
+fn strip_components<'a, 'b>(
+    parts: &'b [&'a str],
+    prefix: &[&str],
+    ignore_case: bool,
+) -> Option<&'b [&'a str]> {
+    if prefix.is_empty() || parts.len() < prefix.len() {
+        return None;
+    }
+    let matches = parts.iter().zip(prefix).all(|(a, b)| {
+        if ignore_case {
+            a.eq_ignore_ascii_case(b)
+        } else {
+            a == b
+        }
+    });
+    matches.then(|| &parts[prefix.len()..])
+}
+
+/// First character, or the first two for dot-directories (`.config` -> `.c`);
+/// drive letters (`C:`) stay whole
+fn abbreviate(part: &str) -> String {
+    if part.len() == 2 && part.ends_with(':') {
+        return part.to_string();
+    }
+    let take = if part.starts_with('.') { 2 } else { 1 };
+    part.chars().take(take).collect()
 }
 
 impl Segment for DirectorySegment {
@@ -42,13 +236,16 @@ impl Segment for DirectorySegment {
 
         // Handle cross-platform path separators manually for better compatibility
         let dir_name = Self::extract_directory_name(current_dir);
+        let path = self.format_path(current_dir);
 
         // Store the full path in metadata for potential use
         let mut metadata = HashMap::new();
         metadata.insert("full_path".to_string(), current_dir.clone());
+        metadata.insert("name".to_string(), dir_name);
+        metadata.insert("path".to_string(), path.clone());
 
         Some(SegmentData {
-            primary: dir_name,
+            primary: path,
             secondary: String::new(),
             metadata,
         })
@@ -58,3 +255,103 @@ impl Segment for DirectorySegment {
         SegmentId::Directory
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn segment(options: serde_json::Value) -> DirectorySegment {
+        let options: HashMap<String, serde_json::Value> = serde_json::from_value(options).unwrap();
+        DirectorySegment::from_options(&options)
+    }
+
+    #[test]
+    fn windows_paths_keep_backslashes() {
+        let full = segment(json!({ "mode": "full" }));
+        assert_eq!(
+            full.format_path(r"C:\Users\me\work\proj"),
+            r"C:\Users\me\work\proj"
+        );
+        let fish = segment(json!({ "mode": "fish" }));
+        assert_eq!(
+            fish.format_path(r"C:\Users\me\.config\nvim"),
+            r"C:\U\m\.c\nvim"
+        );
+        let name = segment(json!({}));
+        assert_eq!(name.format_path(r"C:\Users\me\proj"), "proj");
+        // Mixed separators render with forward slashes
+        assert_eq!(full.format_path(r"C:/Users\me/proj"), "C:/Users/me/proj");
+    }
+
+    #[test]
+    fn windows_aliases_match_case_insensitively() {
+        let full = segment(json!({
+            "mode": "full",
+            "aliases": { r"C:\Users\Me\Work": "work" },
+        }));
+        assert_eq!(
+            full.format_path(r"c:\users\me\work\api\src"),
+            r"work\api\src"
+        );
+        // Unix paths stay case-sensitive
+        let unix = segment(json!({ "mode": "full", "aliases": { "/Home/me": "me" } }));
+        assert_eq!(unix.format_path("/home/me/proj"), "/home/me/proj");
+    }
+
+    #[test]
+    fn longest_alias_prefix_wins() {
+        let full = segment(json!({
+            "mode": "full",
+            "aliases": { "/srv": "srv", "/srv/apps/billing": "billing", "/srv/apps": "apps" },
+        }));
+        assert_eq!(full.format_path("/srv/apps/billing/api"), "billing/api");
+        assert_eq!(full.format_path("/srv/apps/web"), "apps/web");
+        assert_eq!(full.format_path("/srv/apps"), "apps");
+        // A prefix must match whole components
+        assert_eq!(full.format_path("/srv/apps-old"), "srv/apps-old");
+    }
+
+    #[test]
+    fn alias_names_the_directory_in_name_mode() {
+        let name = segment(json!({ "aliases": { "/home/me/src/monorepo": "mono" } }));
+        assert_eq!(name.format_path("/home/me/src/monorepo"), "mono");
+        assert_eq!(name.format_path("/home/me/src/monorepo/web"), "web");
+    }
+
+    #[test]
+    fn alias_then_depth_limit_and_abbreviation() {
+        let fish = segment(json!({
+            "mode": "fish",
+            "max_depth": 2,
+            "aliases": { "/home/me/src": "src" },
+        }));
+        assert_eq!(
+            fish.format_path("/home/me/src/org/team/project"),
+            "src/…/t/project"
+        );
+        let full = segment(json!({ "mode": "full", "max_depth": 2 }));
+        assert_eq!(full.format_path("/a/b/c/d"), "/…/c/d");
+        assert_eq!(full.format_path("/"), "/");
+        assert_eq!(segment(json!({})).format_path("/"), "root");
+    }
+
+    #[test]
+    fn home_is_shown_as_tilde() {
+        let Some(home) = dirs::home_dir() else {
+            return;
+        };
+        let home_mode = segment(json!({ "mode": "home" }));
+        let path = home.join("work").join("proj");
+        assert_eq!(
+            home_mode.format_path(&path.to_string_lossy()),
+            format!("~{0}work{0}proj", std::path::MAIN_SEPARATOR)
+        );
+        // An alias takes precedence over the home directory
+        let aliased = segment(json!({
+            "mode": "home",
+            "aliases": { home.join("work").to_string_lossy(): "W:" },
+        }));
+        assert_eq!(aliased.format_path(&path.to_string_lossy()), "W:proj");
+    }
+}
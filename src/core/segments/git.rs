@@ -1,26 +1,56 @@
 use super::{Segment, SegmentData};
 use crate::config::{InputData, SegmentId};
 use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct GitInfo {
     pub branch: String,
     pub status: GitStatus,
     pub ahead: u32,
     pub behind: u32,
     pub sha: Option<String>,
+    pub upstream: Option<String>,
+    pub staged: u32,
+    pub modified: u32,
+    pub untracked: u32,
+    pub conflicted: u32,
+    /// 有改动的子模块数量
+    pub submodules: u32,
+    pub stash: u32,
+    pub operation: Option<GitOperation>,
+    pub tag: Option<String>,
+    /// 当前目录位于 `git worktree add` 创建的工作树中
+    pub worktree: bool,
+    /// 当前仓库是另一个仓库的子模块
+    pub submodule: bool,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Default, PartialEq)]
 pub enum GitStatus {
+    #[default]
     Clean,
     Dirty,
     Conflicts,
 }
 
+/// 进行中的 git 操作
+#[derive(Debug, PartialEq)]
+pub struct GitOperation {
+    pub kind: &'static str,
+    /// 变基进度，如 (2, 5)
+    pub progress: Option<(u32, u32)>,
+}
+
 pub struct GitSegment {
     show_sha: bool,
+    show_counts: bool,
+    show_stash: bool,
+    show_operation: bool,
+    show_tag: bool,
+    show_upstream: bool,
 }
 
 impl Default for GitSegment {
@@ -31,7 +61,14 @@ impl Default for GitSegment {
 
 impl GitSegment {
     pub fn new() -> Self {
-        Self { show_sha: false }
+        Self {
+            show_sha: false,
+            show_counts: false,
+            show_stash: true,
+            show_operation: true,
+            show_tag: false,
+            show_upstream: false,
+        }
     }
 
     pub fn with_sha(mut self, show_sha: bool) -> Self {
@@ -39,136 +76,210 @@ impl GitSegment {
         self
     }
 
-    fn get_git_info(&self, working_dir: &str) -> Option<GitInfo> {
-        if !self.is_git_repository(working_dir) {
-            return None;
+    /// 从段落配置的 options 读取各个显示开关
+    pub fn from_options(options: &HashMap<String, serde_json::Value>) -> Self {
+        let defaults = Self::new();
+        let flag = |key: &str, default: bool| {
+            options
+                .get(key)
+                .and_then(|v| v.as_bool())
+                .unwrap_or(default)
+        };
+        Self {
+            show_sha: flag("show_sha", defaults.show_sha),
+            show_counts: flag("show_counts", defaults.show_counts),
+            show_stash: flag("show_stash", defaults.show_stash),
+            show_operation: flag("show_operation", defaults.show_operation),
+            show_tag: flag("show_tag", defaults.show_tag),
+            show_upstream: flag("show_upstream", defaults.show_upstream),
         }
+    }
 
-        let branch = self
-            .get_branch(working_dir)
-            .unwrap_or_else(|| "detached".to_string());
-        let status = self.get_status(working_dir);
-        let (ahead, behind) = self.get_ahead_behind(working_dir);
-        let sha = if self.show_sha {
-            self.get_sha(working_dir)
-        } else {
-            None
-        };
+    fn get_git_info(&self, working_dir: &str) -> Option<GitInfo> {
+        let output = self.run_status(working_dir)?;
+        let mut info = parse_porcelain_v2(&output);
 
-        Some(GitInfo {
-            branch,
-            status,
-            ahead,
-            behind,
-            sha,
-        })
-    }
+        if let Some(git_dir) = find_git_dir(Path::new(working_dir)) {
+            info.operation = detect_operation(&git_dir);
+            info.worktree = git_dir.join("commondir").is_file();
+            // 子模块的 git 目录位于上级仓库的 `.git/modules/` 下
+            let components: Vec<_> = git_dir.components().map(|c| c.as_os_str()).collect();
+            info.submodule = components
+                .windows(2)
+                .any(|pair| pair[0] == ".git" && pair[1] == "modules");
+        }
 
-    fn is_git_repository(&self, working_dir: &str) -> bool {
-        Command::new("git")
-            .args(["--no-optional-locks", "rev-parse", "--git-dir"])
-            .current_dir(working_dir)
-            .output()
-            .map(|output| output.status.success())
-            .unwrap_or(false)
+        // 标签不在 status 输出中，只在启用时额外查询
+        if self.show_tag && info.sha.is_some() {
+            info.tag = self.get_tag(working_dir);
+        }
+
+        Some(info)
     }
 
-    fn get_branch(&self, working_dir: &str) -> Option<String> {
-        if let Ok(output) = Command::new("git")
-            .args(["--no-optional-locks", "branch", "--show-current"])
-            .current_dir(working_dir)
-            .output()
-        {
+    /// 一次 `git status --porcelain=v2 --branch` 获取全部状态；
+    /// git 2.35 之前不支持 `--show-stash`，失败时去掉该参数重试
+    fn run_status(&self, working_dir: &str) -> Option<String> {
+        let base = [
+            "--no-optional-locks",
+            "status",
+            "--porcelain=v2",
+            "--branch",
+        ];
+        for extra in [&["--show-stash"][..], &[]] {
+            let output = Command::new("git")
+                .args(base)
+                .args(extra)
+                .current_dir(working_dir)
+                .output()
+                .ok()?;
             if output.status.success() {
-                let branch = String::from_utf8(output.stdout).ok()?.trim().to_string();
-                if !branch.is_empty() {
-                    return Some(branch);
-                }
+                return String::from_utf8(output.stdout).ok();
             }
-        }
-
-        if let Ok(output) = Command::new("git")
-            .args(["--no-optional-locks", "symbolic-ref", "--short", "HEAD"])
-            .current_dir(working_dir)
-            .output()
-        {
-            if output.status.success() {
-                let branch = String::from_utf8(output.stdout).ok()?.trim().to_string();
-                if !branch.is_empty() {
-                    return Some(branch);
-                }
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if !stderr.contains("show-stash") {
+                return None;
             }
         }
-
         None
     }
 
-    fn get_status(&self, working_dir: &str) -> GitStatus {
+    fn get_tag(&self, working_dir: &str) -> Option<String> {
         let output = Command::new("git")
-            .args(["--no-optional-locks", "status", "--porcelain"])
+            .args([
+                "--no-optional-locks",
+                "tag",
+                "--points-at",
+                "HEAD",
+                "--sort=-creatordate",
+            ])
             .current_dir(working_dir)
-            .output();
+            .output()
+            .ok()?;
 
-        match output {
-            Ok(output) if output.status.success() => {
-                let status_text = String::from_utf8(output.stdout).unwrap_or_default();
+        if !output.status.success() {
+            return None;
+        }
+        String::from_utf8(output.stdout)
+            .ok()?
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .map(str::to_string)
+    }
+}
 
-                if status_text.trim().is_empty() {
-                    return GitStatus::Clean;
+/// 解析 `git status --porcelain=v2 --branch --show-stash` 的输出
+fn parse_porcelain_v2(output: &str) -> GitInfo {
+    let mut info = GitInfo {
+        branch: "detached".to_string(),
+        ..Default::default()
+    };
+
+    for line in output.lines() {
+        if let Some(header) = line.strip_prefix("# ") {
+            let (key, value) = header.split_once(' ').unwrap_or((header, ""));
+            match key {
+                "branch.oid" if value != "(initial)" => {
+                    info.sha = Some(value.chars().take(7).collect());
+                }
+                "branch.head" if value != "(detached)" => info.branch = value.to_string(),
+                "branch.upstream" => info.upstream = Some(value.to_string()),
+                "branch.ab" => {
+                    for part in value.split_whitespace() {
+                        if let Some(n) = part.strip_prefix('+') {
+                            info.ahead = n.parse().unwrap_or(0);
+                        } else if let Some(n) = part.strip_prefix('-') {
+                            info.behind = n.parse().unwrap_or(0);
+                        }
+                    }
                 }
+                "stash" => info.stash = value.parse().unwrap_or(0),
+                _ => {}
+            }
+            continue;
+        }
 
-                if status_text.contains("UU")
-                    || status_text.contains("AA")
-                    || status_text.contains("DD")
-                {
-                    GitStatus::Conflicts
-                } else {
-                    GitStatus::Dirty
+        let mut fields = line.split(' ');
+        match fields.next() {
+            // 普通变更 / 重命名或复制：`1 XY sub ...`、`2 XY sub ...`
+            Some("1") | Some("2") => {
+                let xy = fields.next().unwrap_or("..").as_bytes();
+                if xy.first().is_some_and(|&x| x != b'.') {
+                    info.staged += 1;
+                }
+                if xy.get(1).is_some_and(|&y| y != b'.') {
+                    info.modified += 1;
+                }
+                if fields.next().is_some_and(|sub| sub.starts_with('S')) {
+                    info.submodules += 1;
                 }
             }
-            _ => GitStatus::Clean,
+            Some("u") => info.conflicted += 1,
+            Some("?") => info.untracked += 1,
+            _ => {}
         }
     }
 
-    fn get_ahead_behind(&self, working_dir: &str) -> (u32, u32) {
-        let ahead = self.get_commit_count(working_dir, "@{u}..HEAD");
-        let behind = self.get_commit_count(working_dir, "HEAD..@{u}");
-        (ahead, behind)
-    }
+    info.status = if info.conflicted > 0 {
+        GitStatus::Conflicts
+    } else if info.staged + info.modified + info.untracked > 0 {
+        GitStatus::Dirty
+    } else {
+        GitStatus::Clean
+    };
+    info
+}
 
-    fn get_commit_count(&self, working_dir: &str, range: &str) -> u32 {
-        let output = Command::new("git")
-            .args(["--no-optional-locks", "rev-list", "--count", range])
-            .current_dir(working_dir)
-            .output();
+/// 根据 git 目录中的状态文件判断 rebase、merge、cherry-pick、revert 或 bisect
+fn detect_operation(git_dir: &Path) -> Option<GitOperation> {
+    let read_number =
+        |path: PathBuf| -> Option<u32> { fs::read_to_string(path).ok()?.trim().parse().ok() };
 
-        match output {
-            Ok(output) if output.status.success() => String::from_utf8(output.stdout)
-                .ok()
-                .and_then(|s| s.trim().parse().ok())
-                .unwrap_or(0),
-            _ => 0,
+    for (dir, next, last) in [
+        ("rebase-merge", "msgnum", "end"),
+        ("rebase-apply", "next", "last"),
+    ] {
+        let dir = git_dir.join(dir);
+        if dir.is_dir() {
+            let kind = if dir.join("applying").exists() {
+                "am"
+            } else {
+                "rebase"
+            };
+            let progress = read_number(dir.join(next)).zip(read_number(dir.join(last)));
+            return Some(GitOperation { kind, progress });
         }
     }
 
-    fn get_sha(&self, working_dir: &str) -> Option<String> {
-        let output = Command::new("git")
-            .args(["--no-optional-locks", "rev-parse", "--short=7", "HEAD"])
-            .current_dir(working_dir)
-            .output()
-            .ok()?;
+    [
+        ("MERGE_HEAD", "merge"),
+        ("CHERRY_PICK_HEAD", "cherry-pick"),
+        ("REVERT_HEAD", "revert"),
+        ("BISECT_LOG", "bisect"),
+    ]
+    .into_iter()
+    .find(|(file, _)| git_dir.join(file).exists())
+    .map(|(_, kind)| GitOperation {
+        kind,
+        progress: None,
+    })
+}
 
-        if output.status.success() {
-            let sha = String::from_utf8(output.stdout).ok()?.trim().to_string();
-            if sha.is_empty() {
-                None
-            } else {
-                Some(sha)
-            }
-        } else {
-            None
+/// 查找工作目录对应的 git 目录，支持工作树和子模块使用的 `.git` 文件
+pub fn find_git_dir(working_dir: &Path) -> Option<PathBuf> {
+    for dir in working_dir.ancestors() {
+        let dot_git = dir.join(".git");
+        if dot_git.is_dir() {
+            return Some(dot_git);
+        }
+        if dot_git.is_file() {
+            let content = fs::read_to_string(&dot_git).ok()?;
+            let target = content.trim().strip_prefix("gitdir:")?.trim();
+            return Some(dir.join(target));
         }
     }
+    None
 }
 
 impl Segment for GitSegment {
@@ -180,18 +291,70 @@ impl Segment for GitSegment {
         metadata.insert("status".to_string(), format!("{:?}", git_info.status));
         metadata.insert("ahead".to_string(), git_info.ahead.to_string());
         metadata.insert("behind".to_string(), git_info.behind.to_string());
+        metadata.insert("staged".to_string(), git_info.staged.to_string());
+        metadata.insert("modified".to_string(), git_info.modified.to_string());
+        metadata.insert("untracked".to_string(), git_info.untracked.to_string());
+        metadata.insert("conflicted".to_string(), git_info.conflicted.to_string());
+        metadata.insert("submodules".to_string(), git_info.submodules.to_string());
+        metadata.insert("stash".to_string(), git_info.stash.to_string());
+        metadata.insert("worktree".to_string(), git_info.worktree.to_string());
+        metadata.insert("submodule".to_string(), git_info.submodule.to_string());
 
         if let Some(ref sha) = git_info.sha {
             metadata.insert("sha".to_string(), sha.clone());
         }
+        if let Some(ref upstream) = git_info.upstream {
+            metadata.insert("upstream".to_string(), upstream.clone());
+        }
+        if let Some(ref tag) = git_info.tag {
+            metadata.insert("tag".to_string(), tag.clone());
+        }
+        if let Some(ref operation) = git_info.operation {
+            metadata.insert("operation".to_string(), operation.kind.to_string());
+            if let Some((step, total)) = operation.progress {
+                metadata.insert(
+                    "operation_progress".to_string(),
+                    format!("{}/{}", step, total),
+                );
+            }
+        }
+
+        let mut primary = git_info.branch.clone();
+        if self.show_upstream {
+            if let Some(ref upstream) = git_info.upstream {
+                primary = format!("{} → {}", primary, upstream);
+            }
+        }
 
-        let primary = git_info.branch;
         let mut status_parts = Vec::new();
 
-        match git_info.status {
-            GitStatus::Clean => status_parts.push("✓".to_string()),
-            GitStatus::Dirty => status_parts.push("●".to_string()),
-            GitStatus::Conflicts => status_parts.push("⚠".to_string()),
+        if self.show_operation {
+            if let Some(ref operation) = git_info.operation {
+                let mut text = operation.kind.to_uppercase();
+                if let Some((step, total)) = operation.progress {
+                    text.push_str(&format!(" {}/{}", step, total));
+                }
+                status_parts.push(text);
+            }
+        }
+
+        if self.show_counts && git_info.status != GitStatus::Clean {
+            for (count, symbol) in [
+                (git_info.conflicted, "✖"),
+                (git_info.staged, "+"),
+                (git_info.modified, "~"),
+                (git_info.untracked, "?"),
+            ] {
+                if count > 0 {
+                    status_parts.push(format!("{}{}", symbol, count));
+                }
+            }
+        } else {
+            match git_info.status {
+                GitStatus::Clean => status_parts.push("✓".to_string()),
+                GitStatus::Dirty => status_parts.push("●".to_string()),
+                GitStatus::Conflicts => status_parts.push("⚠".to_string()),
+            }
         }
 
         if git_info.ahead > 0 {
@@ -201,8 +364,20 @@ impl Segment for GitSegment {
             status_parts.push(format!("↓{}", git_info.behind));
         }
 
-        if let Some(ref sha) = git_info.sha {
-            status_parts.push(sha.clone());
+        if self.show_stash && git_info.stash > 0 {
+            status_parts.push(format!("≡{}", git_info.stash));
+        }
+
+        if self.show_tag {
+            if let Some(ref tag) = git_info.tag {
+                status_parts.push(format!("#{}", tag));
+            }
+        }
+
+        if self.show_sha {
+            if let Some(ref sha) = git_info.sha {
+                status_parts.push(sha.clone());
+            }
         }
 
         Some(SegmentData {
@@ -216,3 +391,58 @@ impl Segment for GitSegment {
         SegmentId::Git
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_branch_headers_and_entries() {
+        let output = "\
+# branch.oid 4f2a9c1e8b7d6a5f4e3d2c1b0a9f8e7d6c5b4a39
+# branch.head feature/status
+# branch.upstream origin/feature/status
+# branch.ab +3 -2
+# stash 4
+1 M. N... 100644 100644 100644 3b18e512 3b18e512 src/main.rs
+1 .M N... 100644 100644 100644 8c3f9a2d 8c3f9a2d README.md
+1 MM N... 100644 100644 100644 1a2b3c4d 5e6f7a8b Cargo.toml
+2 R. N... 100644 100644 100644 9d8c7b6a 9d8c7b6a R100 src/new name.rs\tsrc/old name.rs
+1 .M SC.. 160000 160000 160000 0f1e2d3c 0f1e2d3c vendor/lib
+u UU N... 100644 100644 100644 100644 aaaaaaa bbbbbbb ccccccc src/conflict.rs
+? notes.txt
+? tmp/
+! target/
+";
+        let info = parse_porcelain_v2(output);
+        assert_eq!(info.branch, "feature/status");
+        assert_eq!(info.sha.as_deref(), Some("4f2a9c1"));
+        assert_eq!(info.upstream.as_deref(), Some("origin/feature/status"));
+        assert_eq!((info.ahead, info.behind), (3, 2));
+        assert_eq!(info.stash, 4);
+        // src/main.rs、Cargo.toml 和重命名已暂存
+        assert_eq!(info.staged, 3);
+        // README.md、Cargo.toml 和子模块在工作区有改动
+        assert_eq!(info.modified, 3);
+        assert_eq!(info.submodules, 1);
+        assert_eq!(info.conflicted, 1);
+        assert_eq!(info.untracked, 2);
+        assert_eq!(info.status, GitStatus::Conflicts);
+    }
+
+    #[test]
+    fn parses_detached_and_initial_heads() {
+        let detached = parse_porcelain_v2(
+            "# branch.oid 0123456789abcdef0123456789abcdef01234567\n# branch.head (detached)\n",
+        );
+        assert_eq!(detached.branch, "detached");
+        assert_eq!(detached.sha.as_deref(), Some("0123456"));
+        assert_eq!(detached.status, GitStatus::Clean);
+
+        let initial = parse_porcelain_v2("# branch.oid (initial)\n# branch.head main\n? new.rs\n");
+        assert_eq!(initial.branch, "main");
+        assert_eq!(initial.sha, None);
+        assert_eq!(initial.upstream, None);
+        assert_eq!(initial.status, GitStatus::Dirty);
+    }
+}
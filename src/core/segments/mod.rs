@@ -1,8 +1,10 @@
+pub mod burn_rate;
 pub mod byebyecode_status;
 pub mod byebyecode_subscription;
 pub mod byebyecode_usage;
 pub mod context_window;
 pub mod cost;
+pub mod custom;
 pub mod directory;
 pub mod git;
 pub mod model;
@@ -28,8 +30,10 @@ pub struct SegmentData {
 }
 
 // Re-export all segment types
+pub use burn_rate::BurnRateSegment;
 pub use context_window::ContextWindowSegment;
 pub use cost::CostSegment;
+pub use custom::CustomSegment;
 pub use directory::DirectorySegment;
 pub use git::GitSegment;
 pub use model::ModelSegment;
@@ -66,12 +66,7 @@ impl UsageSegment {
     }
 
     fn get_cache_path() -> Option<std::path::PathBuf> {
-        let home = dirs::home_dir()?;
-        Some(
-            home.join(".claude")
-                .join("byebyecode")
-                .join(".api_usage_cache.json"),
-        )
+        Some(crate::utils::paths::byebyecode_home()?.join(".api_usage_cache.json"))
     }
 
     fn load_cache(&self) -> Option<ApiUsageCache> {
@@ -125,55 +120,33 @@ impl UsageSegment {
         "claude-code".to_string()
     }
 
-    fn get_proxy_from_settings() -> Option<String> {
-        let home = std::env::var("HOME")
-            .or_else(|_| std::env::var("USERPROFILE"))
-            .ok()?;
-        let settings_path = format!("{}/.claude/settings.json", home);
-
-        let content = std::fs::read_to_string(&settings_path).ok()?;
-        let settings: serde_json::Value = serde_json::from_str(&content).ok()?;
-
-        // Try HTTPS_PROXY first, then HTTP_PROXY
-        settings
-            .get("env")?
-            .get("HTTPS_PROXY")
-            .or_else(|| settings.get("env")?.get("HTTP_PROXY"))
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string())
-    }
-
     fn fetch_api_usage(
         &self,
         api_base_url: &str,
         token: &str,
+        http: &crate::config::HttpConfig,
         timeout_secs: u64,
     ) -> Option<ApiUsageResponse> {
         let url = format!("{}/api/oauth/usage", api_base_url);
         let user_agent = Self::get_claude_code_version();
 
-        let mut agent_builder = ureq::AgentBuilder::new();
-
-        // Configure proxy from Claude settings if available
-        if let Some(proxy_url) = Self::get_proxy_from_settings() {
-            if let Ok(proxy) = ureq::Proxy::new(&proxy_url) {
-                agent_builder = agent_builder.proxy(proxy);
-            }
-        }
-
-        let agent = agent_builder.build();
+        // Proxy, CA certificates and connect timeout come from the shared [http] settings
+        let client = crate::utils::http::client_builder(http)
+            .ok()?
+            .timeout(std::time::Duration::from_secs(timeout_secs))
+            .build()
+            .ok()?;
 
-        let response = agent
+        let response = client
             .get(&url)
-            .set("Authorization", &format!("Bearer {}", token))
-            .set("anthropic-beta", "oauth-2025-04-20")
-            .set("User-Agent", &user_agent)
-            .timeout(std::time::Duration::from_secs(timeout_secs))
-            .call()
+            .header("Authorization", format!("Bearer {}", token))
+            .header("anthropic-beta", "oauth-2025-04-20")
+            .header("User-Agent", user_agent)
+            .send()
             .ok()?;
 
-        if response.status() == 200 {
-            response.into_json().ok()
+        if response.status().is_success() {
+            response.json().ok()
         } else {
             None
         }
@@ -217,7 +190,7 @@ impl Segment for UsageSegment {
                 cache.resets_at,
             )
         } else {
-            match self.fetch_api_usage(api_base_url, &token, timeout) {
+            match self.fetch_api_usage(api_base_url, &token, &config.http, timeout) {
                 Some(response) => {
                     let cache = ApiUsageCache {
                         five_hour_utilization: response.five_hour.utilization,
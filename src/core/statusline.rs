@@ -1,9 +1,12 @@
-use crate::config::{AnsiColor, Config, SegmentConfig, StyleMode};
+use crate::config::{AnsiColor, Config, LayoutEntry, SegmentConfig, StyleMode};
 use crate::core::segments::SegmentData;
+use crate::core::template::Template;
+use unicode_width::UnicodeWidthChar;
 
-/// Strip ANSI escape sequences and return visible text length
+/// Strip ANSI escape sequences and return the visible width in terminal columns
+/// (wide characters such as CJK count as two)
 fn visible_width(text: &str) -> usize {
-    let mut visible = String::new();
+    let mut width = 0;
     let mut in_escape = false;
     let mut chars = text.chars().peekable();
 
@@ -22,46 +25,287 @@ fn visible_width(text: &str) -> usize {
             }
         } else {
             // Regular character
-            visible.push(ch);
+            width += ch.width().unwrap_or(0);
         }
     }
 
-    visible.chars().count()
+    width
+}
+
+/// Cut text to at most `max_width` visible columns, ending with an ellipsis.
+/// Escape sequences are kept so colors stay intact up to the cut.
+fn truncate_to_width(text: &str, max_width: usize) -> String {
+    if visible_width(text) <= max_width {
+        return text.to_string();
+    }
+    if max_width == 0 {
+        return String::new();
+    }
+
+    let mut result = String::new();
+    let mut width = 0;
+    let mut in_escape = false;
+    for ch in text.chars() {
+        if ch == '\x1b' {
+            in_escape = true;
+            result.push(ch);
+        } else if in_escape {
+            result.push(ch);
+            if ch.is_alphabetic() {
+                in_escape = false;
+            }
+        } else {
+            let ch_width = ch.width().unwrap_or(0);
+            // Leave one column for the ellipsis
+            if width + ch_width > max_width - 1 {
+                break;
+            }
+            width += ch_width;
+            result.push(ch);
+        }
+    }
+    result.push('…');
+    result.push_str("\x1b[0m");
+    result
+}
+
+/// Segments narrower than this after truncation are dropped instead
+const MIN_TRUNCATED_WIDTH: usize = 8;
+
+/// A rendered segment waiting to be placed on a line
+struct RenderedSegment {
+    config: SegmentConfig,
+    text: String,
+    width: usize,
+    /// Higher priorities are kept longer when a line is too wide
+    priority: u64,
 }
 
 pub struct StatusLineGenerator {
     config: Config,
+    /// Terminal width used when the layout does not set one
+    columns: Option<usize>,
 }
 
 impl StatusLineGenerator {
     pub fn new(config: Config) -> Self {
-        Self { config }
+        let columns = std::env::var("COLUMNS")
+            .ok()
+            .and_then(|c| c.trim().parse().ok())
+            .filter(|c| *c > 0);
+        Self { config, columns }
+    }
+
+    /// Use this terminal width instead of `$COLUMNS` (the daemon renders for its clients)
+    pub fn with_columns(mut self, columns: Option<usize>) -> Self {
+        self.columns = columns.filter(|c| *c > 0);
+        self
+    }
+
+    fn target_width(&self) -> Option<usize> {
+        match self.config.layout.width {
+            0 => self.columns,
+            width => Some(width),
+        }
     }
 
     pub fn generate(&self, segments: Vec<(SegmentConfig, SegmentData)>) -> String {
-        let mut output = Vec::new();
-        let enabled_segments: Vec<_> = segments
+        self.generate_lines(segments, self.target_width())
+            .join("\n")
+    }
+
+    /// Render every configured line, fitting each one into `width` when known
+    fn generate_lines(
+        &self,
+        segments: Vec<(SegmentConfig, SegmentData)>,
+        width: Option<usize>,
+    ) -> Vec<String> {
+        let rendered: Vec<RenderedSegment> = segments
             .into_iter()
             .filter(|(config, _)| config.enabled)
+            .map(|(config, data)| Self::apply_style_rules(config, data))
+            .filter_map(|(config, data)| {
+                let text = self.render_segment(&config, &data);
+                if text.is_empty() {
+                    return None;
+                }
+                let priority = config
+                    .options
+                    .get("priority")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(0);
+                Some(RenderedSegment {
+                    width: visible_width(&text),
+                    config,
+                    text,
+                    priority,
+                })
+            })
             .collect();
 
-        for (config, data) in enabled_segments.iter() {
-            let rendered = self.render_segment(config, data);
-            if !rendered.is_empty() {
-                output.push(rendered);
+        if rendered.is_empty() {
+            return Vec::new();
+        }
+
+        self.arrange_lines(rendered)
+            .into_iter()
+            .map(|(left, right)| self.render_line(left, right, width))
+            .filter(|line| !line.is_empty())
+            .collect()
+    }
+
+    /// Split segments into (left, right) groups per configured line
+    fn arrange_lines(
+        &self,
+        rendered: Vec<RenderedSegment>,
+    ) -> Vec<(Vec<RenderedSegment>, Vec<RenderedSegment>)> {
+        let layout = &self.config.layout.lines;
+        if layout.is_empty() {
+            return vec![(rendered, Vec::new())];
+        }
+
+        let mut pool: Vec<Option<RenderedSegment>> = rendered.into_iter().map(Some).collect();
+        let mut take = |entry: &LayoutEntry| {
+            pool.iter_mut()
+                .find(|s| s.as_ref().is_some_and(|s| entry.matches(&s.config)))
+                .and_then(Option::take)
+        };
+
+        // Named entries (`custom:<name>`) claim their segments before bare IDs take the
+        // next remaining one, wherever they appear in the layout
+        let groups: Vec<&[LayoutEntry]> = layout
+            .iter()
+            .flat_map(|line| [line.left.as_slice(), line.right.as_slice()])
+            .collect();
+        let mut slots: Vec<Vec<Option<RenderedSegment>>> = groups
+            .iter()
+            .map(|group| group.iter().map(|_| None).collect())
+            .collect();
+        for named in [true, false] {
+            for (group, entries) in slots.iter_mut().zip(&groups) {
+                for (slot, entry) in group.iter_mut().zip(entries.iter()) {
+                    if entry.name.is_some() == named {
+                        *slot = take(entry);
+                    }
+                }
+            }
+        }
+
+        let mut groups = slots
+            .into_iter()
+            .map(|group| group.into_iter().flatten().collect::<Vec<_>>());
+        let mut lines: Vec<_> = layout
+            .iter()
+            .map(|_| {
+                let left = groups.next().unwrap_or_default();
+                let right = groups.next().unwrap_or_default();
+                (left, right)
+            })
+            .collect();
+
+        // Enabled segments the layout does not mention go to the end of the first line
+        lines[0].0.extend(pool.into_iter().flatten());
+        lines
+    }
+
+    fn separator_width(&self) -> usize {
+        if self.config.style.separator == "\u{e0b0}" {
+            1
+        } else {
+            visible_width(&self.config.style.separator)
+        }
+    }
+
+    fn group_width(&self, group: &[RenderedSegment]) -> usize {
+        let separators = group.len().saturating_sub(1) * self.separator_width();
+        group.iter().map(|s| s.width).sum::<usize>() + separators
+    }
+
+    fn line_width(&self, left: &[RenderedSegment], right: &[RenderedSegment]) -> usize {
+        let gap = usize::from(!left.is_empty() && !right.is_empty());
+        self.group_width(left) + self.group_width(right) + gap
+    }
+
+    /// Drop or truncate the lowest-priority segments (rightmost first on ties) until the line fits
+    fn fit_line(
+        &self,
+        left: &mut Vec<RenderedSegment>,
+        right: &mut Vec<RenderedSegment>,
+        width: usize,
+    ) {
+        loop {
+            let total = self.line_width(left, right);
+            if total <= width || (left.is_empty() && right.is_empty()) {
+                return;
+            }
+            let overflow = total - width;
+
+            let Some((index, _)) = left
+                .iter()
+                .chain(right.iter())
+                .enumerate()
+                .min_by_key(|(i, s)| (s.priority, std::cmp::Reverse(*i)))
+            else {
+                return;
+            };
+            let only = left.len() + right.len() == 1;
+            let (group, index) = if index < left.len() {
+                (&mut *left, index)
+            } else {
+                (&mut *right, index - left.len())
+            };
+
+            let segment = &mut group[index];
+            let target = segment.width.saturating_sub(overflow);
+            if target >= MIN_TRUNCATED_WIDTH || (only && target > 0) {
+                segment.text = truncate_to_width(&segment.text, target);
+                segment.width = visible_width(&segment.text);
+            } else {
+                group.remove(index);
             }
         }
+    }
+
+    fn render_line(
+        &self,
+        mut left: Vec<RenderedSegment>,
+        mut right: Vec<RenderedSegment>,
+        width: Option<usize>,
+    ) -> String {
+        let Some(width) = width else {
+            // Without a width there is nothing to align against
+            left.append(&mut right);
+            return self.join_segments(&left);
+        };
+
+        self.fit_line(&mut left, &mut right, width);
+        let left_text = self.join_segments(&left);
+        if right.is_empty() {
+            return left_text;
+        }
+        let padding =
+            width.saturating_sub(self.line_width(&left, &right)) + usize::from(!left.is_empty());
+        format!(
+            "{}{}{}",
+            left_text,
+            " ".repeat(padding),
+            self.join_segments(&right)
+        )
+    }
 
-        if output.is_empty() {
+    fn join_segments(&self, segments: &[RenderedSegment]) -> String {
+        let texts: Vec<String> = segments.iter().map(|s| s.text.clone()).collect();
+        if texts.is_empty() {
             return String::new();
         }
 
         // Handle Powerline arrow separators with color transition
         if self.config.style.separator == "\u{e0b0}" {
-            self.join_with_powerline_arrows(&output, &enabled_segments)
+            let configs: Vec<&SegmentConfig> = segments.iter().map(|s| &s.config).collect();
+            self.join_with_powerline_arrows(&texts, &configs)
         } else {
             // For all other separators, use white color and simple join
-            self.join_with_white_separators(&output)
+            self.join_with_white_separators(&texts)
         }
     }
 
@@ -97,9 +341,29 @@ impl StatusLineGenerator {
         use ansi_to_tui::IntoText;
         use ratatui::text::{Line, Span, Text};
 
+        // A configured layout is previewed as is, at the preview width
+        if !self.config.layout.lines.is_empty() {
+            let width = match self.config.layout.width {
+                0 => max_width as usize,
+                width => width.min(max_width as usize),
+            };
+            let mut tui_lines = Vec::new();
+            for line in self.generate_lines(segments, Some(width)) {
+                match line.into_text() {
+                    Ok(text) => tui_lines.extend(text.lines),
+                    Err(_) => tui_lines.push(Line::from(vec![Span::raw(line)])),
+                }
+            }
+            if tui_lines.is_empty() {
+                tui_lines.push(Line::default());
+            }
+            return Text::from(tui_lines);
+        }
+
         let enabled_segments: Vec<_> = segments
             .into_iter()
             .filter(|(config, _)| config.enabled)
+            .map(|(config, data)| Self::apply_style_rules(config, data))
             .collect();
 
         if enabled_segments.is_empty() {
@@ -214,7 +478,50 @@ impl StatusLineGenerator {
         Text::from(tui_lines)
     }
 
+    /// Apply the segment's matching style rules; a rule icon replaces the segment's dynamic icon
+    fn apply_style_rules(
+        config: SegmentConfig,
+        mut data: SegmentData,
+    ) -> (SegmentConfig, SegmentData) {
+        if !config.has_style_rules() {
+            return (config, data);
+        }
+        let mut styled = config.clone();
+        for rule in config.style_rules() {
+            if rule.is_match(&data.metadata) {
+                rule.apply(&mut styled);
+                if rule.icon.is_some() {
+                    data.metadata.remove("dynamic_icon");
+                }
+            }
+        }
+        (styled, data)
+    }
+
+    /// Primary and secondary text, replaced by the rendered `format` option when set
+    fn segment_text(config: &SegmentConfig, data: &SegmentData) -> (String, String) {
+        let template = config
+            .options
+            .get("format")
+            .and_then(|v| v.as_str())
+            .filter(|f| !f.is_empty())
+            .and_then(|f| Template::parse(f).ok());
+        match template {
+            Some(template) => (template.render(data), String::new()),
+            None => (data.primary.clone(), data.secondary.clone()),
+        }
+    }
+
     fn render_segment(&self, config: &SegmentConfig, data: &SegmentData) -> String {
+        let (primary, secondary) = Self::segment_text(config, data);
+        // A template that renders nothing hides the segment
+        if primary.trim().is_empty()
+            && secondary.is_empty()
+            && config.options.contains_key("format")
+        {
+            return String::new();
+        }
+
         let icon = if let Some(dynamic_icon) = data.metadata.get("dynamic_icon") {
             dynamic_icon.clone()
         } else {
@@ -235,7 +542,7 @@ impl StatusLineGenerator {
 
             let text_styled = self
                 .apply_style(
-                    &data.primary,
+                    &primary,
                     config.colors.text.as_ref(),
                     config.styles.text_bold,
                 )
@@ -243,10 +550,10 @@ impl StatusLineGenerator {
 
             let mut segment_content = format!(" {} {} ", icon_colored, text_styled);
 
-            if !data.secondary.is_empty() {
+            if !secondary.is_empty() {
                 let secondary_styled = self
                     .apply_style(
-                        &data.secondary,
+                        &secondary,
                         config.colors.text.as_ref(),
                         config.styles.text_bold,
                     )
@@ -260,18 +567,18 @@ impl StatusLineGenerator {
             // No background color, use original logic
             let icon_colored = self.apply_color(&icon, config.colors.icon.as_ref());
             let text_styled = self.apply_style(
-                &data.primary,
+                &primary,
                 config.colors.text.as_ref(),
                 config.styles.text_bold,
             );
 
             let mut segment = format!("{} {}", icon_colored, text_styled);
 
-            if !data.secondary.is_empty() {
+            if !secondary.is_empty() {
                 segment.push_str(&format!(
                     " {}",
                     self.apply_style(
-                        &data.secondary,
+                        &secondary,
                         config.colors.text.as_ref(),
                         config.styles.text_bold
                     )
@@ -372,7 +679,7 @@ impl StatusLineGenerator {
     fn join_with_powerline_arrows(
         &self,
         rendered_segments: &[String],
-        segment_configs: &[(SegmentConfig, SegmentData)],
+        segment_configs: &[&SegmentConfig],
     ) -> String {
         if rendered_segments.is_empty() {
             return String::new();
@@ -387,10 +694,10 @@ impl StatusLineGenerator {
         for (i, _) in rendered_segments.iter().enumerate().skip(1) {
             let prev_bg = segment_configs
                 .get(i - 1)
-                .and_then(|(config, _)| config.colors.background.as_ref());
+                .and_then(|config| config.colors.background.as_ref());
             let curr_bg = segment_configs
                 .get(i)
-                .and_then(|(config, _)| config.colors.background.as_ref());
+                .and_then(|config| config.colors.background.as_ref());
 
             // Create Powerline arrow with color transition
             let arrow = self.create_powerline_arrow(prev_bg, curr_bg);
@@ -458,64 +765,205 @@ pub fn collect_all_segments(
     config: &Config,
     input: &crate::config::InputData,
 ) -> Vec<(SegmentConfig, SegmentData)> {
+    config
+        .segments
+        .iter()
+        .filter_map(|segment_config| {
+            collect_segment(config, segment_config, input)
+                .map(|data| (segment_config.clone(), data))
+        })
+        .collect()
+}
+
+/// Collect data for a single segment using its configured options
+pub fn collect_segment(
+    config: &Config,
+    segment_config: &SegmentConfig,
+    input: &crate::config::InputData,
+) -> Option<SegmentData> {
     use crate::core::segments::*;
 
-    let mut results = Vec::new();
+    match segment_config.id {
+        crate::config::SegmentId::Model => {
+            let segment = ModelSegment::new();
+            segment.collect(input)
+        }
+        crate::config::SegmentId::Directory => {
+            let segment = DirectorySegment::from_options(&segment_config.options);
+            segment.collect(input)
+        }
+        crate::config::SegmentId::Git => {
+            let segment = GitSegment::from_options(&segment_config.options);
+            segment.collect(input)
+        }
+        crate::config::SegmentId::ContextWindow => {
+            let segment = ContextWindowSegment::from_options(&segment_config.options);
+            segment.collect(input)
+        }
+        crate::config::SegmentId::Usage => {
+            let segment = UsageSegment::new();
+            segment.collect(input)
+        }
+        crate::config::SegmentId::Cost => {
+            let source = cost::CostSource::of_segment(segment_config);
+            let segment = CostSegment::new().with_source(source);
+            segment.collect(input)
+        }
+        crate::config::SegmentId::Session => {
+            let segment = SessionSegment::new();
+            segment.collect(input)
+        }
+        crate::config::SegmentId::OutputStyle => {
+            let segment = OutputStyleSegment::new();
+            segment.collect(input)
+        }
+        crate::config::SegmentId::Update => {
+            let segment = UpdateSegment::new();
+            segment.collect(input)
+        }
+        crate::config::SegmentId::ByeByeCodeUsage => byebyecode_usage::collect(config, input),
+        crate::config::SegmentId::ByeByeCodeSubscription => {
+            byebyecode_subscription::collect(config, input)
+        }
+        crate::config::SegmentId::ByeByeCodeStatus => byebyecode_status::collect(config, input),
+        crate::config::SegmentId::Custom => {
+            CustomSegment::from_options(&segment_config.options)?.collect(input)
+        }
+        crate::config::SegmentId::BurnRate => {
+            let window_hours = segment_config
+                .options
+                .get("window_hours")
+                .and_then(|v| v.as_f64())
+                .unwrap_or(3.0);
+            let segment = BurnRateSegment::new()
+                .with_window_hours(window_hours)
+                .with_api_config(burn_rate::resolve_api_config(config, segment_config));
+            segment.collect(input)
+        }
+    }
+}
 
-    for segment_config in &config.segments {
-        let segment_data = match segment_config.id {
-            crate::config::SegmentId::Model => {
-                let segment = ModelSegment::new();
-                segment.collect(input)
-            }
-            crate::config::SegmentId::Directory => {
-                let segment = DirectorySegment::new();
-                segment.collect(input)
-            }
-            crate::config::SegmentId::Git => {
-                let show_sha = segment_config
-                    .options
-                    .get("show_sha")
-                    .and_then(|v| v.as_bool())
-                    .unwrap_or(false);
-                let segment = GitSegment::new().with_sha(show_sha);
-                segment.collect(input)
-            }
-            crate::config::SegmentId::ContextWindow => {
-                let segment = ContextWindowSegment::new();
-                segment.collect(input)
-            }
-            crate::config::SegmentId::Usage => {
-                let segment = UsageSegment::new();
-                segment.collect(input)
-            }
-            crate::config::SegmentId::Cost => {
-                let segment = CostSegment::new();
-                segment.collect(input)
-            }
-            crate::config::SegmentId::Session => {
-                let segment = SessionSegment::new();
-                segment.collect(input)
-            }
-            crate::config::SegmentId::OutputStyle => {
-                let segment = OutputStyleSegment::new();
-                segment.collect(input)
-            }
-            crate::config::SegmentId::Update => {
-                let segment = UpdateSegment::new();
-                segment.collect(input)
-            }
-            crate::config::SegmentId::ByeByeCodeUsage => byebyecode_usage::collect(config, input),
-            crate::config::SegmentId::ByeByeCodeSubscription => {
-                byebyecode_subscription::collect(config, input)
-            }
-            crate::config::SegmentId::ByeByeCodeStatus => byebyecode_status::collect(config, input),
-        };
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{LayoutConfig, SegmentId};
+
+    fn generator() -> StatusLineGenerator {
+        let mut config = Config::default();
+        config.style.separator = " | ".to_string();
+        StatusLineGenerator::new(config).with_columns(None)
+    }
 
-        if let Some(data) = segment_data {
-            results.push((segment_config.clone(), data));
+    fn segment(text: &str, priority: u64) -> RenderedSegment {
+        RenderedSegment {
+            config: Config::default().segments[0].clone(),
+            text: text.to_string(),
+            width: visible_width(text),
+            priority,
         }
     }
 
-    results
+    fn texts(group: &[RenderedSegment]) -> Vec<&str> {
+        group.iter().map(|s| s.text.as_str()).collect()
+    }
+
+    #[test]
+    fn truncate_keeps_escapes_and_counts_wide_characters() {
+        assert_eq!(truncate_to_width("short", 5), "short");
+        assert_eq!(truncate_to_width("anything", 0), "");
+        assert_eq!(truncate_to_width("abcdef", 4), "abc…\x1b[0m");
+        assert_eq!(
+            truncate_to_width("\x1b[31mabcdef\x1b[0m", 4),
+            "\x1b[31mabc…\x1b[0m"
+        );
+        // A two-column character that does not fit before the ellipsis is dropped whole
+        assert_eq!(truncate_to_width("ab中文", 4), "ab…\x1b[0m");
+        assert_eq!(visible_width(&truncate_to_width("中文字符", 5)), 5);
+    }
+
+    fn custom(name: &str) -> RenderedSegment {
+        let mut config = Config::default().segments[0].clone();
+        config.id = SegmentId::Custom;
+        config
+            .options
+            .insert("name".to_string(), serde_json::Value::from(name));
+        RenderedSegment {
+            width: name.len(),
+            text: name.to_string(),
+            config,
+            priority: 0,
+        }
+    }
+
+    #[test]
+    fn layout_places_custom_segments_by_name() {
+        let mut config = Config::default();
+        config.layout.lines = toml::from_str::<LayoutConfig>(
+            r#"lines = [{ left = ["custom:b"] }, { left = ["custom"], right = ["custom:a"] }]"#,
+        )
+        .unwrap()
+        .lines;
+        let generator = StatusLineGenerator::new(config);
+
+        let lines = generator.arrange_lines(vec![custom("a"), custom("b"), custom("c")]);
+        assert_eq!(texts(&lines[0].0), ["b"]);
+        assert_eq!(texts(&lines[1].0), ["c"]);
+        assert_eq!(texts(&lines[1].1), ["a"]);
+    }
+
+    #[test]
+    fn layout_entries_round_trip_and_reject_unknown_ids() {
+        let entry = LayoutEntry::try_from("custom:k8s".to_string()).unwrap();
+        assert_eq!(entry.id, SegmentId::Custom);
+        assert_eq!(String::from(entry), "custom:k8s");
+        assert_eq!(
+            String::from(LayoutEntry::from(SegmentId::ContextWindow)),
+            "context_window"
+        );
+        assert!(LayoutEntry::try_from("nosuch".to_string()).is_err());
+        assert!(LayoutEntry::try_from("git:main".to_string()).is_err());
+    }
+
+    #[test]
+    fn fit_line_leaves_lines_that_fit() {
+        let generator = generator();
+        let mut left = vec![segment("aaaa", 0), segment("bbbb", 0)];
+        let mut right = vec![segment("cc", 0)];
+        // 4 + 3 + 4 + 1 + 2
+        generator.fit_line(&mut left, &mut right, 14);
+        assert_eq!(texts(&left), ["aaaa", "bbbb"]);
+        assert_eq!(texts(&right), ["cc"]);
+    }
+
+    #[test]
+    fn fit_line_truncates_the_lowest_priority_segment() {
+        let generator = generator();
+        let mut left = vec![segment("model-name", 2), segment("a-long-branch-name", 1)];
+        let mut right = Vec::new();
+        // 10 + 3 + 18 = 31, four columns too wide
+        generator.fit_line(&mut left, &mut right, 27);
+        assert_eq!(left[0].text, "model-name");
+        assert_eq!(left[1].width, 14);
+        assert_eq!(generator.line_width(&left, &right), 27);
+    }
+
+    #[test]
+    fn fit_line_drops_segments_too_narrow_to_truncate() {
+        let generator = generator();
+        let mut left = vec![segment("keep-me", 5), segment("tiny-seg", 0)];
+        let mut right = vec![segment("right", 0)];
+        // Ties go to the rightmost segment: "right" first, then "tiny-seg"
+        generator.fit_line(&mut left, &mut right, 10);
+        assert_eq!(texts(&left), ["keep-me"]);
+        assert!(right.is_empty());
+    }
+
+    #[test]
+    fn fit_line_truncates_a_lone_segment_below_the_minimum() {
+        let generator = generator();
+        let mut left = vec![segment("only-segment", 0)];
+        let mut right = Vec::new();
+        generator.fit_line(&mut left, &mut right, 4);
+        assert_eq!(texts(&left), ["onl…\x1b[0m"]);
+    }
 }
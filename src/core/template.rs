@@ -0,0 +1,465 @@
+//! Segment `format` templates
+//!
+//! A template mixes literal text with placeholders that read `SegmentData.metadata`
+//! (plus `primary` and `secondary`):
+//!
+//! - `{name}` inserts a value; missing values render as an empty string
+//! - `{name:>8.1}` applies a format spec: alignment (`<`, `>`, `^`), width and
+//!   precision (precision only applies to numeric values)
+//! - `{name|k}` pipes the value through filters: `k`, `duration` (ms), `duration_s`,
+//!   `upper`, `lower`, `truncate(n)`, `default(text)`
+//! - `{?name}...{/}` renders its body only when `name` is set, non-empty and not
+//!   zero/false/`-`; `{!name}...{/}` is the inverse
+//! - `{{` and `}}` are literal braces
+
+use crate::core::segments::SegmentData;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Filter {
+    /// `k`: 1234 -> 1.2k, 2500000 -> 2.5M
+    Human,
+    /// `duration`: milliseconds -> 1h 5m / 3m 12s / 42s / 850ms
+    DurationMs,
+    /// `duration_s`: same as `duration` for values in seconds
+    DurationSecs,
+    /// `upper` / `lower`
+    Upper,
+    Lower,
+    /// `truncate(n)`: at most n characters, ending with `…` when cut
+    Truncate(usize),
+    /// `default(text)`: used when the value is missing or empty
+    Default(String),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Spec {
+    align: Option<char>,
+    width: usize,
+    precision: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Text(String),
+    Field {
+        name: String,
+        filters: Vec<Filter>,
+        spec: Spec,
+    },
+    Conditional {
+        name: String,
+        negate: bool,
+        body: Vec<Node>,
+    },
+}
+
+/// Parsed segment format template
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        // Stack of open blocks: (name, negate, nodes collected so far)
+        let mut stack: Vec<(String, bool, Vec<Node>)> = vec![(String::new(), false, Vec::new())];
+        let mut text = String::new();
+        let mut chars = source.chars().peekable();
+
+        fn flush(text: &mut String, nodes: &mut Vec<Node>) {
+            if !text.is_empty() {
+                nodes.push(Node::Text(std::mem::take(text)));
+            }
+        }
+
+        while let Some(ch) = chars.next() {
+            match ch {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '}' => return Err("unmatched '}' (use '}}' for a literal brace)".to_string()),
+                '{' => {
+                    let mut inner = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some('{') => return Err("nested '{' inside a placeholder".to_string()),
+                            Some(c) => inner.push(c),
+                            None => return Err(format!("unclosed placeholder '{{{}'", inner)),
+                        }
+                    }
+                    let inner = inner.trim();
+                    let nodes = &mut stack.last_mut().expect("root block").2;
+                    flush(&mut text, nodes);
+
+                    if let Some(name) = inner.strip_prefix('?').or(inner.strip_prefix('!')) {
+                        let name = parse_name(name)?;
+                        stack.push((name, inner.starts_with('!'), Vec::new()));
+                    } else if let Some(closing) = inner.strip_prefix('/') {
+                        let (name, negate, body) = stack.pop().expect("root block");
+                        if stack.is_empty() {
+                            return Err("'{/}' without an open '{?name}' block".to_string());
+                        }
+                        let closing = closing.trim();
+                        if !closing.is_empty() && closing != name {
+                            return Err(format!("'{{/{}}}' closes block '{}'", closing, name));
+                        }
+                        stack
+                            .last_mut()
+                            .expect("root block")
+                            .2
+                            .push(Node::Conditional { name, negate, body });
+                    } else {
+                        nodes.push(parse_field(inner)?);
+                    }
+                }
+                c => text.push(c),
+            }
+        }
+
+        if stack.len() > 1 {
+            let (name, _, _) = stack.pop().expect("open block");
+            return Err(format!(
+                "block '{{?{}}}' is never closed with '{{/}}'",
+                name
+            ));
+        }
+        let (_, _, mut nodes) = stack.pop().expect("root block");
+        flush(&mut text, &mut nodes);
+        Ok(Self { nodes })
+    }
+
+    /// Render with values from a segment's metadata
+    pub fn render(&self, data: &SegmentData) -> String {
+        let lookup = |name: &str| match name {
+            "primary" => Some(data.primary.clone()),
+            "secondary" => Some(data.secondary.clone()),
+            _ => data.metadata.get(name).cloned(),
+        };
+        let mut output = String::new();
+        render_nodes(&self.nodes, &lookup, &mut output);
+        output
+    }
+}
+
+fn parse_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(format!("invalid placeholder name '{}'", name));
+    }
+    Ok(name.to_string())
+}
+
+/// Byte offsets of `target` outside filter arguments and double-quoted text
+fn top_level_positions(text: &str, target: char) -> Vec<usize> {
+    let mut depth = 0usize;
+    let mut quoted = false;
+    let mut positions = Vec::new();
+    for (i, c) in text.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            _ if quoted => {}
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            c if c == target && depth == 0 => positions.push(i),
+            _ => {}
+        }
+    }
+    positions
+}
+
+fn parse_field(inner: &str) -> Result<Node, String> {
+    // The spec follows the last ':' outside of filter arguments
+    let (expr, spec) = match top_level_positions(inner, ':').last() {
+        Some(&i) => (&inner[..i], parse_spec(&inner[i + 1..])?),
+        None => (inner, Spec::default()),
+    };
+
+    // Filters are split on '|' outside of their arguments, so default(a|b) stays whole
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for i in top_level_positions(expr, '|') {
+        parts.push(&expr[start..i]);
+        start = i + 1;
+    }
+    parts.push(&expr[start..]);
+
+    let name = parse_name(parts[0])?;
+    let filters = parts[1..]
+        .iter()
+        .map(|filter| parse_filter(filter))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Node::Field {
+        name,
+        filters,
+        spec,
+    })
+}
+
+fn parse_filter(filter: &str) -> Result<Filter, String> {
+    let filter = filter.trim();
+    let (name, arg) = match filter.split_once('(') {
+        Some((name, rest)) => {
+            let arg = rest
+                .strip_suffix(')')
+                .ok_or_else(|| format!("missing ')' in filter '{}'", filter))?;
+            (name.trim(), Some(arg))
+        }
+        None => (filter, None),
+    };
+
+    match (name, arg) {
+        ("k", None) => Ok(Filter::Human),
+        ("duration", None) => Ok(Filter::DurationMs),
+        ("duration_s", None) => Ok(Filter::DurationSecs),
+        ("upper", None) => Ok(Filter::Upper),
+        ("lower", None) => Ok(Filter::Lower),
+        ("truncate", Some(n)) => n
+            .trim()
+            .parse()
+            .map(Filter::Truncate)
+            .map_err(|_| format!("truncate expects a length, got '{}'", n)),
+        ("default", Some(text)) => {
+            // Quotes keep ')' and leading or trailing spaces: default(" n/a ")
+            let unquoted = text
+                .trim()
+                .strip_prefix('"')
+                .and_then(|t| t.strip_suffix('"'));
+            Ok(Filter::Default(unquoted.unwrap_or(text).to_string()))
+        }
+        _ => Err(format!(
+            "unknown filter '{}' (expected k, duration, duration_s, upper, lower, truncate(n), default(text))",
+            filter
+        )),
+    }
+}
+
+fn parse_spec(spec: &str) -> Result<Spec, String> {
+    let invalid = || format!("invalid format spec ':{}'", spec);
+    let mut rest = spec;
+    let mut result = Spec::default();
+
+    if let Some(c) = rest.chars().next().filter(|c| matches!(c, '<' | '>' | '^')) {
+        result.align = Some(c);
+        rest = &rest[1..];
+    }
+    let (width, precision) = match rest.split_once('.') {
+        Some((width, precision)) => (width, Some(precision)),
+        None => (rest, None),
+    };
+    if !width.is_empty() {
+        result.width = width.parse().map_err(|_| invalid())?;
+    }
+    if let Some(precision) = precision {
+        result.precision = Some(precision.parse().map_err(|_| invalid())?);
+    }
+    Ok(result)
+}
+
+fn is_truthy(value: Option<&str>) -> bool {
+    match value.map(str::trim) {
+        None | Some("") | Some("-") | Some("false") => false,
+        Some(v) => v.parse::<f64>() != Ok(0.0),
+    }
+}
+
+fn render_nodes(nodes: &[Node], lookup: &dyn Fn(&str) -> Option<String>, output: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push_str(text),
+            Node::Field {
+                name,
+                filters,
+                spec,
+            } => {
+                let value = lookup(name).unwrap_or_default();
+                let value = filters.iter().fold(value, |v, f| apply_filter(f, v));
+                output.push_str(&apply_spec(spec, value));
+            }
+            Node::Conditional { name, negate, body } => {
+                if is_truthy(lookup(name).as_deref()) != *negate {
+                    render_nodes(body, lookup, output);
+                }
+            }
+        }
+    }
+}
+
+fn apply_filter(filter: &Filter, value: String) -> String {
+    match filter {
+        Filter::Human => match value.trim().parse::<f64>() {
+            Ok(n) => human_count(n),
+            Err(_) => value,
+        },
+        Filter::DurationMs => match value.trim().parse::<f64>() {
+            Ok(ms) => human_duration(ms),
+            Err(_) => value,
+        },
+        Filter::DurationSecs => match value.trim().parse::<f64>() {
+            Ok(secs) => human_duration(secs * 1000.0),
+            Err(_) => value,
+        },
+        Filter::Upper => value.to_uppercase(),
+        Filter::Lower => value.to_lowercase(),
+        Filter::Truncate(max) => {
+            if value.chars().count() <= *max {
+                value
+            } else if *max == 0 {
+                String::new()
+            } else {
+                let mut cut: String = value.chars().take(max - 1).collect();
+                cut.push('…');
+                cut
+            }
+        }
+        Filter::Default(text) => {
+            if value.trim().is_empty() {
+                text.clone()
+            } else {
+                value
+            }
+        }
+    }
+}
+
+fn apply_spec(spec: &Spec, value: String) -> String {
+    let value = match (spec.precision, value.trim().parse::<f64>()) {
+        (Some(precision), Ok(n)) => format!("{:.*}", precision, n),
+        _ => value,
+    };
+    match spec.align {
+        Some('<') => format!("{:<width$}", value, width = spec.width),
+        Some('^') => format!("{:^width$}", value, width = spec.width),
+        // Right-aligned by default, like numbers in `format!`
+        _ => format!("{:>width$}", value, width = spec.width),
+    }
+}
+
+/// 950 -> 950, 1200 -> 1.2k, 12000 -> 12k, 2500000 -> 2.5M
+fn human_count(n: f64) -> String {
+    let (value, suffix) = match n.abs() {
+        a if a >= 1e9 => (n / 1e9, "B"),
+        a if a >= 1e6 => (n / 1e6, "M"),
+        a if a >= 1e3 => (n / 1e3, "k"),
+        _ => return format!("{}", n.round() as i64),
+    };
+    let rounded = format!("{:.1}", value);
+    format!("{}{}", rounded.trim_end_matches(".0"), suffix)
+}
+
+/// Two most significant units: 1h 5m, 3m 12s, 42s, 850ms
+fn human_duration(ms: f64) -> String {
+    if ms < 1000.0 {
+        return format!("{}ms", ms.round() as u64);
+    }
+    let secs = (ms / 1000.0).round() as u64;
+    let (days, hours, minutes, seconds) = (
+        secs / 86_400,
+        secs % 86_400 / 3600,
+        secs % 3600 / 60,
+        secs % 60,
+    );
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m {}s", minutes, seconds)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn render(source: &str, values: &[(&str, &str)]) -> String {
+        let data = SegmentData {
+            primary: "main".to_string(),
+            secondary: String::new(),
+            metadata: values
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<HashMap<_, _>>(),
+        };
+        Template::parse(source).unwrap().render(&data)
+    }
+
+    #[test]
+    fn nested_conditionals() {
+        let source = "{primary}{?dirty} *{?staged}+{staged}{/staged}{!ahead} synced{/}{/dirty}";
+        assert_eq!(render(source, &[]), "main");
+        assert_eq!(render(source, &[("dirty", "1")]), "main * synced");
+        assert_eq!(
+            render(
+                source,
+                &[("dirty", "true"), ("staged", "3"), ("ahead", "2")]
+            ),
+            "main *+3"
+        );
+        // Zero, false and '-' count as unset
+        assert_eq!(render(source, &[("dirty", "0"), ("staged", "3")]), "main");
+        assert_eq!(render(source, &[("dirty", "-")]), "main");
+    }
+
+    #[test]
+    fn escaped_braces() {
+        assert_eq!(render("{{{primary}}}", &[]), "{main}");
+        assert_eq!(render("{{?x}} }}{{", &[]), "{?x} }{");
+    }
+
+    #[test]
+    fn format_specs() {
+        assert_eq!(
+            render("[{cost:>8.1}]", &[("cost", "3.14159")]),
+            "[     3.1]"
+        );
+        assert_eq!(render("[{cost:<6.2}]", &[("cost", "2")]), "[2.00  ]");
+        assert_eq!(render("[{name:^7}]", &[("name", "abc")]), "[  abc  ]");
+        // Right-aligned by default; precision is ignored for text
+        assert_eq!(render("[{name:5.1}]", &[("name", "ab")]), "[   ab]");
+        // ':' inside filter arguments is not a spec
+        assert_eq!(render("{name|default(a:b)}", &[]), "a:b");
+        assert_eq!(render("{name|default(\"x:\"):>4}", &[]), "  x:");
+        assert_eq!(render("{tokens|k:>6}", &[("tokens", "1234")]), "  1.2k");
+    }
+
+    #[test]
+    fn filters() {
+        assert_eq!(render("{ms|duration}", &[("ms", "3900000")]), "1h 5m");
+        assert_eq!(render("{s|duration_s}", &[("s", "42")]), "42s");
+        assert_eq!(
+            render("{name|upper|truncate(4)}", &[("name", "feature")]),
+            "FEA…"
+        );
+        // '|' inside filter arguments or quotes does not start a new filter
+        assert_eq!(render("{name|default(a|b)}", &[]), "a|b");
+        assert_eq!(render("{name|default(a|b)|upper}", &[]), "A|B");
+        assert_eq!(render("{name|default(\"(|)\")}", &[]), "(|)");
+        assert_eq!(render("{name|default(a|b)}", &[("name", "x")]), "x");
+    }
+
+    #[test]
+    fn parse_errors() {
+        for source in [
+            "{unclosed",
+            "stray }",
+            "{?open}never closed",
+            "{/}",
+            "{?a}{/b}",
+            "{x|nosuch}",
+            "{x:>abc}",
+            "{a{b}}",
+            "{bad name}",
+        ] {
+            assert!(Template::parse(source).is_err(), "{}", source);
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! Shared reading of Claude Code transcripts (JSONL, one entry per line) for the
+//! cost ledger and `byebyecode report`
+
+use super::ledger::TokenCounts;
+use crate::config::TranscriptEntry;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Usage an assistant entry is billed for
+#[derive(Debug, Clone, PartialEq)]
+pub struct BillableUsage {
+    /// API message ID; Claude Code writes one entry per content block with the same ID
+    pub message_id: Option<String>,
+    pub model: String,
+    pub tokens: TokenCounts,
+}
+
+/// Parse one transcript line; blank and malformed lines give None
+pub fn parse_line(line: &str) -> Option<TranscriptEntry> {
+    let line = line.trim();
+    if line.is_empty() {
+        return None;
+    }
+    serde_json::from_str(line).ok()
+}
+
+/// Billable usage of an assistant entry; None for other entries and for synthetic
+/// messages (API errors, interruptions) that carry no billable usage
+pub fn billable_usage(entry: &TranscriptEntry) -> Option<BillableUsage> {
+    if entry.r#type.as_deref() != Some("assistant") {
+        return None;
+    }
+    let message = entry.message.as_ref()?;
+    let tokens = TokenCounts::from_usage(&message.usage.clone()?.normalize());
+    let model = message
+        .model
+        .clone()
+        .unwrap_or_else(|| "unknown".to_string());
+    if tokens.is_empty() || model.starts_with('<') {
+        return None;
+    }
+
+    Some(BillableUsage {
+        message_id: message.id.clone(),
+        model,
+        tokens,
+    })
+}
+
+/// Every transcript file below `dir`, including nested ones such as subagent transcripts
+pub fn find_transcripts(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    collect_transcripts(dir, &mut files);
+    files.sort();
+    files
+}
+
+fn collect_transcripts(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        // file_type does not follow symlinks, so linked directories cannot loop
+        match entry.file_type() {
+            Ok(kind) if kind.is_dir() => collect_transcripts(&path, files),
+            Ok(_) if path.extension().and_then(|s| s.to_str()) == Some("jsonl") => files.push(path),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assistant(model: &str, input: u64, output: u64) -> String {
+        format!(
+            r#"{{"type":"assistant","uuid":"u1","message":{{"id":"msg_1","model":"{}","usage":{{"input_tokens":{},"output_tokens":{}}}}}}}"#,
+            model, input, output
+        )
+    }
+
+    #[test]
+    fn parses_billable_assistant_usage() {
+        let entry = parse_line(&assistant("claude-sonnet-4", 10, 5)).unwrap();
+        let usage = billable_usage(&entry).unwrap();
+        assert_eq!(usage.message_id.as_deref(), Some("msg_1"));
+        assert_eq!(usage.model, "claude-sonnet-4");
+        assert_eq!(usage.tokens.input, 10);
+        assert_eq!(usage.tokens.output, 5);
+    }
+
+    #[test]
+    fn skips_synthetic_empty_and_non_assistant_entries() {
+        let synthetic = parse_line(&assistant("<synthetic>", 10, 5)).unwrap();
+        assert!(billable_usage(&synthetic).is_none());
+        let empty = parse_line(&assistant("claude-sonnet-4", 0, 0)).unwrap();
+        assert!(billable_usage(&empty).is_none());
+        let user = parse_line(r#"{"type":"user","message":{"id":"x"}}"#).unwrap();
+        assert!(billable_usage(&user).is_none());
+        assert!(parse_line("   ").is_none());
+        assert!(parse_line("{truncated").is_none());
+    }
+
+    #[test]
+    fn finds_nested_transcripts() {
+        let root =
+            std::env::temp_dir().join(format!("byebyecode-transcripts-{}", std::process::id()));
+        let nested = root.join("project").join("session").join("subagents");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(root.join("project").join("a.jsonl"), "").unwrap();
+        fs::write(nested.join("b.jsonl"), "").unwrap();
+        fs::write(nested.join("notes.txt"), "").unwrap();
+
+        let found = find_transcripts(&root);
+        let _ = fs::remove_dir_all(&root);
+        assert_eq!(
+            found,
+            vec![root.join("project").join("a.jsonl"), nested.join("b.jsonl")]
+        );
+    }
+}
@@ -1,7 +1,9 @@
-use byebyecode::cli::Cli;
-use byebyecode::config::{Config, InputData};
+use byebyecode::cli::{Cli, Commands, UsageCommands};
+use byebyecode::config::{validator, Config, InputData, LayeredConfig};
+use byebyecode::core::report::{print_report, ReportOptions};
 use byebyecode::core::{collect_all_segments, StatusLineGenerator};
-use std::io::{self, IsTerminal};
+use std::io::{self, IsTerminal, Read};
+use std::path::Path;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Migrate legacy config directory if needed
@@ -9,6 +11,79 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let cli = Cli::parse_args();
 
+    if let Some(Commands::Usage { command }) = &cli.command {
+        match command {
+            UsageCommands::History { days, hours } => {
+                byebyecode::api::history::print_history(*days, *hours)?;
+            }
+        }
+        return Ok(());
+    }
+
+    if let Some(Commands::Refresh) = &cli.command {
+        return byebyecode::api::cache::run_refresh_from_stdin();
+    }
+
+    if let Some(Commands::Report {
+        since,
+        until,
+        month,
+        group_by,
+        format,
+    }) = &cli.command
+    {
+        // Keep the requested column order, dropping repeats
+        let mut columns = Vec::new();
+        for group in group_by {
+            if !columns.contains(group) {
+                columns.push(*group);
+            }
+        }
+        let mut options = ReportOptions {
+            since: *since,
+            until: *until,
+            group_by: columns,
+        };
+        if let Some(month) = month {
+            options = options.with_month(month)?;
+        }
+        print_report(&options, *format)?;
+        return Ok(());
+    }
+
+    if let Some(Commands::Daemon { stop, status }) = &cli.command {
+        #[cfg(unix)]
+        {
+            use byebyecode::core::daemon;
+            if *stop {
+                daemon::stop_daemon()?;
+            } else if *status {
+                daemon::print_status()?;
+            } else {
+                daemon::run_daemon()?;
+            }
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = (stop, status);
+            eprintln!("Daemon mode is only supported on Unix platforms.");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if let Some(Commands::Run { args }) = cli.command {
+        let config = Config::load()?;
+        let claude_path = byebyecode::wrapper::find_claude_code()?;
+        let mut injector = byebyecode::wrapper::injector::ClaudeCodeInjector::new(
+            claude_path,
+            Some(config.translation),
+            &config.http,
+        )?;
+        injector.run_with_interception(args)?;
+        return Ok(());
+    }
+
     // Handle configuration commands
     if cli.init {
         Config::init()?;
@@ -27,20 +102,62 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     if cli.print {
-        let mut config = Config::load().unwrap_or_else(|_| Config::default());
-
         // Apply theme override if provided
         if let Some(theme) = cli.theme {
-            config = byebyecode::ui::themes::ThemePresets::get_theme(&theme);
+            let config = Config::load()?;
+            byebyecode::ui::themes::ThemePresets::apply_theme(&config, &theme).print()?;
+            return Ok(());
         }
 
-        config.print()?;
+        // Global config merged with the project overlay for the current directory
+        LayeredConfig::load(&std::env::current_dir()?)?.print()?;
         return Ok(());
     }
 
     if cli.check {
-        let config = Config::load()?;
-        config.check()?;
+        let pending = Config::pending_migrations()?;
+        if !pending.is_empty() {
+            println!("⚠ Pending config migrations:");
+            for migration in &pending {
+                println!("  v{}: {}", migration.to_version, migration.description);
+            }
+            println!("  They are applied on the next load; config.toml is backed up first.");
+        }
+
+        let current_dir = std::env::current_dir()?;
+        let mut files = Vec::new();
+        let global_path = Config::get_config_path();
+        if global_path.exists() {
+            files.push((global_path, false));
+        }
+        if let Some(overlay) = byebyecode::config::layers::find_project_overlay(&current_dir) {
+            files.push((overlay, true));
+        }
+
+        let mut errors = 0;
+        for (path, partial) in &files {
+            for diagnostic in validator::validate_file(path, *partial) {
+                if diagnostic.severity == validator::Severity::Error {
+                    errors += 1;
+                }
+                println!("{}", diagnostic);
+            }
+        }
+        if errors > 0 {
+            return Err(format!("{} error(s) in configuration", errors).into());
+        }
+
+        let layered = LayeredConfig::preview(&current_dir)?;
+        layered.config.check()?;
+        if let Some(overlay) = layered.project_overlay() {
+            println!("✓ Project overlay applied: {}", overlay.display());
+            if !layered.ignored.is_empty() {
+                println!("⚠ Ignored from the project overlay (not in trusted_projects, or an unknown theme):");
+                for path in &layered.ignored {
+                    println!("  {}", path);
+                }
+            }
+        }
         println!("✓ Configuration valid");
         return Ok(());
     }
@@ -115,14 +232,6 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         return Ok(());
     }
 
-    // Load configuration
-    let mut config = Config::load().unwrap_or_else(|_| Config::default());
-
-    // Apply theme override if provided
-    if let Some(theme) = cli.theme {
-        config = byebyecode::ui::themes::ThemePresets::get_theme(&theme);
-    }
-
     // Check if stdin has data
     if io::stdin().is_terminal() {
         // No input data available, show main menu
@@ -160,8 +269,42 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     // Read Claude Code data from stdin
-    let stdin = io::stdin();
-    let input: InputData = serde_json::from_reader(stdin.lock())?;
+    let mut raw_input = String::new();
+    io::stdin().read_to_string(&mut raw_input)?;
+
+    // Hand off to the daemon when one is running
+    #[cfg(unix)]
+    if let Some(statusline) =
+        byebyecode::core::daemon::render_via_daemon(&raw_input, cli.theme.as_deref())
+    {
+        println!("{}", statusline);
+        return Ok(());
+    }
+
+    let mut input: InputData = serde_json::from_str(&raw_input)?;
+    input.raw_json = raw_input;
+
+    // Load configuration, including the project overlay for the workspace; a theme
+    // override replaces the look of the global config, so overlays only apply without one
+    let config = match cli.theme {
+        Some(theme) => Config::load()
+            .map(|config| byebyecode::ui::themes::ThemePresets::apply_theme(&config, &theme)),
+        None => Config::load_for_workspace(Path::new(&input.workspace.current_dir)),
+    };
+    // A config that fails to load or migrate is reported rather than replaced by defaults
+    let config = match config {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("byebyecode: failed to load config: {}", e);
+            println!("{}", config_error_line(&*e));
+            return Ok(());
+        }
+    };
+
+    // Update the local cost ledger before segments read it
+    if byebyecode::core::ledger::is_used_by(&config) {
+        let _ = byebyecode::core::ledger::record_session(&input);
+    }
 
     // Collect segment data
     let segments_data = collect_all_segments(&config, &input);
@@ -176,10 +319,19 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// One-line statusline shown instead of the configured one when the config is broken
+fn config_error_line(error: &dyn std::error::Error) -> String {
+    let message = error.to_string();
+    format!(
+        "⚠ byebyecode config error: {} (run byebyecode --check)",
+        message.lines().next().unwrap_or_default()
+    )
+}
+
 fn migrate_legacy_config() -> Result<(), Box<dyn std::error::Error>> {
-    if let Some(home) = dirs::home_dir() {
-        let old_dir = home.join(".claude").join("88code");
-        let new_dir = home.join(".claude").join("byebyecode");
+    if let Some(claude_dir) = byebyecode::utils::paths::claude_dir() {
+        let old_dir = claude_dir.join("88code");
+        let new_dir = claude_dir.join("byebyecode");
 
         if old_dir.exists() && !new_dir.exists() {
             std::fs::rename(&old_dir, &new_dir)?;
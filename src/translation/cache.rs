@@ -0,0 +1,332 @@
+use super::glossary::ProtectedText;
+use super::{Language, Translator};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 缓存条目上限，超出时淘汰最早写入的条目
+const MAX_CACHE_ENTRIES: usize = 5000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    translation: String,
+    created_at: u64,
+}
+
+/// 持久化翻译缓存（~/.claude/byebyecode/cache/translations.json）
+///
+/// 以 "翻译服务和模型 + 目标语言 + 原文" 的 SHA-256 为键，原文中的代码和路径已替换为占位符，
+/// 因此相同句式搭配不同代码片段也能复用同一条译文。
+pub struct TranslationCache {
+    path: Option<PathBuf>,
+    entries: HashMap<String, CacheEntry>,
+}
+
+impl TranslationCache {
+    /// 获取缓存文件路径
+    fn cache_file() -> Option<PathBuf> {
+        let cache_dir = crate::utils::paths::cache_dir()?;
+
+        // 确保缓存目录存在
+        fs::create_dir_all(&cache_dir).ok()?;
+
+        Some(cache_dir.join("translations.json"))
+    }
+
+    /// 加载缓存，文件缺失或损坏时从空缓存开始
+    pub fn load() -> Self {
+        Self::open(Self::cache_file())
+    }
+
+    fn open(path: Option<PathBuf>) -> Self {
+        let entries = path.as_deref().map(read_entries).unwrap_or_default();
+        Self { path, entries }
+    }
+
+    /// engine 标识翻译服务、接口地址和模型，不同模型的译文互不复用
+    pub fn key(text: &str, target: Language, engine: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(engine.as_bytes());
+        hasher.update([0u8]);
+        hasher.update(target.prompt_name().as_bytes());
+        hasher.update([0u8]);
+        hasher.update(text.as_bytes());
+        format!("{:x}", hasher.finalize())
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries.get(key).map(|e| e.translation.as_str())
+    }
+
+    /// 写入缓存并立即落盘
+    pub fn insert(&mut self, key: String, translation: String) {
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        self.entries.insert(
+            key,
+            CacheEntry {
+                translation,
+                created_at,
+            },
+        );
+
+        let _ = self.save();
+    }
+
+    /// 淘汰最早写入的条目，直到不超过上限
+    fn evict(&mut self) {
+        if self.entries.len() <= MAX_CACHE_ENTRIES {
+            return;
+        }
+        let mut by_age: Vec<(u64, String)> = self
+            .entries
+            .iter()
+            .map(|(k, e)| (e.created_at, k.clone()))
+            .collect();
+        by_age.sort();
+        let excess = self.entries.len() - MAX_CACHE_ENTRIES;
+        for (_, key) in by_age.into_iter().take(excess) {
+            self.entries.remove(&key);
+        }
+    }
+
+    /// 多个会话的翻译进程可能同时写缓存：持有 `.lock` 文件的独占锁，
+    /// 先合并磁盘上其他进程新写入的条目，再写临时文件并重命名替换
+    fn save(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(path) = self.path.clone() else {
+            return Ok(());
+        };
+
+        let lock = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path.with_extension("lock"))?;
+        lock.lock()?;
+
+        for (key, entry) in read_entries(&path) {
+            self.entries.entry(key).or_insert(entry);
+        }
+        self.evict();
+
+        let tmp = path.with_extension(format!("json.{}.tmp", std::process::id()));
+        fs::write(&tmp, serde_json::to_string(&self.entries)?)?;
+        if let Err(e) = fs::rename(&tmp, &path) {
+            let _ = fs::remove_file(&tmp);
+            return Err(e.into());
+        }
+        Ok(())
+    }
+}
+
+/// 读取缓存文件，缺失或损坏时返回空表
+fn read_entries(path: &Path) -> HashMap<String, CacheEntry> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// 为任意翻译器增加缓存、术语表和代码/路径保护
+pub struct CachedTranslator {
+    inner: Box<dyn Translator>,
+    /// 翻译服务、接口地址和模型，作为缓存键的一部分
+    engine: String,
+    cache: Option<Mutex<TranslationCache>>,
+    glossary: Vec<String>,
+}
+
+impl CachedTranslator {
+    pub fn new(
+        inner: Box<dyn Translator>,
+        engine: String,
+        use_cache: bool,
+        glossary: Vec<String>,
+    ) -> Self {
+        Self {
+            inner,
+            engine,
+            cache: use_cache.then(|| Mutex::new(TranslationCache::load())),
+            glossary,
+        }
+    }
+}
+
+impl Translator for CachedTranslator {
+    fn translate(
+        &self,
+        text: &str,
+        target: Language,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let protected = ProtectedText::new(text, &self.glossary);
+
+        // 只有代码、路径或术语时无需调用翻译接口
+        if !protected.has_translatable_text() {
+            return Ok(text.to_string());
+        }
+
+        let key = TranslationCache::key(&protected.text, target, &self.engine);
+        if let Some(cache) = &self.cache {
+            if let Ok(cache) = cache.lock() {
+                if let Some(restored) = cache.get(&key).and_then(|t| protected.restore(t)) {
+                    return Ok(restored);
+                }
+            }
+        }
+
+        let translated = self.inner.translate(&protected.text, target)?;
+
+        // 译文丢失占位符时宁可显示原文，也不输出被篡改的代码
+        let restored = protected
+            .restore(&translated)
+            .ok_or("Translation dropped protected code or path fragments")?;
+
+        if let Some(cache) = &self.cache {
+            if let Ok(mut cache) = cache.lock() {
+                cache.insert(key, translated);
+            }
+        }
+
+        Ok(restored)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn temp_cache_file(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "byebyecode-translations-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("translations.json")
+    }
+
+    #[test]
+    fn key_depends_on_engine_target_and_text() {
+        let key = TranslationCache::key("hello", Language::Chinese, "glm||");
+        assert_eq!(
+            key,
+            TranslationCache::key("hello", Language::Chinese, "glm||")
+        );
+        assert_ne!(
+            key,
+            TranslationCache::key("hello", Language::Chinese, "openai||gpt-4o-mini")
+        );
+        assert_ne!(
+            key,
+            TranslationCache::key("hello", Language::English, "glm||")
+        );
+        assert_ne!(
+            key,
+            TranslationCache::key("hello!", Language::Chinese, "glm||")
+        );
+    }
+
+    #[test]
+    fn concurrent_writers_keep_each_others_entries() {
+        let path = temp_cache_file("merge");
+        let mut first = TranslationCache::open(Some(path.clone()));
+        let mut second = TranslationCache::open(Some(path.clone()));
+
+        first.insert("a".to_string(), "甲".to_string());
+        second.insert("b".to_string(), "乙".to_string());
+
+        let reloaded = TranslationCache::open(Some(path.clone()));
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+        assert_eq!(reloaded.get("a"), Some("甲"));
+        assert_eq!(reloaded.get("b"), Some("乙"));
+    }
+
+    #[test]
+    fn oldest_entries_are_evicted_over_the_limit() {
+        let mut cache = TranslationCache::open(None);
+        for i in 0..=MAX_CACHE_ENTRIES {
+            cache.entries.insert(
+                i.to_string(),
+                CacheEntry {
+                    translation: String::new(),
+                    created_at: i as u64,
+                },
+            );
+        }
+        cache.evict();
+        assert_eq!(cache.entries.len(), MAX_CACHE_ENTRIES);
+        assert!(cache.get("0").is_none());
+        assert!(cache.get("1").is_some());
+    }
+
+    /// 记录调用次数的翻译器，原样返回加了前缀的文本
+    struct Echo {
+        calls: Arc<AtomicUsize>,
+        drop_placeholders: bool,
+    }
+
+    impl Translator for Echo {
+        fn translate(
+            &self,
+            text: &str,
+            _target: Language,
+        ) -> Result<String, Box<dyn std::error::Error>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self.drop_placeholders {
+                return Ok("译文".to_string());
+            }
+            Ok(format!("译：{}", text))
+        }
+    }
+
+    fn translator(drop_placeholders: bool) -> (CachedTranslator, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let inner = Echo {
+            calls: calls.clone(),
+            drop_placeholders,
+        };
+        let translator = CachedTranslator::new(
+            Box::new(inner),
+            "test".to_string(),
+            false,
+            vec!["PR".to_string()],
+        );
+        (translator, calls)
+    }
+
+    #[test]
+    fn protected_fragments_survive_translation() {
+        let (translator, calls) = translator(false);
+        let translated = translator
+            .translate("Open the PR and edit src/main.rs", Language::Chinese)
+            .unwrap();
+        assert_eq!(translated, "译：Open the PR and edit src/main.rs");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // 只有路径和代码时不调用翻译接口
+        assert_eq!(
+            translator
+                .translate("`cargo test` src/main.rs", Language::Chinese)
+                .unwrap(),
+            "`cargo test` src/main.rs"
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn dropped_placeholders_are_an_error() {
+        let (translator, _) = translator(true);
+        assert!(translator
+            .translate("edit src/main.rs please", Language::Chinese)
+            .is_err());
+    }
+}
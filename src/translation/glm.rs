@@ -0,0 +1,58 @@
+use super::{build_http_client, chat_completion, Language, TranslationConfig, Translator};
+use crate::config::HttpConfig;
+use reqwest::blocking::Client;
+
+const GLM_BASE_URL: &str = "https://open.bigmodel.cn/api/paas/v4";
+const GLM_DEFAULT_MODEL: &str = "glm-4.5-flash";
+
+/// 智谱 GLM 翻译（默认使用免费的 GLM-4.5-Flash）
+#[derive(Clone)]
+pub struct GLMTranslator {
+    client: Client,
+    api_key: String,
+    base_url: String,
+    model: String,
+}
+
+impl GLMTranslator {
+    pub fn new(
+        config: TranslationConfig,
+        http: &HttpConfig,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            client: build_http_client(http, config.timeout_secs)?,
+            api_key: config.api_key,
+            base_url: config
+                .base_url
+                .filter(|s| !s.is_empty())
+                .unwrap_or_else(|| GLM_BASE_URL.to_string()),
+            model: config
+                .model
+                .filter(|s| !s.is_empty())
+                .unwrap_or_else(|| GLM_DEFAULT_MODEL.to_string()),
+        })
+    }
+}
+
+impl Translator for GLMTranslator {
+    fn translate(
+        &self,
+        text: &str,
+        target: Language,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        // GLM-4.5 系列默认开启深度思考，翻译场景关闭以降低延迟
+        let body = serde_json::json!({
+            "model": self.model,
+            "temperature": 0.1,
+            "thinking": { "type": "disabled" },
+        });
+        chat_completion(
+            &self.client,
+            &self.base_url,
+            &self.api_key,
+            body,
+            text,
+            target,
+        )
+    }
+}
@@ -0,0 +1,248 @@
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// 受保护片段的占位符，翻译前替换、翻译后还原
+const PLACEHOLDER_OPEN: char = '⟦';
+const PLACEHOLDER_CLOSE: char = '⟧';
+
+/// 常见源码/配置文件扩展名，用于识别不带目录的文件名
+const FILE_EXTENSIONS: &str = "rs|toml|json|jsonl|yaml|yml|md|txt|lock|ts|tsx|js|jsx|mjs|py|go|java|kt|c|h|cc|cpp|hpp|cs|rb|php|sh|bash|zsh|ps1|sql|html|css|scss|vue|svelte|xml|ini|env|log";
+
+fn fenced_code_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    // Unterminated fences run to the end of the text
+    PATTERN.get_or_init(|| Regex::new(r"(?s)```.*?(?:```|\z)").expect("invalid fence pattern"))
+}
+
+fn protected_patterns() -> &'static [Regex] {
+    static PATTERNS: OnceLock<Vec<Regex>> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        [
+            // Inline code
+            r"`[^`\n]+`",
+            // URLs
+            r#"https?://[^\s<>"'`]+"#,
+            // Paths with at least one separator: src/main.rs, ~/.claude, C:\Users\me
+            r"(?:[A-Za-z]:\\|~/|\.{1,2}/|/)?(?:[A-Za-z0-9_.\-]+[/\\])+[A-Za-z0-9_.\-]*",
+            // Bare file names with a known extension: Cargo.toml, main.rs
+            &format!(
+                r"[A-Za-z0-9_\-]+(?:\.[A-Za-z0-9_\-]+)*\.(?:{})(?-u:\b)",
+                FILE_EXTENSIONS
+            ),
+        ]
+        .iter()
+        .map(|p| Regex::new(p).expect("invalid protected pattern"))
+        .collect()
+    })
+}
+
+/// 判断一行是否像代码（Claude Code 渲染后的代码片段不再带 ``` 围栏）
+fn looks_like_code(line: &str) -> bool {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return false;
+    }
+
+    const CODE_PREFIXES: &[&str] = &[
+        "fn ",
+        "pub ",
+        "let ",
+        "use ",
+        "impl ",
+        "struct ",
+        "enum ",
+        "mod ",
+        "const ",
+        "def ",
+        "class ",
+        "import ",
+        "from ",
+        "export ",
+        "function ",
+        "return ",
+        "#include",
+        "$ ",
+        "//",
+        "#[",
+    ];
+    if CODE_PREFIXES.iter().any(|p| trimmed.starts_with(p)) {
+        return true;
+    }
+    if trimmed.ends_with(';') || trimmed.ends_with('{') || trimmed == "}" || trimmed == "};" {
+        return true;
+    }
+
+    // 符号占比过高视为代码
+    let symbols = trimmed
+        .chars()
+        .filter(|c| {
+            matches!(
+                c,
+                '{' | '}' | '(' | ')' | '[' | ']' | ';' | '=' | '<' | '>' | '&' | '|'
+            )
+        })
+        .count();
+    symbols * 5 > trimmed.chars().count()
+}
+
+/// 已替换受保护片段的文本
+pub struct ProtectedText {
+    /// 含占位符的待翻译文本
+    pub text: String,
+    fragments: Vec<String>,
+}
+
+impl ProtectedText {
+    /// 把代码块、行内代码、URL、文件路径和术语表中的词替换为占位符
+    pub fn new(source: &str, glossary: &[String]) -> Self {
+        let mut protected = Self {
+            text: String::with_capacity(source.len()),
+            fragments: Vec::new(),
+        };
+
+        // 代码块和整行代码先行保护，保证多行代码片段原样保留
+        let text = protected.replace_all(fenced_code_pattern(), source, |_, _| true);
+        let lines: Vec<String> = text
+            .split('\n')
+            .map(|line| {
+                if looks_like_code(line) {
+                    protected.placeholder(line)
+                } else {
+                    line.to_string()
+                }
+            })
+            .collect();
+        let mut text = lines.join("\n");
+
+        for pattern in protected_patterns() {
+            text = protected.replace_all(pattern, &text, |_, _| true);
+        }
+
+        for term in glossary.iter().filter(|t| !t.trim().is_empty()) {
+            let pattern = Regex::new(&format!("(?i){}", regex::escape(term.trim())));
+            if let Ok(pattern) = pattern {
+                text = protected.replace_all(&pattern, &text, is_whole_word);
+            }
+        }
+
+        protected.text = text;
+        protected
+    }
+
+    fn placeholder(&mut self, fragment: &str) -> String {
+        self.fragments.push(fragment.to_string());
+        format!(
+            "{}{}{}",
+            PLACEHOLDER_OPEN,
+            self.fragments.len() - 1,
+            PLACEHOLDER_CLOSE
+        )
+    }
+
+    fn replace_all(
+        &mut self,
+        pattern: &Regex,
+        text: &str,
+        accept: impl Fn(&str, std::ops::Range<usize>) -> bool,
+    ) -> String {
+        let mut result = String::with_capacity(text.len());
+        let mut last = 0;
+        for m in pattern.find_iter(text) {
+            // 不拆分已有占位符
+            if m.as_str().contains(PLACEHOLDER_OPEN)
+                || m.as_str().contains(PLACEHOLDER_CLOSE)
+                || !accept(text, m.range())
+            {
+                continue;
+            }
+            result.push_str(&text[last..m.start()]);
+            result.push_str(&self.placeholder(m.as_str()));
+            last = m.end();
+        }
+        result.push_str(&text[last..]);
+        result
+    }
+
+    /// 除占位符外是否还有需要翻译的文字
+    pub fn has_translatable_text(&self) -> bool {
+        let mut inside = false;
+        self.text.chars().any(|c| {
+            match c {
+                PLACEHOLDER_OPEN => inside = true,
+                PLACEHOLDER_CLOSE => inside = false,
+                _ => return !inside && c.is_alphabetic(),
+            }
+            false
+        })
+    }
+
+    /// 把译文中的占位符还原为原始片段
+    /// 译文丢失了任意占位符时返回 None，调用方应回退为原文
+    pub fn restore(&self, translated: &str) -> Option<String> {
+        let mut result = translated.to_string();
+        for (index, fragment) in self.fragments.iter().enumerate() {
+            let placeholder = format!("{}{}{}", PLACEHOLDER_OPEN, index, PLACEHOLDER_CLOSE);
+            if !result.contains(&placeholder) {
+                return None;
+            }
+            result = result.replacen(&placeholder, fragment, 1);
+        }
+        Some(result)
+    }
+}
+
+/// 术语按整词匹配，如 `PR` 不会命中 `PRINT`
+/// 只看 ASCII 字母数字，保证紧贴中文的术语（如“提交PR”）也能命中
+fn is_whole_word(text: &str, range: std::ops::Range<usize>) -> bool {
+    let before = text[..range.start].chars().next_back();
+    let after = text[range.end..].chars().next();
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_ascii_alphanumeric() || c == '_');
+    !is_word(before) && !is_word(after)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn protect(source: &str, glossary: &[&str]) -> ProtectedText {
+        let glossary: Vec<String> = glossary.iter().map(|t| t.to_string()).collect();
+        ProtectedText::new(source, &glossary)
+    }
+
+    #[test]
+    fn code_paths_and_urls_become_placeholders() {
+        let protected = protect(
+            "Run `cargo build` in ~/work/crate, see https://example.com/a?b=1 and Cargo.toml",
+            &[],
+        );
+        assert_eq!(protected.text, "Run ⟦0⟧ in ⟦2⟧, see ⟦1⟧ and ⟦3⟧");
+        assert_eq!(
+            protected.restore(&protected.text).unwrap(),
+            "Run `cargo build` in ~/work/crate, see https://example.com/a?b=1 and Cargo.toml"
+        );
+    }
+
+    #[test]
+    fn code_blocks_and_code_lines_are_kept_whole() {
+        let protected = protect("Change this:\n```rust\nlet x = 1;\n```\nlet y = 2;", &[]);
+        assert_eq!(protected.text, "Change this:\n⟦0⟧\n⟦1⟧");
+        assert!(protected.has_translatable_text());
+        assert!(!protect("```\nfn main() {}\n```", &[]).has_translatable_text());
+    }
+
+    #[test]
+    fn glossary_terms_match_whole_words_only() {
+        let protected = protect("Open a PR, then PRINT it; 提交PR", &["PR"]);
+        assert_eq!(protected.text, "Open a ⟦0⟧, then PRINT it; 提交⟦1⟧");
+    }
+
+    #[test]
+    fn restore_fails_when_a_placeholder_is_missing() {
+        let protected = protect("edit src/main.rs and src/lib.rs", &[]);
+        assert_eq!(
+            protected.restore("修改 ⟦1⟧ 和 ⟦0⟧").unwrap(),
+            "修改 src/lib.rs 和 src/main.rs"
+        );
+        assert!(protected.restore("修改 ⟦0⟧").is_none());
+    }
+}
@@ -0,0 +1,372 @@
+pub mod cache;
+pub mod glm;
+pub mod glossary;
+pub mod openai;
+
+use crate::config::HttpConfig;
+use reqwest::blocking::Client;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// 翻译服务配置（config.toml 中的 `[translation]` 段）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranslationConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 翻译服务类型：glm 或 openai（任意 OpenAI 兼容接口）
+    #[serde(default)]
+    pub provider: TranslationProvider,
+    /// 密钥，也可以是 `env:NAME`、`file:PATH`、`cmd:COMMAND` 引用
+    #[serde(default)]
+    pub api_key: String,
+    /// 接口地址（不含 /chat/completions），为空时使用服务默认地址
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    /// 模型名称，为空时使用服务默认模型
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// 请求超时（秒）
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// 是否启用持久化翻译缓存（~/.claude/byebyecode/cache/translations.json）
+    #[serde(default = "default_cache")]
+    pub cache: bool,
+    /// 术语表：这些词保持原文不翻译，如 "commit"、"PR"、crate 名称
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub glossary: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TranslationProvider {
+    #[default]
+    Glm,
+    #[serde(alias = "openai_compatible")]
+    Openai,
+}
+
+impl TranslationProvider {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TranslationProvider::Glm => "glm",
+            TranslationProvider::Openai => "openai",
+        }
+    }
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+fn default_cache() -> bool {
+    true
+}
+
+impl Default for TranslationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            provider: TranslationProvider::default(),
+            api_key: String::new(),
+            base_url: None,
+            model: None,
+            timeout_secs: default_timeout_secs(),
+            cache: default_cache(),
+            glossary: Vec::new(),
+        }
+    }
+}
+
+impl TranslationConfig {
+    /// 是否可以启用翻译
+    /// OpenAI 兼容接口指定了自定义地址时允许不配置密钥（如本地模型）
+    pub fn is_usable(&self) -> bool {
+        self.enabled
+            && (!self.api_key.is_empty()
+                || (self.provider == TranslationProvider::Openai && self.base_url.is_some()))
+    }
+}
+
+/// 翻译目标语言
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Language {
+    English,
+    Chinese,
+}
+
+impl Language {
+    fn prompt_name(&self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::Chinese => "Simplified Chinese",
+        }
+    }
+}
+
+pub trait Translator: Send + Sync {
+    fn translate(&self, text: &str, target: Language)
+        -> Result<String, Box<dyn std::error::Error>>;
+
+    fn translate_to_english(&self, text: &str) -> Result<String, Box<dyn std::error::Error>> {
+        self.translate(text, Language::English)
+    }
+
+    fn translate_to_chinese(&self, text: &str) -> Result<String, Box<dyn std::error::Error>> {
+        self.translate(text, Language::Chinese)
+    }
+}
+
+/// 根据配置创建翻译器（带缓存、术语表和代码/路径保护）
+pub fn create_translator(
+    mut config: TranslationConfig,
+    http: &HttpConfig,
+) -> Result<Box<dyn Translator>, Box<dyn std::error::Error>> {
+    // api_key 可以是 env:/file:/cmd: 引用，创建翻译器时才解析
+    config.api_key = crate::config::secrets::resolve(&config.api_key).unwrap_or_default();
+    let use_cache = config.cache;
+    let glossary = config.glossary.clone();
+    // 缓存按翻译服务、接口地址和模型区分，换模型后不会复用旧模型的译文
+    let engine = format!(
+        "{}|{}|{}",
+        config.provider.name(),
+        config.base_url.as_deref().unwrap_or_default(),
+        config.model.as_deref().unwrap_or_default()
+    );
+
+    let inner: Box<dyn Translator> = match config.provider {
+        TranslationProvider::Glm => Box::new(glm::GLMTranslator::new(config, http)?),
+        TranslationProvider::Openai => Box::new(openai::OpenAITranslator::new(config, http)?),
+    };
+
+    Ok(Box::new(cache::CachedTranslator::new(
+        inner, engine, use_cache, glossary,
+    )))
+}
+
+/// 判断文本是否包含中文字符
+pub fn contains_chinese(text: &str) -> bool {
+    text.chars()
+        .any(|c| matches!(c, '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}'))
+}
+
+/// 共享的代理、证书设置，超时使用翻译配置的 timeout_secs
+fn build_http_client(
+    http: &HttpConfig,
+    timeout_secs: u64,
+) -> Result<Client, Box<dyn std::error::Error>> {
+    Ok(crate::utils::http::client_builder(http)?
+        .timeout(Duration::from_secs(timeout_secs))
+        .build()?)
+}
+
+fn system_prompt(target: Language) -> String {
+    format!(
+        "You are a translation engine for a coding assistant. Translate the user's text into {}. \
+         Keep code, file paths, commands, identifiers and Markdown formatting unchanged. \
+         Copy placeholders such as ⟦0⟧ exactly as they appear. \
+         Output only the translation without explanations.",
+        target.prompt_name()
+    )
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletionResponse {
+    choices: Vec<ChatChoice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatChoice {
+    message: ChatMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatMessage {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+/// 调用 OpenAI 兼容的 /chat/completions 接口
+fn chat_completion(
+    client: &Client,
+    base_url: &str,
+    api_key: &str,
+    mut body: serde_json::Value,
+    text: &str,
+    target: Language,
+) -> Result<String, Box<dyn std::error::Error>> {
+    if text.trim().is_empty() {
+        return Ok(text.to_string());
+    }
+
+    body["messages"] = serde_json::json!([
+        { "role": "system", "content": system_prompt(target) },
+        { "role": "user", "content": text },
+    ]);
+
+    let url = format!("{}/chat/completions", base_url.trim_end_matches('/'));
+    let mut request = client.post(&url).json(&body);
+    if !api_key.is_empty() {
+        request = request.header("Authorization", format!("Bearer {}", api_key));
+    }
+
+    let response = request.send()?;
+    if !response.status().is_success() {
+        return Err(format!("Translation API request failed: {}", response.status()).into());
+    }
+
+    let response_text = response.text()?;
+    let resp: ChatCompletionResponse = serde_json::from_str(&response_text).map_err(|e| {
+        format!(
+            "Translation JSON parse error: {} | Response: {}",
+            e, response_text
+        )
+    })?;
+
+    resp.choices
+        .into_iter()
+        .next()
+        .and_then(|c| c.message.content)
+        .map(|s| s.trim().to_string())
+        .ok_or_else(|| "Translation API returned no choices".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread::JoinHandle;
+
+    /// 只应答一次的本地 HTTP 服务，返回地址和收到的请求（请求头 + 请求体）
+    fn mock_server(status: &'static str, body: &'static str) -> (String, JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/v1", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request = String::new();
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    content_length = value.trim().parse().unwrap();
+                }
+                request.push_str(&line);
+                if line == "\r\n" || line.is_empty() {
+                    break;
+                }
+            }
+            let mut payload = vec![0; content_length];
+            reader.read_exact(&mut payload).unwrap();
+            request.push_str(&String::from_utf8_lossy(&payload));
+
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            reader.get_mut().write_all(response.as_bytes()).unwrap();
+            request
+        });
+        (url, handle)
+    }
+
+    fn config(base_url: String, provider: TranslationProvider) -> TranslationConfig {
+        TranslationConfig {
+            enabled: true,
+            provider,
+            api_key: "test-key".to_string(),
+            base_url: Some(base_url),
+            timeout_secs: 5,
+            ..TranslationConfig::default()
+        }
+    }
+
+    fn http() -> HttpConfig {
+        // 不读取本机 settings.json 或环境变量中的代理
+        HttpConfig {
+            proxy: "off".to_string(),
+            ..HttpConfig::default()
+        }
+    }
+
+    const OK_BODY: &str = r#"{"choices":[{"message":{"content":"  你好  "}}]}"#;
+
+    #[test]
+    fn glm_translates_via_chat_completions() {
+        let (url, server) = mock_server("200 OK", OK_BODY);
+        let translator =
+            glm::GLMTranslator::new(config(url, TranslationProvider::Glm), &http()).unwrap();
+
+        assert_eq!(translator.translate_to_chinese("hello").unwrap(), "你好");
+        let request = server.join().unwrap();
+        assert!(request.starts_with("POST /v1/chat/completions "));
+        assert!(request.contains("authorization: Bearer test-key"));
+        assert!(request.contains(r#""thinking":{"type":"disabled"}"#));
+        assert!(request.contains(r#""model":"glm-4.5-flash""#));
+        assert!(request.contains("Simplified Chinese"));
+    }
+
+    #[test]
+    fn openai_translates_via_chat_completions() {
+        let (url, server) = mock_server("200 OK", OK_BODY);
+        let mut config = config(url, TranslationProvider::Openai);
+        config.model = Some("local-model".to_string());
+        let translator = openai::OpenAITranslator::new(config, &http()).unwrap();
+
+        assert_eq!(translator.translate_to_english("你好").unwrap(), "你好");
+        let request = server.join().unwrap();
+        assert!(request.starts_with("POST /v1/chat/completions "));
+        assert!(request.contains(r#""model":"local-model""#));
+        assert!(request.contains("into English"));
+    }
+
+    #[test]
+    fn non_success_status_is_an_error() {
+        let (url, server) = mock_server("500 Internal Server Error", r#"{"error":"boom"}"#);
+        let translator =
+            glm::GLMTranslator::new(config(url, TranslationProvider::Glm), &http()).unwrap();
+
+        let error = translator.translate_to_chinese("hello").unwrap_err();
+        assert!(error.to_string().contains("500"), "{}", error);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn malformed_json_is_an_error() {
+        let (url, server) = mock_server("200 OK", "<html>gateway</html>");
+        let translator =
+            openai::OpenAITranslator::new(config(url, TranslationProvider::Openai), &http())
+                .unwrap();
+
+        let error = translator.translate_to_chinese("hello").unwrap_err();
+        assert!(error.to_string().contains("JSON parse error"), "{}", error);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn empty_choices_is_an_error() {
+        let (url, server) = mock_server("200 OK", r#"{"choices":[]}"#);
+        let translator =
+            openai::OpenAITranslator::new(config(url, TranslationProvider::Openai), &http())
+                .unwrap();
+
+        assert!(translator.translate_to_chinese("hello").is_err());
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn blank_text_skips_the_request() {
+        let translator = openai::OpenAITranslator::new(
+            config(
+                "http://127.0.0.1:9".to_string(),
+                TranslationProvider::Openai,
+            ),
+            &http(),
+        )
+        .unwrap();
+        assert_eq!(translator.translate_to_chinese("  \n").unwrap(), "  \n");
+    }
+}
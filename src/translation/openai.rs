@@ -0,0 +1,56 @@
+use super::{build_http_client, chat_completion, Language, TranslationConfig, Translator};
+use crate::config::HttpConfig;
+use reqwest::blocking::Client;
+
+const OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+const OPENAI_DEFAULT_MODEL: &str = "gpt-4o-mini";
+
+/// OpenAI 兼容接口翻译（OpenAI、DeepSeek、本地 Ollama 等）
+#[derive(Clone)]
+pub struct OpenAITranslator {
+    client: Client,
+    api_key: String,
+    base_url: String,
+    model: String,
+}
+
+impl OpenAITranslator {
+    pub fn new(
+        config: TranslationConfig,
+        http: &HttpConfig,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            client: build_http_client(http, config.timeout_secs)?,
+            api_key: config.api_key,
+            base_url: config
+                .base_url
+                .filter(|s| !s.is_empty())
+                .unwrap_or_else(|| OPENAI_BASE_URL.to_string()),
+            model: config
+                .model
+                .filter(|s| !s.is_empty())
+                .unwrap_or_else(|| OPENAI_DEFAULT_MODEL.to_string()),
+        })
+    }
+}
+
+impl Translator for OpenAITranslator {
+    fn translate(
+        &self,
+        text: &str,
+        target: Language,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let body = serde_json::json!({
+            "model": self.model,
+            "temperature": 0.1,
+        });
+        chat_completion(
+            &self.client,
+            &self.base_url,
+            &self.api_key,
+            body,
+            text,
+            target,
+        )
+    }
+}
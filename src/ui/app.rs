@@ -4,6 +4,7 @@ use crate::ui::components::{
     help::HelpComponent,
     icon_selector::IconSelectorComponent,
     name_input::NameInputComponent,
+    options_editor::OptionsEditorComponent,
     preview::PreviewComponent,
     segment_list::{FieldSelection, Panel, SegmentListComponent},
     separator_editor::SeparatorEditorComponent,
@@ -33,6 +34,7 @@ pub struct App {
     color_picker: ColorPickerComponent,
     icon_selector: IconSelectorComponent,
     name_input: NameInputComponent,
+    options_editor: OptionsEditorComponent,
     preview: PreviewComponent,
     segment_list: SegmentListComponent,
     separator_editor: SeparatorEditorComponent,
@@ -53,6 +55,7 @@ impl App {
             color_picker: ColorPickerComponent::new(),
             icon_selector: IconSelectorComponent::new(),
             name_input: NameInputComponent::new(),
+            options_editor: OptionsEditorComponent::new(),
             preview: PreviewComponent::new(),
             segment_list: SegmentListComponent::new(),
             separator_editor: SeparatorEditorComponent::new(),
@@ -71,8 +74,8 @@ impl App {
             eprintln!("Warning: Failed to initialize themes: {}", e);
         }
 
-        // Load config
-        let mut config = Config::load().unwrap_or_else(|_| Config::default());
+        // Load config; a broken one is reported instead of being overwritten with defaults
+        let mut config = Config::load()?;
 
         // If a theme is specified, reload it to get the latest changes
         if !config.theme.is_empty() && config.theme != "default" {
@@ -116,6 +119,22 @@ impl App {
                         KeyCode::Backspace => app.name_input.backspace(),
                         _ => {}
                     }
+                } else if app.options_editor.is_open {
+                    match key.code {
+                        KeyCode::Esc if app.options_editor.editing => {
+                            app.options_editor.cancel_edit()
+                        }
+                        KeyCode::Esc => app.options_editor.close(),
+                        KeyCode::Enter => app.edit_current_option(),
+                        KeyCode::Up => app.options_editor.move_selection(-1),
+                        KeyCode::Down => app.options_editor.move_selection(1),
+                        KeyCode::Char('d') | KeyCode::Char('D') if !app.options_editor.editing => {
+                            app.reset_current_option()
+                        }
+                        KeyCode::Char(c) => app.options_editor.input_char(c),
+                        KeyCode::Backspace => app.options_editor.backspace(),
+                        _ => {}
+                    }
                 } else if app.separator_editor.is_open {
                     match key.code {
                         KeyCode::Esc => app.separator_editor.close(),
@@ -450,6 +469,11 @@ impl App {
         if self.separator_editor.is_open {
             self.separator_editor.render(f, f.area());
         }
+        if self.options_editor.is_open {
+            if let Some(segment) = self.config.segments.get(self.selected_segment) {
+                self.options_editor.render(f, f.area(), &segment.options);
+            }
+        }
     }
 
     fn move_selection(&mut self, delta: i32) {
@@ -506,6 +530,8 @@ impl App {
                         SegmentId::ByeByeCodeUsage => "ByeByeCode Usage",
                         SegmentId::ByeByeCodeSubscription => "ByeByeCode Subscription",
                         SegmentId::ByeByeCodeStatus => "ByeByeCode Status",
+                        SegmentId::BurnRate => "Burn Rate",
+                        SegmentId::Custom => "Custom",
                     };
                     let is_enabled = segment.enabled;
                     self.status_message = Some(format!(
@@ -536,6 +562,8 @@ impl App {
                                 SegmentId::ByeByeCodeUsage => "ByeByeCode Usage",
                                 SegmentId::ByeByeCodeSubscription => "ByeByeCode Subscription",
                                 SegmentId::ByeByeCodeStatus => "ByeByeCode Status",
+                                SegmentId::BurnRate => "Burn Rate",
+                                SegmentId::Custom => "Custom",
                             };
                             let is_enabled = segment.enabled;
                             self.status_message = Some(format!(
@@ -566,9 +594,9 @@ impl App {
                         }
                     }
                     FieldSelection::Options => {
-                        // TODO: Implement options editor
-                        self.status_message =
-                            Some("Options editor not implemented yet".to_string());
+                        if let Some(segment) = self.config.segments.get(self.selected_segment) {
+                            self.options_editor.open(segment.id);
+                        }
                     }
                 }
             }
@@ -658,9 +686,11 @@ impl App {
         }
 
         // Also add any segments from old config that aren't in new theme
-        // (like byebyecode segments if they were manually added)
+        // (like byebyecode segments if they were manually added, or custom commands)
         for old_segment in &self.config.segments {
-            if !merged_segments.iter().any(|s| s.id == old_segment.id) {
+            if old_segment.id == SegmentId::Custom
+                || !merged_segments.iter().any(|s| s.id == old_segment.id)
+            {
                 merged_segments.push(old_segment.clone());
             }
         }
@@ -678,7 +708,7 @@ impl App {
     /// Reset current theme to its default configuration
     fn reset_to_theme_defaults(&mut self) {
         let current_theme = self.config.theme.clone();
-        self.config = crate::ui::themes::ThemePresets::get_theme(&current_theme);
+        self.config = crate::ui::themes::ThemePresets::apply_theme(&self.config, &current_theme);
         self.selected_segment = 0;
         self.preview.update_preview(&self.config);
         self.status_message = Some(format!("Reset {} theme to defaults", current_theme));
@@ -746,4 +776,26 @@ impl App {
         self.status_message = Some("Opening separator editor...".to_string());
         self.separator_editor.open(&self.config.style.separator);
     }
+
+    /// Apply Enter in the options editor to the selected segment
+    fn edit_current_option(&mut self) {
+        let Some(segment) = self.config.segments.get_mut(self.selected_segment) else {
+            return;
+        };
+        if self.options_editor.activate(&mut segment.options) {
+            self.status_message = Some(format!("{} options updated", segment.id.config_name()));
+            self.preview.update_preview(&self.config);
+        }
+    }
+
+    /// Reset the selected option to its default
+    fn reset_current_option(&mut self) {
+        let Some(segment) = self.config.segments.get_mut(self.selected_segment) else {
+            return;
+        };
+        if self.options_editor.reset(&mut segment.options) {
+            self.status_message = Some("Option reset to default".to_string());
+            self.preview.update_preview(&self.config);
+        }
+    }
 }
@@ -3,6 +3,7 @@ pub mod editor;
 pub mod help;
 pub mod icon_selector;
 pub mod name_input;
+pub mod options_editor;
 pub mod preview;
 pub mod segment_list;
 pub mod separator_editor;
@@ -0,0 +1,304 @@
+use crate::config::secrets::{self, SecretRef};
+use crate::config::{OptionKind, OptionSpec, SegmentId};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Popup for editing a segment's options, driven by its option schema
+#[derive(Debug, Clone)]
+pub struct OptionsEditorComponent {
+    pub is_open: bool,
+    pub editing: bool,
+    pub input: String,
+    segment: Option<SegmentId>,
+    selected: usize,
+    error: Option<String>,
+}
+
+impl Default for OptionsEditorComponent {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OptionsEditorComponent {
+    pub fn new() -> Self {
+        Self {
+            is_open: false,
+            editing: false,
+            input: String::new(),
+            segment: None,
+            selected: 0,
+            error: None,
+        }
+    }
+
+    pub fn open(&mut self, segment: SegmentId) {
+        self.is_open = true;
+        self.editing = false;
+        self.input.clear();
+        self.segment = Some(segment);
+        self.selected = 0;
+        self.error = None;
+    }
+
+    pub fn close(&mut self) {
+        self.is_open = false;
+        self.editing = false;
+        self.input.clear();
+        self.error = None;
+    }
+
+    fn specs(&self) -> Vec<&'static OptionSpec> {
+        self.segment
+            .map(|id| id.option_schema())
+            .unwrap_or_default()
+    }
+
+    fn selected_spec(&self) -> Option<&'static OptionSpec> {
+        self.specs().get(self.selected).copied()
+    }
+
+    pub fn move_selection(&mut self, delta: i32) {
+        let len = self.specs().len();
+        if len == 0 || self.editing {
+            return;
+        }
+        self.selected = (self.selected as i32 + delta).rem_euclid(len as i32) as usize;
+        self.error = None;
+    }
+
+    pub fn input_char(&mut self, c: char) {
+        if self.editing && !c.is_control() {
+            self.input.push(c);
+        }
+    }
+
+    pub fn backspace(&mut self) {
+        if self.editing {
+            self.input.pop();
+        }
+    }
+
+    /// Stop text editing without changing the option
+    pub fn cancel_edit(&mut self) {
+        self.editing = false;
+        self.input.clear();
+        self.error = None;
+    }
+
+    /// Enter on the selected option: toggle bools, cycle enums, otherwise start or
+    /// finish text editing. Returns true when `options` changed.
+    pub fn activate(&mut self, options: &mut HashMap<String, Value>) -> bool {
+        let Some(spec) = self.selected_spec() else {
+            return false;
+        };
+        self.error = None;
+
+        if self.editing {
+            return match spec.kind.parse_input(&self.input) {
+                Ok(value) => {
+                    // An empty value falls back to the default
+                    if value.as_str() == Some("") {
+                        options.remove(spec.key);
+                    } else {
+                        options.insert(spec.key.to_string(), value);
+                    }
+                    self.cancel_edit();
+                    true
+                }
+                Err(e) => {
+                    self.error = Some(e);
+                    false
+                }
+            };
+        }
+
+        let current = options.get(spec.key).cloned().or(spec.default_value());
+        match spec.kind {
+            OptionKind::Bool => {
+                let enabled = current.and_then(|v| v.as_bool()).unwrap_or(false);
+                options.insert(spec.key.to_string(), Value::Bool(!enabled));
+                true
+            }
+            OptionKind::Enum(values) => {
+                let current = current
+                    .as_ref()
+                    .and_then(|v| v.as_str())
+                    .and_then(|s| values.iter().position(|v| v.eq_ignore_ascii_case(s)));
+                let next = current.map_or(0, |i| (i + 1) % values.len());
+                options.insert(
+                    spec.key.to_string(),
+                    Value::String(values[next].to_string()),
+                );
+                true
+            }
+            OptionKind::RelaySchema | OptionKind::Rules | OptionKind::StringMap => {
+                self.error = Some(format!("{} can only be edited in config.toml", spec.key));
+                false
+            }
+            OptionKind::Secret => {
+                // Start literal keys from an empty input instead of revealing them
+                self.editing = true;
+                self.input = current
+                    .as_ref()
+                    .and_then(|v| v.as_str())
+                    .filter(|s| !SecretRef::parse(s).is_literal())
+                    .unwrap_or_default()
+                    .to_string();
+                false
+            }
+            _ => {
+                self.editing = true;
+                self.input = match current {
+                    Some(Value::String(s)) => s,
+                    Some(value) => value.to_string(),
+                    None => String::new(),
+                };
+                false
+            }
+        }
+    }
+
+    /// Remove the selected option so the segment falls back to its default
+    pub fn reset(&mut self, options: &mut HashMap<String, Value>) -> bool {
+        if self.editing {
+            return false;
+        }
+        match self.selected_spec() {
+            Some(spec) => options.remove(spec.key).is_some(),
+            None => false,
+        }
+    }
+
+    pub fn render(&self, f: &mut Frame, area: Rect, options: &HashMap<String, Value>) {
+        if !self.is_open {
+            return;
+        }
+
+        let specs = self.specs();
+        let popup_width = 76_u16.min(area.width.saturating_sub(4));
+        let list_height = (specs.len().max(1) as u16) * 2 + 2;
+        let popup_height = (list_height + 7).min(area.height.saturating_sub(2));
+        let popup_area = Rect {
+            x: (area.width.saturating_sub(popup_width)) / 2,
+            y: (area.height.saturating_sub(popup_height)) / 2,
+            width: popup_width,
+            height: popup_height,
+        };
+
+        f.render_widget(Clear, popup_area);
+
+        let title = match self.segment {
+            Some(id) => format!("Options: {}", id.config_name()),
+            None => "Options".to_string(),
+        };
+        let popup_block = Block::default().borders(Borders::ALL).title(title);
+        let inner = popup_block.inner(popup_area);
+        f.render_widget(popup_block, popup_area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(3),    // Option list
+                Constraint::Length(3), // Input or error
+                Constraint::Length(2), // Actions
+            ])
+            .split(inner);
+
+        // Option list: "key = value" with type and description below
+        let mut lines = Vec::new();
+        if specs.is_empty() {
+            lines.push(Line::from(Span::styled(
+                "This segment has no options",
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+        for (i, spec) in specs.iter().enumerate() {
+            let (value, origin) = match options.get(spec.key) {
+                Some(Value::String(secret)) if spec.kind == OptionKind::Secret => {
+                    (secrets::redact(secret), "")
+                }
+                Some(value) => (display_value(value), ""),
+                None => match spec.default_value() {
+                    Some(value) => (display_value(&value), " (default)"),
+                    None => ("-".to_string(), " (unset)"),
+                },
+            };
+            let selected = i == self.selected;
+            let key_style = if selected {
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            lines.push(Line::from(vec![
+                Span::raw(if selected { "▶ " } else { "  " }),
+                Span::styled(spec.key, key_style),
+                Span::raw(" = "),
+                Span::styled(value, Style::default().fg(Color::Yellow)),
+                Span::styled(origin, Style::default().fg(Color::DarkGray)),
+            ]));
+            lines.push(Line::from(Span::styled(
+                format!("    [{}] {}", spec.kind.name(), spec.description),
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+        f.render_widget(
+            Paragraph::new(lines).block(Block::default().borders(Borders::ALL)),
+            chunks[0],
+        );
+
+        // Input field or the last error
+        let (text, style) = if let Some(error) = &self.error {
+            (error.clone(), Style::default().fg(Color::Red))
+        } else if self.editing {
+            let shown = match self.selected_spec() {
+                Some(spec)
+                    if spec.kind == OptionKind::Secret
+                        && SecretRef::parse(&self.input).is_literal() =>
+                {
+                    "*".repeat(self.input.chars().count())
+                }
+                _ => self.input.clone(),
+            };
+            (format!("> {} <", shown), Style::default().fg(Color::Yellow))
+        } else {
+            (
+                "Enter: toggle bool / cycle choice / edit value".to_string(),
+                Style::default().fg(Color::DarkGray),
+            )
+        };
+        f.render_widget(
+            Paragraph::new(text)
+                .style(style)
+                .wrap(Wrap { trim: true })
+                .block(Block::default().borders(Borders::ALL).title("Value")),
+            chunks[1],
+        );
+
+        let actions = if self.editing {
+            "[Enter] Apply  [Esc] Cancel edit"
+        } else {
+            "[↑↓] Select  [Enter] Edit  [D] Reset to default  [Esc] Close"
+        };
+        f.render_widget(Paragraph::new(actions), chunks[2]);
+    }
+}
+
+fn display_value(value: &Value) -> String {
+    match value {
+        Value::String(s) if s.is_empty() => "\"\"".to_string(),
+        Value::String(s) => s.clone(),
+        Value::Object(_) => "{ ... }".to_string(),
+        Value::Array(items) => format!("[{} item(s)]", items.len()),
+        other => other.to_string(),
+    }
+}
@@ -110,6 +110,8 @@ impl PreviewComponent {
                     metadata: {
                         let mut map = HashMap::new();
                         map.insert("current_dir".to_string(), "~/CCometixLine".to_string());
+                        map.insert("name".to_string(), "CCometixLine".to_string());
+                        map.insert("path".to_string(), "CCometixLine".to_string());
                         map
                     },
                 },
@@ -122,6 +124,12 @@ impl PreviewComponent {
                         map.insert("status".to_string(), "Clean".to_string());
                         map.insert("ahead".to_string(), "0".to_string());
                         map.insert("behind".to_string(), "0".to_string());
+                        map.insert("staged".to_string(), "0".to_string());
+                        map.insert("modified".to_string(), "0".to_string());
+                        map.insert("untracked".to_string(), "0".to_string());
+                        map.insert("conflicted".to_string(), "0".to_string());
+                        map.insert("stash".to_string(), "0".to_string());
+                        map.insert("upstream".to_string(), "origin/master".to_string());
                         map
                     },
                 },
@@ -133,6 +141,14 @@ impl PreviewComponent {
                         map.insert("total_tokens".to_string(), "156400".to_string());
                         map.insert("percentage".to_string(), "78.2".to_string());
                         map.insert("session_tokens".to_string(), "48200".to_string());
+                        map.insert("tokens".to_string(), "156400".to_string());
+                        map.insert("limit".to_string(), "200000".to_string());
+                        map.insert("input_tokens".to_string(), "12".to_string());
+                        map.insert("cache_read_tokens".to_string(), "150100".to_string());
+                        map.insert("cache_creation_tokens".to_string(), "5400".to_string());
+                        map.insert("output_tokens".to_string(), "888".to_string());
+                        map.insert("growth_per_turn".to_string(), "4200".to_string());
+                        map.insert("turns_left".to_string(), "9".to_string());
                         map
                     },
                 },
@@ -212,6 +228,33 @@ impl PreviewComponent {
                     secondary: "".to_string(),
                     metadata: HashMap::new(),
                 },
+                SegmentId::BurnRate => SegmentData {
+                    primary: "$1.20/h".to_string(),
+                    secondary: "~8.3h耗尽".to_string(),
+                    metadata: {
+                        let mut map = HashMap::new();
+                        map.insert("per_hour".to_string(), "1.20".to_string());
+                        map.insert("hours_left".to_string(), "8.30".to_string());
+                        map
+                    },
+                },
+                SegmentId::Custom => {
+                    let name = segment_config
+                        .options
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("custom");
+                    SegmentData {
+                        primary: format!("{} output", name),
+                        secondary: "".to_string(),
+                        metadata: {
+                            let mut map = HashMap::new();
+                            map.insert("name".to_string(), name.to_string());
+                            map.insert("exit_code".to_string(), "0".to_string());
+                            map
+                        },
+                    }
+                }
             };
 
             segments_data.push((segment_config.clone(), mock_data));
@@ -71,6 +71,12 @@ impl SegmentListComponent {
                     }
                     SegmentId::ByeByeCodeSubscription => "88code 订阅",
                     SegmentId::ByeByeCodeStatus => "88code 状态",
+                    SegmentId::BurnRate => "消耗速率",
+                    SegmentId::Custom => segment
+                        .options
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("自定义命令"),
                 };
 
                 if is_selected {
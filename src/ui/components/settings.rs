@@ -39,6 +39,8 @@ impl SettingsComponent {
                 SegmentId::ByeByeCodeUsage => "88code 用量",
                 SegmentId::ByeByeCodeSubscription => "88code 订阅",
                 SegmentId::ByeByeCodeStatus => "88code 状态",
+                SegmentId::BurnRate => "消耗速率",
+                SegmentId::Custom => "自定义命令",
             };
             let current_icon = match config.style.mode {
                 StyleMode::Plain => &segment.icon.plain,
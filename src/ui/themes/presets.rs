@@ -1,6 +1,10 @@
 // Theme presets for TUI configuration
 
-use crate::config::{Config, StyleConfig, StyleMode};
+use crate::config::{
+    migrations, Config, HttpConfig, LayoutConfig, RelayConfig, StyleConfig, StyleMode,
+    CURRENT_CONFIG_VERSION,
+};
+use crate::translation::TranslationConfig;
 
 // Import all theme modules
 use super::{
@@ -32,6 +36,18 @@ impl ThemePresets {
         }
     }
 
+    /// `config` with the style and segments of `theme_name`; settings that are not part of
+    /// a theme (translation, relay, network, layout, trusted projects) are kept
+    pub fn apply_theme(config: &Config, theme_name: &str) -> Config {
+        let themed = Self::get_theme(theme_name);
+        Config {
+            style: themed.style,
+            segments: themed.segments,
+            theme: themed.theme,
+            ..config.clone()
+        }
+    }
+
     /// Load theme from file system
     pub fn load_theme_from_file(theme_name: &str) -> Result<Config, Box<dyn std::error::Error>> {
         let themes_dir = Self::get_themes_path();
@@ -42,7 +58,10 @@ impl ThemePresets {
         }
 
         let content = std::fs::read_to_string(&theme_path)?;
-        let mut config: Config = toml::from_str(&content)?;
+        // Theme files are upgraded in memory only
+        let mut table: toml::Table = toml::from_str(&content)?;
+        migrations::migrate(&mut table)?;
+        let mut config: Config = toml::Value::Table(table).try_into()?;
 
         // Ensure the theme field matches the requested theme
         config.theme = theme_name.to_string();
@@ -50,13 +69,9 @@ impl ThemePresets {
         Ok(config)
     }
 
-    /// Get the themes directory path (~/.claude/byebyecode/themes/)
-    fn get_themes_path() -> std::path::PathBuf {
-        if let Some(home) = dirs::home_dir() {
-            home.join(".claude/byebyecode").join("themes")
-        } else {
-            std::path::PathBuf::from(".claude/byebyecode/themes")
-        }
+    /// Get the themes directory path (`themes/` in the byebyecode home)
+    pub fn get_themes_path() -> std::path::PathBuf {
+        crate::utils::paths::byebyecode_home_or_default().join("themes")
     }
 
     /// Save current config as a new theme
@@ -70,6 +85,8 @@ impl ThemePresets {
         // Create a copy of config with the correct theme name
         let mut theme_config = config.clone();
         theme_config.theme = theme_name.to_string();
+        // Translation settings (including API keys) don't belong in shareable theme files
+        theme_config.translation = TranslationConfig::default();
 
         let content = toml::to_string_pretty(&theme_config)?;
         std::fs::write(&theme_path, content)?;
@@ -124,6 +141,7 @@ impl ThemePresets {
 
     pub fn get_cometix() -> Config {
         Config {
+            version: CURRENT_CONFIG_VERSION,
             style: StyleConfig {
                 mode: StyleMode::NerdFont,
                 separator: " | ".to_string(),
@@ -139,13 +157,20 @@ impl ThemePresets {
                 theme_cometix::output_style_segment(),
                 theme_cometix::byebyecode_usage_segment(),
                 theme_cometix::byebyecode_subscription_segment(),
+                theme_cometix::burn_rate_segment(),
             ],
             theme: "cometix".to_string(),
+            translation: TranslationConfig::default(),
+            relay: RelayConfig::default(),
+            http: HttpConfig::default(),
+            layout: LayoutConfig::default(),
+            trusted_projects: Vec::new(),
         }
     }
 
     pub fn get_default() -> Config {
         Config {
+            version: CURRENT_CONFIG_VERSION,
             style: StyleConfig {
                 mode: StyleMode::Plain,
                 separator: " | ".to_string(),
@@ -161,14 +186,21 @@ impl ThemePresets {
                 theme_default::output_style_segment(),
                 theme_default::byebyecode_usage_segment(),
                 theme_default::byebyecode_subscription_segment(),
+                theme_default::burn_rate_segment(),
                 theme_default::byebyecode_status_segment(),
             ],
             theme: "default".to_string(),
+            translation: TranslationConfig::default(),
+            relay: RelayConfig::default(),
+            http: HttpConfig::default(),
+            layout: LayoutConfig::default(),
+            trusted_projects: Vec::new(),
         }
     }
 
     pub fn get_minimal() -> Config {
         Config {
+            version: CURRENT_CONFIG_VERSION,
             style: StyleConfig {
                 mode: StyleMode::Plain,
                 separator: " │ ".to_string(),
@@ -184,13 +216,20 @@ impl ThemePresets {
                 theme_minimal::output_style_segment(),
                 theme_minimal::byebyecode_usage_segment(),
                 theme_minimal::byebyecode_subscription_segment(),
+                theme_minimal::burn_rate_segment(),
             ],
             theme: "minimal".to_string(),
+            translation: TranslationConfig::default(),
+            relay: RelayConfig::default(),
+            http: HttpConfig::default(),
+            layout: LayoutConfig::default(),
+            trusted_projects: Vec::new(),
         }
     }
 
     pub fn get_gruvbox() -> Config {
         Config {
+            version: CURRENT_CONFIG_VERSION,
             style: StyleConfig {
                 mode: StyleMode::NerdFont,
                 separator: " | ".to_string(),
@@ -206,13 +245,20 @@ impl ThemePresets {
                 theme_gruvbox::output_style_segment(),
                 theme_gruvbox::byebyecode_usage_segment(),
                 theme_gruvbox::byebyecode_subscription_segment(),
+                theme_gruvbox::burn_rate_segment(),
             ],
             theme: "gruvbox".to_string(),
+            translation: TranslationConfig::default(),
+            relay: RelayConfig::default(),
+            http: HttpConfig::default(),
+            layout: LayoutConfig::default(),
+            trusted_projects: Vec::new(),
         }
     }
 
     pub fn get_nord() -> Config {
         Config {
+            version: CURRENT_CONFIG_VERSION,
             style: StyleConfig {
                 mode: StyleMode::NerdFont,
                 separator: "".to_string(),
@@ -228,13 +274,20 @@ impl ThemePresets {
                 theme_nord::output_style_segment(),
                 theme_nord::byebyecode_usage_segment(),
                 theme_nord::byebyecode_subscription_segment(),
+                theme_nord::burn_rate_segment(),
             ],
             theme: "nord".to_string(),
+            translation: TranslationConfig::default(),
+            relay: RelayConfig::default(),
+            http: HttpConfig::default(),
+            layout: LayoutConfig::default(),
+            trusted_projects: Vec::new(),
         }
     }
 
     pub fn get_powerline_dark() -> Config {
         Config {
+            version: CURRENT_CONFIG_VERSION,
             style: StyleConfig {
                 mode: StyleMode::NerdFont,
                 separator: "".to_string(),
@@ -250,13 +303,20 @@ impl ThemePresets {
                 theme_powerline_dark::output_style_segment(),
                 theme_powerline_dark::byebyecode_usage_segment(),
                 theme_powerline_dark::byebyecode_subscription_segment(),
+                theme_powerline_dark::burn_rate_segment(),
             ],
             theme: "powerline-dark".to_string(),
+            translation: TranslationConfig::default(),
+            relay: RelayConfig::default(),
+            http: HttpConfig::default(),
+            layout: LayoutConfig::default(),
+            trusted_projects: Vec::new(),
         }
     }
 
     pub fn get_powerline_light() -> Config {
         Config {
+            version: CURRENT_CONFIG_VERSION,
             style: StyleConfig {
                 mode: StyleMode::NerdFont,
                 separator: "".to_string(),
@@ -272,13 +332,20 @@ impl ThemePresets {
                 theme_powerline_light::output_style_segment(),
                 theme_powerline_light::byebyecode_usage_segment(),
                 theme_powerline_light::byebyecode_subscription_segment(),
+                theme_powerline_light::burn_rate_segment(),
             ],
             theme: "powerline-light".to_string(),
+            translation: TranslationConfig::default(),
+            relay: RelayConfig::default(),
+            http: HttpConfig::default(),
+            layout: LayoutConfig::default(),
+            trusted_projects: Vec::new(),
         }
     }
 
     pub fn get_powerline_rose_pine() -> Config {
         Config {
+            version: CURRENT_CONFIG_VERSION,
             style: StyleConfig {
                 mode: StyleMode::NerdFont,
                 separator: "".to_string(),
@@ -294,13 +361,20 @@ impl ThemePresets {
                 theme_powerline_rose_pine::output_style_segment(),
                 theme_powerline_rose_pine::byebyecode_usage_segment(),
                 theme_powerline_rose_pine::byebyecode_subscription_segment(),
+                theme_powerline_rose_pine::burn_rate_segment(),
             ],
             theme: "powerline-rose-pine".to_string(),
+            translation: TranslationConfig::default(),
+            relay: RelayConfig::default(),
+            http: HttpConfig::default(),
+            layout: LayoutConfig::default(),
+            trusted_projects: Vec::new(),
         }
     }
 
     pub fn get_powerline_tokyo_night() -> Config {
         Config {
+            version: CURRENT_CONFIG_VERSION,
             style: StyleConfig {
                 mode: StyleMode::NerdFont,
                 separator: "".to_string(),
@@ -316,8 +390,14 @@ impl ThemePresets {
                 theme_powerline_tokyo_night::output_style_segment(),
                 theme_powerline_tokyo_night::byebyecode_usage_segment(),
                 theme_powerline_tokyo_night::byebyecode_subscription_segment(),
+                theme_powerline_tokyo_night::burn_rate_segment(),
             ],
             theme: "powerline-tokyo-night".to_string(),
+            translation: TranslationConfig::default(),
+            relay: RelayConfig::default(),
+            http: HttpConfig::default(),
+            layout: LayoutConfig::default(),
+            trusted_projects: Vec::new(),
         }
     }
 }
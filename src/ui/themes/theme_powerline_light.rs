@@ -1,5 +1,5 @@
 use crate::config::{
-    AnsiColor, ColorConfig, IconConfig, SegmentConfig, SegmentId, TextStyleConfig,
+    rules, AnsiColor, ColorConfig, IconConfig, SegmentConfig, SegmentId, StyleRule, TextStyleConfig,
 };
 use std::collections::HashMap;
 
@@ -253,14 +253,10 @@ pub fn byebyecode_usage_segment() -> SegmentConfig {
             background: None,
         },
         styles: TextStyleConfig::default(),
-        options: {
-            let mut opts = HashMap::new();
-            opts.insert(
-                "api_key".to_string(),
-                serde_json::Value::String("".to_string()),
-            );
-            opts
-        },
+        options: HashMap::from([(
+            "rules".to_string(),
+            serde_json::json!(rules::default_usage_rules()),
+        )]),
     }
 }
 
@@ -278,14 +274,10 @@ pub fn byebyecode_subscription_segment() -> SegmentConfig {
             background: None,
         },
         styles: TextStyleConfig::default(),
-        options: {
-            let mut opts = HashMap::new();
-            opts.insert(
-                "api_key".to_string(),
-                serde_json::Value::String("".to_string()),
-            );
-            opts
-        },
+        options: HashMap::from([(
+            "rules".to_string(),
+            serde_json::json!(rules::default_subscription_rules()),
+        )]),
     }
 }
 
@@ -306,3 +298,40 @@ pub fn byebyecode_status_segment() -> SegmentConfig {
         options: HashMap::new(),
     }
 }
+
+pub fn burn_rate_segment() -> SegmentConfig {
+    SegmentConfig {
+        id: SegmentId::BurnRate,
+        enabled: false,
+        icon: IconConfig {
+            plain: "速率".to_string(),
+            nerd_font: "\u{f0238}".to_string(),
+        },
+        colors: ColorConfig {
+            icon: Some(AnsiColor::Color256 { c256: 203 }),
+            text: Some(AnsiColor::Color256 { c256: 255 }),
+            background: None,
+        },
+        styles: TextStyleConfig::default(),
+        options: {
+            let mut opts = HashMap::new();
+            opts.insert("window_hours".to_string(), serde_json::Value::from(3.0));
+            opts.insert(
+                "rules".to_string(),
+                serde_json::json!([
+                    StyleRule::text_color_when(
+                        "state",
+                        "warning",
+                        AnsiColor::Color256 { c256: 179 }
+                    ),
+                    StyleRule::text_color_when(
+                        "state",
+                        "critical",
+                        AnsiColor::Color256 { c256: 167 }
+                    ),
+                ]),
+            );
+            opts
+        },
+    }
+}
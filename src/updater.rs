@@ -77,10 +77,7 @@ impl UpdateState {
     pub fn load() -> Self {
         #[cfg(feature = "self-update")]
         {
-            let config_dir = dirs::home_dir()
-                .unwrap_or_default()
-                .join(".claude")
-                .join("byebyecode");
+            let config_dir = crate::utils::paths::byebyecode_home_or_default();
 
             let state_file = config_dir.join(".update_state.json");
 
@@ -192,10 +189,7 @@ impl UpdateState {
     pub fn save(&self) -> Result<(), std::io::Error> {
         #[cfg(feature = "self-update")]
         {
-            let config_dir = dirs::home_dir()
-                .unwrap_or_default()
-                .join(".claude")
-                .join("byebyecode");
+            let config_dir = crate::utils::paths::byebyecode_home_or_default();
 
             std::fs::create_dir_all(&config_dir)?;
             let state_file = config_dir.join(".update_state.json");
@@ -350,15 +344,13 @@ pub mod github {
     pub fn check_for_updates() -> Result<Option<GitHubRelease>, Box<dyn std::error::Error>> {
         let url = "https://api.github.com/repos/byebyecode/byebyecode/releases/latest";
 
-        let response = ureq::get(url)
-            .set(
-                "User-Agent",
-                &format!("byebyecode/{}", env!("CARGO_PKG_VERSION")),
-            )
-            .call()?;
+        let http = crate::config::Config::load()
+            .map(|config| config.http)
+            .unwrap_or_default();
+        let response = crate::utils::http::client(&http)?.get(url).send()?;
 
-        if response.status() == 200 {
-            let release: GitHubRelease = response.into_json()?;
+        if response.status().is_success() {
+            let release: GitHubRelease = response.json()?;
 
             let current_version = env!("CARGO_PKG_VERSION");
             let latest_version = release.version();
@@ -373,7 +365,7 @@ pub mod github {
                 Ok(None)
             }
         } else {
-            Err(format!("HTTP {}: {}", response.status(), response.status_text()).into())
+            Err(format!("HTTP {}", response.status()).into())
         }
     }
 }
@@ -0,0 +1,150 @@
+//! Shell commands run on behalf of the statusline (custom segments, `cmd:` secrets)
+//!
+//! Commands must never hang a render: the child is killed at the deadline, and its
+//! output is collected with the same deadline, because a background grandchild
+//! (`foo & echo x`) can keep stdout open long after the shell exits.
+
+use std::io::{Read, Write};
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// Interval between checks whether the child has exited
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// A command that exited before its deadline
+#[derive(Debug)]
+pub struct CommandOutput {
+    /// Everything written to stdout before EOF or the deadline
+    pub stdout: String,
+    pub status: ExitStatus,
+}
+
+/// `command` run through the platform shell (`sh -c`, `cmd /C`)
+pub fn shell(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C").arg(command);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(command);
+        shell
+    }
+}
+
+/// Run `command` with stdout captured and stderr discarded, writing `input` to its stdin
+/// when given. Returns None when it cannot be started or is still running at `timeout`.
+pub fn run_with_timeout(
+    command: &mut Command,
+    input: Option<String>,
+    timeout: Duration,
+) -> Option<CommandOutput> {
+    let deadline = Instant::now() + timeout;
+    let mut child = command
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        // Written on a thread so a command that never reads stdin cannot block us
+        std::thread::spawn(move || {
+            let _ = stdin.write_all(input.as_bytes());
+        });
+    }
+
+    // Read on a thread so a full pipe cannot deadlock the child; chunks arrive
+    // over a channel so the read can be abandoned at the deadline
+    let mut stdout = child.stdout.take()?;
+    let (chunks, received) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut buf = [0u8; 4096];
+        while let Ok(n) = stdout.read(&mut buf) {
+            if n == 0 || chunks.send(buf[..n].to_vec()).is_err() {
+                break;
+            }
+        }
+    });
+
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(POLL_INTERVAL),
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+        }
+    };
+
+    // Stops at EOF, or at the deadline when a grandchild still holds stdout;
+    // whatever arrived until then is used
+    let mut output = Vec::new();
+    while let Ok(chunk) = received.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+    {
+        output.extend(chunk);
+    }
+
+    Some(CommandOutput {
+        stdout: String::from_utf8_lossy(&output).into_owned(),
+        status,
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn captures_output_and_status() {
+        let output = run_with_timeout(
+            &mut shell("echo hello; exit 3"),
+            None,
+            Duration::from_secs(5),
+        )
+        .unwrap();
+        assert_eq!(output.stdout, "hello\n");
+        assert_eq!(output.status.code(), Some(3));
+    }
+
+    #[test]
+    fn passes_input_on_stdin() {
+        let output = run_with_timeout(
+            &mut shell("cat"),
+            Some("from stdin".to_string()),
+            Duration::from_secs(5),
+        )
+        .unwrap();
+        assert_eq!(output.stdout, "from stdin");
+    }
+
+    #[test]
+    fn kills_commands_past_the_deadline() {
+        let started = Instant::now();
+        assert!(
+            run_with_timeout(&mut shell("sleep 5"), None, Duration::from_millis(200)).is_none()
+        );
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn background_grandchild_does_not_block() {
+        let started = Instant::now();
+        let output = run_with_timeout(
+            &mut shell("sleep 5 & echo x"),
+            None,
+            Duration::from_millis(500),
+        )
+        .unwrap();
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert_eq!(output.stdout, "x\n");
+        assert!(output.status.success());
+    }
+}
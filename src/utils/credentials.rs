@@ -75,6 +75,5 @@ fn get_oauth_token_file() -> Option<String> {
 }
 
 fn get_credentials_path() -> Option<PathBuf> {
-    let home = dirs::home_dir()?;
-    Some(home.join(".claude").join(".credentials.json"))
+    super::paths::claude_credentials_path()
 }
@@ -0,0 +1,141 @@
+//! HTTP clients built from the shared `[http]` settings
+
+use crate::config::HttpConfig;
+use reqwest::blocking::{Client, ClientBuilder, RequestBuilder};
+use reqwest::{Certificate, NoProxy, Proxy, StatusCode};
+use std::fs;
+use std::io::Read;
+use std::time::{Duration, Instant};
+
+/// User agent for byebyecode's own requests
+pub const USER_AGENT: &str = concat!("byebyecode/", env!("CARGO_PKG_VERSION"));
+
+/// Proxy configured in the `env` of Claude Code's settings.json, with its NO_PROXY list
+fn claude_settings_proxy() -> Option<(String, Option<String>)> {
+    let path = super::paths::claude_settings_path()?;
+    let settings: serde_json::Value = serde_json::from_str(&fs::read_to_string(path).ok()?).ok()?;
+    let env = settings.get("env")?;
+    let var = |name: &str| {
+        env.get(name)
+            .or_else(|| env.get(name.to_lowercase()))
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+    let proxy = var("HTTPS_PROXY").or_else(|| var("HTTP_PROXY"))?;
+    Some((proxy, var("NO_PROXY")))
+}
+
+/// Client builder with the proxy, root certificates, timeouts and user agent from `http`
+///
+/// The blocking client's timeout bounds each wait (for the response, then for every body
+/// read), so it serves as the read timeout; `send` enforces the total.
+pub fn client_builder(http: &HttpConfig) -> Result<ClientBuilder, Box<dyn std::error::Error>> {
+    let read_timeout = http.read_timeout_secs.min(http.timeout_secs);
+    let mut builder = Client::builder()
+        .user_agent(USER_AGENT)
+        .connect_timeout(Duration::from_secs(http.connect_timeout_secs))
+        .timeout(Duration::from_secs(read_timeout));
+
+    match http.proxy.trim() {
+        "off" => builder = builder.no_proxy(),
+        // Without an explicit proxy reqwest still honors HTTPS_PROXY / HTTP_PROXY from the environment
+        "" => {
+            if let Some((url, no_proxy)) = claude_settings_proxy() {
+                let proxy = Proxy::all(&url)
+                    .map_err(|e| format!("invalid proxy {} in Claude settings: {}", url, e))?;
+                builder = builder
+                    .proxy(proxy.no_proxy(no_proxy.as_deref().and_then(NoProxy::from_string)));
+            }
+        }
+        url => {
+            let proxy = Proxy::all(url).map_err(|e| format!("invalid proxy {}: {}", url, e))?;
+            builder = builder.proxy(proxy.no_proxy(NoProxy::from_env()));
+        }
+    }
+
+    for path in &http.ca_certs {
+        let file = crate::config::secrets::expand_home(path);
+        let pem = fs::read(&file)
+            .map_err(|e| format!("cannot read CA certificate {}: {}", file.display(), e))?;
+        let certs = Certificate::from_pem_bundle(&pem)
+            .map_err(|e| format!("invalid CA certificate {}: {}", file.display(), e))?;
+        if certs.is_empty() {
+            return Err(format!("no certificates in {}", file.display()).into());
+        }
+        for cert in certs {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+
+    Ok(builder)
+}
+
+/// Client with the shared settings
+pub fn client(http: &HttpConfig) -> Result<Client, Box<dyn std::error::Error>> {
+    Ok(client_builder(http)?.build()?)
+}
+
+/// Send a request and read its body as text, giving up once `timeout_secs` have passed
+/// in total (checked between reads, each of which the read timeout bounds)
+pub fn send(
+    request: RequestBuilder,
+    http: &HttpConfig,
+) -> Result<(StatusCode, String), Box<dyn std::error::Error>> {
+    let deadline = Instant::now() + Duration::from_secs(http.timeout_secs);
+    let mut response = request.send()?;
+    let status = response.status();
+
+    let mut body = Vec::new();
+    let mut chunk = [0u8; 8192];
+    loop {
+        if Instant::now() >= deadline {
+            return Err(format!("request timed out after {}s", http.timeout_secs).into());
+        }
+        match response.read(&mut chunk)? {
+            0 => break,
+            n => body.extend_from_slice(&chunk[..n]),
+        }
+    }
+
+    Ok((status, String::from_utf8_lossy(&body).into_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::net::TcpListener;
+    use std::thread;
+
+    #[test]
+    fn send_gives_up_on_a_slow_body_after_the_total_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        // Headers arrive at once, then one body byte every 200ms: no single read times out
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 4096];
+            let _ = stream.read(&mut request);
+            let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 100\r\n\r\n");
+            for _ in 0..100 {
+                if stream.write_all(b"x").is_err() {
+                    break;
+                }
+                thread::sleep(Duration::from_millis(200));
+            }
+        });
+
+        let http = HttpConfig {
+            proxy: "off".to_string(),
+            read_timeout_secs: 1,
+            timeout_secs: 1,
+            ..HttpConfig::default()
+        };
+        let started = Instant::now();
+        let result = send(client(&http).unwrap().get(&url), &http);
+        assert!(result.is_err());
+        assert!(started.elapsed() < Duration::from_secs(3));
+    }
+}
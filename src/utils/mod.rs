@@ -1,4 +1,7 @@
 pub mod claude_code_patcher;
+pub mod command;
 pub mod credentials;
+pub mod http;
+pub mod paths;
 
 pub use claude_code_patcher::{ClaudeCodePatcher, LocationResult};
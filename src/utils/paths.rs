@@ -0,0 +1,189 @@
+//! Where Claude Code and byebyecode keep their files
+//!
+//! - Claude Code directory (`settings.json`, credentials, transcripts):
+//!   `$CLAUDE_CONFIG_DIR`, otherwise `~/.claude`
+//! - byebyecode home (`config.toml`, themes, `models.toml`, history, ledger, daemon socket):
+//!   `$BYEBYECODE_HOME`, otherwise `$XDG_CONFIG_HOME/byebyecode` when that directory exists,
+//!   otherwise `byebyecode/` inside the Claude Code directory
+//! - Cache: `$BYEBYECODE_HOME/cache` when set, otherwise `$XDG_CACHE_HOME/byebyecode`,
+//!   otherwise `cache/` inside the byebyecode home
+//! - Runtime state that must not outlive the login session (resolved `cmd:` secrets):
+//!   `$XDG_RUNTIME_DIR/byebyecode`, otherwise `run/` inside the byebyecode home
+//!
+//! Empty environment variables are ignored. Directories are not created here.
+
+use std::path::PathBuf;
+
+/// Environment lookup; the process environment outside of tests
+type Env<'a> = &'a dyn Fn(&str) -> Option<PathBuf>;
+
+/// Non-empty environment variable as a path
+fn env_dir(name: &str) -> Option<PathBuf> {
+    std::env::var_os(name)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Claude Code's configuration directory
+pub fn claude_dir() -> Option<PathBuf> {
+    claude_dir_in(&env_dir)
+}
+
+fn claude_dir_in(env: Env) -> Option<PathBuf> {
+    env("CLAUDE_CONFIG_DIR").or_else(|| dirs::home_dir().map(|home| home.join(".claude")))
+}
+
+/// Claude Code's `settings.json`
+pub fn claude_settings_path() -> Option<PathBuf> {
+    claude_dir().map(|dir| dir.join("settings.json"))
+}
+
+/// Claude Code's OAuth credentials file
+pub fn claude_credentials_path() -> Option<PathBuf> {
+    claude_dir().map(|dir| dir.join(".credentials.json"))
+}
+
+/// Claude Code's per-project transcripts
+pub fn claude_projects_dir() -> Option<PathBuf> {
+    claude_dir().map(|dir| dir.join("projects"))
+}
+
+/// byebyecode's configuration and state directory
+pub fn byebyecode_home() -> Option<PathBuf> {
+    byebyecode_home_in(&env_dir)
+}
+
+fn byebyecode_home_in(env: Env) -> Option<PathBuf> {
+    if let Some(home) = env("BYEBYECODE_HOME") {
+        return Some(home);
+    }
+    // XDG is opt-in: existing ~/.claude/byebyecode setups keep working unless the user
+    // has created the XDG directory
+    if let Some(xdg) = env("XDG_CONFIG_HOME").map(|dir| dir.join("byebyecode")) {
+        if xdg.is_dir() {
+            return Some(xdg);
+        }
+    }
+    claude_dir_in(env).map(|dir| dir.join("byebyecode"))
+}
+
+/// byebyecode home, or a relative fallback when no home directory is known
+pub fn byebyecode_home_or_default() -> PathBuf {
+    byebyecode_home().unwrap_or_else(|| PathBuf::from(".claude").join("byebyecode"))
+}
+
+/// Directory for regenerable data (relay responses, translations, command output)
+pub fn cache_dir() -> Option<PathBuf> {
+    cache_dir_in(&env_dir)
+}
+
+fn cache_dir_in(env: Env) -> Option<PathBuf> {
+    if let Some(home) = env("BYEBYECODE_HOME") {
+        return Some(home.join("cache"));
+    }
+    if let Some(xdg) = env("XDG_CACHE_HOME") {
+        return Some(xdg.join("byebyecode"));
+    }
+    byebyecode_home_in(env).map(|home| home.join("cache"))
+}
+
+/// Directory for private short-lived state; `$XDG_RUNTIME_DIR` is user-only and cleared at logout
+pub fn runtime_dir() -> Option<PathBuf> {
+    runtime_dir_in(&env_dir)
+}
+
+fn runtime_dir_in(env: Env) -> Option<PathBuf> {
+    if let Some(runtime) = env("XDG_RUNTIME_DIR") {
+        return Some(runtime.join("byebyecode"));
+    }
+    byebyecode_home_in(env).map(|home| home.join("run"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// Lookup over fixed variables instead of the process environment
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<PathBuf> {
+        let vars: Vec<(String, PathBuf)> = vars
+            .iter()
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(name, value)| (name.to_string(), PathBuf::from(value)))
+            .collect();
+        move |name| {
+            vars.iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, value)| value.clone())
+        }
+    }
+
+    #[test]
+    fn claude_config_dir_moves_claude_files_and_the_default_home() {
+        let env = env(&[("CLAUDE_CONFIG_DIR", "/cc")]);
+        assert_eq!(claude_dir_in(&env), Some(PathBuf::from("/cc")));
+        assert_eq!(
+            byebyecode_home_in(&env),
+            Some(PathBuf::from("/cc/byebyecode"))
+        );
+        assert_eq!(
+            cache_dir_in(&env),
+            Some(PathBuf::from("/cc/byebyecode/cache"))
+        );
+        assert_eq!(
+            runtime_dir_in(&env),
+            Some(PathBuf::from("/cc/byebyecode/run"))
+        );
+
+        let unset = self::env(&[("CLAUDE_CONFIG_DIR", "")]);
+        assert_eq!(
+            claude_dir_in(&unset),
+            dirs::home_dir().map(|home| home.join(".claude"))
+        );
+    }
+
+    #[test]
+    fn byebyecode_home_wins_over_xdg_and_claude_dir() {
+        let env = env(&[
+            ("BYEBYECODE_HOME", "/bbc"),
+            ("CLAUDE_CONFIG_DIR", "/cc"),
+            ("XDG_CONFIG_HOME", "/xdg-config"),
+            ("XDG_CACHE_HOME", "/xdg-cache"),
+        ]);
+        assert_eq!(byebyecode_home_in(&env), Some(PathBuf::from("/bbc")));
+        assert_eq!(cache_dir_in(&env), Some(PathBuf::from("/bbc/cache")));
+        // Claude Code's own files stay where Claude Code keeps them
+        assert_eq!(claude_dir_in(&env), Some(PathBuf::from("/cc")));
+    }
+
+    #[test]
+    fn xdg_config_is_used_only_when_the_directory_exists() {
+        let xdg = std::env::temp_dir().join(format!("byebyecode-xdg-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&xdg);
+        let xdg_str = xdg.to_string_lossy().to_string();
+        let env = env(&[
+            ("CLAUDE_CONFIG_DIR", "/cc"),
+            ("XDG_CONFIG_HOME", &xdg_str),
+            ("XDG_CACHE_HOME", "/xdg-cache"),
+            ("XDG_RUNTIME_DIR", "/run/user/1000"),
+        ]);
+
+        assert_eq!(
+            byebyecode_home_in(&env),
+            Some(PathBuf::from("/cc/byebyecode"))
+        );
+        fs::create_dir_all(xdg.join("byebyecode")).unwrap();
+        let home = byebyecode_home_in(&env);
+        let _ = fs::remove_dir_all(&xdg);
+        assert_eq!(home, Some(xdg.join("byebyecode")));
+
+        assert_eq!(
+            cache_dir_in(&env),
+            Some(PathBuf::from("/xdg-cache/byebyecode"))
+        );
+        assert_eq!(
+            runtime_dir_in(&env),
+            Some(PathBuf::from("/run/user/1000/byebyecode"))
+        );
+    }
+}
@@ -34,3 +34,135 @@ impl IoInterceptor {
         &self.buffer
     }
 }
+
+/// Claude Code 渲染助手消息时使用的前导符号
+const ASSISTANT_BULLET: char = '⏺';
+
+/// Remove ANSI escape sequences (CSI, OSC and two-byte escapes) from a line
+pub fn strip_ansi(text: &str) -> String {
+    let mut visible = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        if ch != '\x1b' {
+            if ch != '\r' {
+                visible.push(ch);
+            }
+            continue;
+        }
+
+        match chars.next() {
+            // CSI: ESC [ ... final byte in @..~
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // OSC: ESC ] ... terminated by BEL or ESC \
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' {
+                        break;
+                    }
+                    if c == '\x1b' && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    visible
+}
+
+/// Detects assistant text blocks in Claude Code's rendered output.
+///
+/// Output bytes are always forwarded untouched; completed lines are inspected
+/// (ANSI-stripped) to find blocks that start with the assistant bullet and
+/// continue with indented lines. Tool calls (`⏺ Bash(...)`) are ignored.
+#[derive(Default)]
+pub struct OutputInterceptor {
+    line: Vec<u8>,
+    block: Vec<String>,
+}
+
+impl OutputInterceptor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a chunk of raw PTY output, returning the assistant blocks that
+    /// were completed by lines in this chunk
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<String> {
+        let mut blocks = Vec::new();
+
+        for byte in chunk {
+            if *byte != b'\n' {
+                self.line.push(*byte);
+                continue;
+            }
+
+            let line = strip_ansi(&String::from_utf8_lossy(&self.line));
+            self.line.clear();
+
+            if let Some(block) = self.process_line(&line) {
+                blocks.push(block);
+            }
+        }
+
+        blocks
+    }
+
+    /// Flush the block in progress, e.g. when the child exits
+    pub fn finish(&mut self) -> Option<String> {
+        self.take_block()
+    }
+
+    /// Update block state with a completed visible line.
+    /// Returns the finished block text when this line terminates a block.
+    fn process_line(&mut self, line: &str) -> Option<String> {
+        let trimmed = line.trim_end();
+
+        if let Some(rest) = trimmed.trim_start().strip_prefix(ASSISTANT_BULLET) {
+            let finished = self.take_block();
+            let text = rest.trim();
+            if !text.is_empty() && !Self::is_tool_call(text) {
+                self.block.push(text.to_string());
+            }
+            return finished;
+        }
+
+        if self.block.is_empty() {
+            return None;
+        }
+
+        // Continuation lines are indented under the bullet
+        if trimmed.starts_with("  ") && !trimmed.trim().is_empty() {
+            self.block.push(trimmed.trim().to_string());
+            return None;
+        }
+
+        self.take_block()
+    }
+
+    fn take_block(&mut self) -> Option<String> {
+        if self.block.is_empty() {
+            return None;
+        }
+        let text = self.block.join("\n");
+        self.block.clear();
+        Some(text)
+    }
+
+    /// `Bash(ls)`, `Read(src/main.rs)`, `mcp__server__tool(...)` etc.
+    fn is_tool_call(text: &str) -> bool {
+        let name_len = text
+            .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '_' | ':' | '-')))
+            .unwrap_or(text.len());
+        name_len > 0 && text[name_len..].starts_with('(')
+    }
+}
@@ -1,5 +1,7 @@
 pub mod injector;
 pub mod io_interceptor;
+#[cfg(feature = "wrapper")]
+pub mod pty;
 
 use std::path::PathBuf;
 
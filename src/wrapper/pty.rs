@@ -0,0 +1,603 @@
+use super::io_interceptor::OutputInterceptor;
+use crate::translation::{contains_chinese, Translator};
+use crossterm::terminal;
+use portable_pty::{native_pty_system, CommandBuilder, PtySize};
+use std::collections::{HashSet, VecDeque};
+use std::io::{IsTerminal, Read, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use unicode_width::UnicodeWidthChar;
+
+/// 窗口尺寸轮询间隔（跨平台，无需 SIGWINCH）
+const RESIZE_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// 子进程输出静默这么久后，认为 Ink 已画完一帧，可以重绘译文区
+const FRAME_IDLE: Duration = Duration::from_millis(80);
+
+/// 译文区最多占用的行数（含标题行）
+const PANE_MAX_ROWS: u16 = 6;
+
+/// 终端低于该行数时不显示译文区，把空间全部留给 Claude Code
+const PANE_MIN_TERMINAL_ROWS: u16 = 16;
+
+/// 记住最近这么多个已翻译的文本块，用于跳过 Ink 重绘时的重复输出
+const RECENT_BLOCKS: usize = 256;
+
+/// 子进程退出后，最多等待这么久拿到最后一个文本块的译文
+const FINAL_TRANSLATION_WAIT: Duration = Duration::from_secs(5);
+
+/// 离开作用域时恢复终端模式
+struct RawModeGuard;
+
+impl RawModeGuard {
+    /// stdin 不是终端时（如管道输入）不切换模式
+    fn enable() -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        if !std::io::stdin().is_terminal() {
+            return Ok(None);
+        }
+        terminal::enable_raw_mode()?;
+        Ok(Some(Self))
+    }
+}
+
+impl Drop for RawModeGuard {
+    fn drop(&mut self) {
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn terminal_size() -> (u16, u16) {
+    terminal::size().unwrap_or((80, 24))
+}
+
+/// 译文区行数：stdout 不是终端或终端太矮时为 0
+fn pane_rows(rows: u16, enabled: bool) -> u16 {
+    if !enabled || rows < PANE_MIN_TERMINAL_ROWS {
+        return 0;
+    }
+    (rows / 4).min(PANE_MAX_ROWS)
+}
+
+/// 子终端尺寸：扣除底部的译文区
+fn child_size((cols, rows): (u16, u16), pane: u16) -> PtySize {
+    PtySize {
+        rows: rows - pane,
+        cols,
+        pixel_width: 0,
+        pixel_height: 0,
+    }
+}
+
+fn build_command(claude_path: &Path, args: &[String]) -> CommandBuilder {
+    let mut cmd =
+        if cfg!(target_os = "windows") && claude_path.extension().is_some_and(|ext| ext == "cmd") {
+            // On Windows, .cmd files need to be run through cmd.exe
+            let mut c = CommandBuilder::new("cmd");
+            c.arg("/C");
+            c.arg(claude_path);
+            c
+        } else {
+            CommandBuilder::new(claude_path)
+        };
+
+    cmd.args(args);
+
+    if let Ok(cwd) = std::env::current_dir() {
+        cmd.cwd(cwd);
+    }
+
+    // Set environment variable to indicate wrapper is active
+    cmd.env("BYEBYECODE_WRAPPER", "1");
+    cmd.env("BYEBYECODE_VERSION", env!("CARGO_PKG_VERSION"));
+
+    cmd
+}
+
+/// 跟踪子进程输出是否停在转义序列或 UTF-8 字符中间，只有停在边界上才能插入自己的输出
+#[derive(Default)]
+struct SequenceTracker {
+    state: SequenceState,
+    utf8_pending: u8,
+}
+
+#[derive(Default, Clone, Copy, PartialEq)]
+enum SequenceState {
+    #[default]
+    Ground,
+    Escape,
+    Csi,
+    Osc,
+    OscEscape,
+}
+
+impl SequenceTracker {
+    fn feed(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.state = match (self.state, byte) {
+                (SequenceState::Ground, 0x1b) => SequenceState::Escape,
+                (SequenceState::Ground, _) => {
+                    self.utf8_pending = match byte {
+                        0x80..=0xbf => self.utf8_pending.saturating_sub(1),
+                        0xc0..=0xdf => 1,
+                        0xe0..=0xef => 2,
+                        0xf0..=0xf7 => 3,
+                        _ => 0,
+                    };
+                    SequenceState::Ground
+                }
+                (SequenceState::Escape, b'[') => SequenceState::Csi,
+                (SequenceState::Escape, b']') => SequenceState::Osc,
+                // 两字节转义（ESC 7、ESC M 等）以及字符集选择的中间字节
+                (SequenceState::Escape, 0x20..=0x2f) => SequenceState::Escape,
+                (SequenceState::Escape, _) => SequenceState::Ground,
+                (SequenceState::Csi, 0x40..=0x7e) => SequenceState::Ground,
+                (SequenceState::Csi, _) => SequenceState::Csi,
+                (SequenceState::Osc, 0x07) => SequenceState::Ground,
+                (SequenceState::Osc, 0x1b) => SequenceState::OscEscape,
+                (SequenceState::Osc, _) => SequenceState::Osc,
+                (SequenceState::OscEscape, b'\\') => SequenceState::Ground,
+                (SequenceState::OscEscape, _) => SequenceState::Osc,
+            };
+        }
+    }
+
+    fn at_boundary(&self) -> bool {
+        self.state == SequenceState::Ground && self.utf8_pending == 0
+    }
+}
+
+/// 最近见过的文本块，超过 `RECENT_BLOCKS` 个时淘汰最早的
+#[derive(Default)]
+struct RecentBlocks {
+    order: VecDeque<String>,
+    seen: HashSet<String>,
+}
+
+impl RecentBlocks {
+    /// 记录文本块，已经见过时返回 false
+    fn insert(&mut self, block: &str) -> bool {
+        if self.seen.contains(block) {
+            return false;
+        }
+        if self.order.len() >= RECENT_BLOCKS {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        self.order.push_back(block.to_string());
+        self.seen.insert(block.to_string());
+        true
+    }
+}
+
+/// 按显示宽度折行
+fn wrap_to_width(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for source in text
+        .lines()
+        .map(str::trim_end)
+        .filter(|l| !l.trim().is_empty())
+    {
+        let mut line = String::new();
+        let mut line_width = 0;
+        for ch in source.chars() {
+            let ch_width = ch.width().unwrap_or(0);
+            if line_width + ch_width > width && !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+                line_width = 0;
+            }
+            line.push(ch);
+            line_width += ch_width;
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// 终端底部的译文区
+///
+/// Claude Code（Ink）通过上移光标、擦除行来重绘界面，在它的输出里插入额外的行会打乱重绘。
+/// 因此子终端比真实终端矮 `rows` 行，滚动区域限制在上方，译文画在滚动区域之外。
+struct TranslationPane {
+    cols: u16,
+    /// 真实终端行数
+    total: u16,
+    /// 译文区行数，0 表示不显示
+    rows: u16,
+    text: Option<String>,
+}
+
+impl TranslationPane {
+    fn new((cols, total): (u16, u16), rows: u16) -> Self {
+        Self {
+            cols,
+            total,
+            rows,
+            text: None,
+        }
+    }
+
+    /// 在光标下方腾出译文区并设置滚动区域，子进程启动前调用
+    fn open(&self) -> Vec<u8> {
+        if self.rows == 0 {
+            return Vec::new();
+        }
+        let mut out = "\n".repeat(self.rows as usize);
+        out.push_str(&format!("\x1b[{}A", self.rows));
+        out.push_str(&self.scroll_region());
+        out.into_bytes()
+    }
+
+    /// 设置滚动区域；DECSTBM 会把光标移到左上角，所以前后保存、恢复光标
+    fn scroll_region(&self) -> String {
+        if self.rows == 0 {
+            "\x1b7\x1b[r\x1b8".to_string()
+        } else {
+            format!("\x1b7\x1b[1;{}r\x1b8", self.total - self.rows)
+        }
+    }
+
+    /// 终端尺寸变化后重新布局，返回需要写出的控制序列
+    fn resize(&mut self, size: (u16, u16), rows: u16) -> Vec<u8> {
+        let mut out = String::new();
+        if rows < self.rows {
+            // 译文区变小，先清掉多出来的行
+            out.push_str(&self.clear_rows(self.total - self.rows, self.rows - rows));
+        }
+        (self.cols, self.total) = size;
+        self.rows = rows;
+        out.push_str(&self.scroll_region());
+        out.into_bytes()
+    }
+
+    fn clear_rows(&self, first: u16, count: u16) -> String {
+        let mut out = String::from("\x1b7");
+        for row in first..first + count {
+            out.push_str(&format!("\x1b[{};1H\x1b[2K", row + 1));
+        }
+        out.push_str("\x1b8");
+        out
+    }
+
+    /// 重绘译文区：标题行加译文，超出部分以省略号结尾
+    fn draw(&self) -> Vec<u8> {
+        if self.rows == 0 {
+            return Vec::new();
+        }
+        let width = self.cols as usize;
+        // "─ 译文 " 占 7 列，其余用横线补到行尾前一列
+        let mut lines = vec![format!(
+            "\x1b[2m─ 译文 {}\x1b[0m",
+            "─".repeat(width.saturating_sub(8))
+        )];
+
+        let capacity = (self.rows - 1) as usize;
+        let mut text = self
+            .text
+            .as_deref()
+            .map(|text| wrap_to_width(text, width.saturating_sub(3).max(1)))
+            .unwrap_or_default();
+        if text.len() > capacity {
+            text.truncate(capacity);
+            if let Some(last) = text.last_mut() {
+                last.push('…');
+            }
+        }
+        lines.extend(
+            text.into_iter()
+                .map(|line| format!("\x1b[2m  {}\x1b[0m", line)),
+        );
+
+        let mut out = String::from("\x1b7");
+        let first = self.total - self.rows;
+        for row in 0..self.rows {
+            out.push_str(&format!("\x1b[{};1H\x1b[2K", first + row + 1));
+            if let Some(line) = lines.get(row as usize) {
+                out.push_str(line);
+            }
+        }
+        out.push_str("\x1b8");
+        out.into_bytes()
+    }
+
+    /// 退出时清空译文区并恢复整屏滚动
+    fn close(&self) -> Vec<u8> {
+        if self.rows == 0 {
+            return Vec::new();
+        }
+        let mut out = self.clear_rows(self.total - self.rows, self.rows);
+        out.push_str("\x1b7\x1b[r\x1b8");
+        out.into_bytes()
+    }
+}
+
+/// 输出线程收到的事件
+enum Event {
+    /// 子进程输出
+    Output(Vec<u8>),
+    /// 翻译线程完成的译文
+    Translation(String),
+    /// 真实终端尺寸变化
+    Resize((u16, u16)),
+    /// 子进程输出结束
+    Closed,
+}
+
+/// 翻译线程：在后台逐个翻译文本块，不阻塞输出透传
+fn spawn_translator(translator: Arc<dyn Translator>, events: Sender<Event>) -> Sender<String> {
+    let (blocks, queue) = mpsc::channel::<String>();
+    thread::spawn(move || {
+        for block in queue {
+            if let Ok(chinese) = translator.translate_to_chinese(&block) {
+                if events.send(Event::Translation(chinese)).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+    blocks
+}
+
+/// 在伪终端中运行 Claude Code
+///
+/// 原始按键直接转发，窗口尺寸变化同步到子终端；输出原样透传，
+/// 识别出的助手文本块交给后台线程翻译，译文显示在终端底部的译文区。
+pub fn run_in_pty(
+    claude_path: &Path,
+    args: &[String],
+    translator: Option<Arc<dyn Translator>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let pane_enabled = translator.is_some() && std::io::stdout().is_terminal();
+    let size = terminal_size();
+    let mut pane = TranslationPane::new(size, pane_rows(size.1, pane_enabled));
+
+    let pair = native_pty_system().openpty(child_size(size, pane.rows))?;
+
+    let mut child = pair.slave.spawn_command(build_command(claude_path, args))?;
+    // 子进程持有 slave 端，父进程需关闭以便子进程退出后读端收到 EOF
+    drop(pair.slave);
+
+    let mut reader = pair.master.try_clone_reader()?;
+    let mut writer = pair.master.take_writer()?;
+    let master = pair.master;
+
+    let _raw_mode = RawModeGuard::enable()?;
+    let running = Arc::new(AtomicBool::new(true));
+    let (events, event_queue) = mpsc::channel();
+
+    // 原始按键转发：输入的编辑、补全由 Claude Code 自己处理，raw 模式下拿不到完整的一行，
+    // 因此 PTY 模式不翻译中文输入（有意不支持），按键直接透传
+    // 该线程阻塞在 stdin 读取上，子进程退出后随主进程结束
+    thread::spawn(move || {
+        let mut stdin = std::io::stdin();
+        let mut buf = [0u8; 1024];
+        loop {
+            match stdin.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    if writer.write_all(&buf[..n]).is_err() || writer.flush().is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    // 窗口尺寸轮询，变化交给输出线程统一处理（它负责译文区布局）
+    let resize_running = running.clone();
+    let resize_events = events.clone();
+    let resize_handle = thread::spawn(move || {
+        let mut last = terminal_size();
+        while resize_running.load(Ordering::Relaxed) {
+            thread::sleep(RESIZE_POLL_INTERVAL);
+            let size = terminal_size();
+            if size != last {
+                last = size;
+                if resize_events.send(Event::Resize(size)).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+
+    // 子进程输出读取；翻译线程也持有发送端，所以 EOF 时显式通知输出线程结束
+    let output_events = events.clone();
+    thread::spawn(move || {
+        let mut buf = [0u8; 8192];
+        loop {
+            match reader.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    if output_events
+                        .send(Event::Output(buf[..n].to_vec()))
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+            }
+        }
+        let _ = output_events.send(Event::Closed);
+    });
+
+    let blocks = translator.map(|translator| spawn_translator(translator, events));
+
+    let output_handle = thread::spawn(move || {
+        let mut stdout = std::io::stdout();
+        let mut interceptor = OutputInterceptor::new();
+        let mut tracker = SequenceTracker::default();
+        let mut translated = RecentBlocks::default();
+        let mut dirty = false;
+        // 尺寸变化后的滚动区域设置，和译文区一起在帧间写出
+        let mut layout = Vec::new();
+
+        let _ = stdout.write_all(&pane.open());
+        let _ = stdout.flush();
+
+        // 交给翻译线程；Ink 重绘时会重复输出同一段文本，已翻译过的跳过
+        let submit = |block: String, translated: &mut RecentBlocks| {
+            if contains_chinese(&block) || !translated.insert(&block) {
+                return;
+            }
+            if let Some(blocks) = &blocks {
+                let _ = blocks.send(block);
+            }
+        };
+
+        loop {
+            match event_queue.recv_timeout(FRAME_IDLE) {
+                Ok(Event::Output(chunk)) => {
+                    let _ = stdout.write_all(&chunk);
+                    let _ = stdout.flush();
+                    tracker.feed(&chunk);
+                    // 子进程可能清屏，一帧结束后重绘译文区
+                    dirty = pane.rows > 0;
+
+                    for block in interceptor.feed(&chunk) {
+                        submit(block, &mut translated);
+                    }
+                }
+                Ok(Event::Translation(text)) => {
+                    pane.text = Some(text);
+                    dirty = pane.rows > 0;
+                }
+                Ok(Event::Resize(size)) => {
+                    let rows = pane_rows(size.1, pane_enabled);
+                    layout.extend(pane.resize(size, rows));
+                    let _ = master.resize(child_size(size, rows));
+                    dirty = true;
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    if dirty && tracker.at_boundary() {
+                        let _ = stdout.write_all(&layout);
+                        layout.clear();
+                        let _ = stdout.write_all(&pane.draw());
+                        let _ = stdout.flush();
+                        dirty = false;
+                    }
+                }
+                Ok(Event::Closed) | Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+
+        // 最后一个文本块后面可能没有结束它的行，退出时补上，并等待还在翻译的文本块
+        let mut pending = false;
+        if let Some(block) = interceptor.finish() {
+            submit(block, &mut translated);
+            pending = blocks.is_some();
+        }
+        // 关闭队列，翻译线程处理完剩余文本块后退出，事件通道随之断开
+        drop(blocks);
+        let mut last = None;
+        if pending {
+            let deadline = Instant::now() + FINAL_TRANSLATION_WAIT;
+            loop {
+                let wait = deadline.saturating_duration_since(Instant::now());
+                match event_queue.recv_timeout(wait) {
+                    Ok(Event::Translation(text)) => last = Some(text),
+                    Ok(_) => {}
+                    Err(_) => break,
+                }
+            }
+        }
+
+        let _ = stdout.write_all(&pane.close());
+        // 译文区随退出清空，最后的译文改为写在正常输出里
+        if let Some(text) = last {
+            let mut out = String::from("\r\n\x1b[2m─ 译文\x1b[0m\r\n");
+            for line in text.lines() {
+                out.push_str(&format!("\x1b[2m  {}\x1b[0m\r\n", line));
+            }
+            let _ = stdout.write_all(out.as_bytes());
+        }
+        let _ = stdout.flush();
+    });
+
+    let status = child.wait()?;
+
+    running.store(false, Ordering::Relaxed);
+    let _ = resize_handle.join();
+    let _ = output_handle.join();
+
+    if !status.success() {
+        return Err(format!("Claude Code exited with status: {}", status.exit_code()).into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracker_waits_for_complete_sequences() {
+        let mut tracker = SequenceTracker::default();
+        tracker.feed(b"text\x1b[3");
+        assert!(!tracker.at_boundary());
+        tracker.feed(b"1m");
+        assert!(tracker.at_boundary());
+
+        tracker.feed(b"\x1b]0;title\x1b");
+        assert!(!tracker.at_boundary());
+        tracker.feed(b"\\");
+        assert!(tracker.at_boundary());
+
+        tracker.feed(b"\x1b(");
+        assert!(!tracker.at_boundary());
+        tracker.feed(b"B");
+        assert!(tracker.at_boundary());
+    }
+
+    #[test]
+    fn tracker_waits_for_complete_utf8() {
+        let mut tracker = SequenceTracker::default();
+        let bytes = "译".as_bytes();
+        tracker.feed(&bytes[..2]);
+        assert!(!tracker.at_boundary());
+        tracker.feed(&bytes[2..]);
+        assert!(tracker.at_boundary());
+    }
+
+    #[test]
+    fn recent_blocks_forget_the_oldest() {
+        let mut recent = RecentBlocks::default();
+        assert!(recent.insert("first"));
+        assert!(!recent.insert("first"));
+        for i in 0..RECENT_BLOCKS {
+            recent.insert(&i.to_string());
+        }
+        assert_eq!(recent.order.len(), RECENT_BLOCKS);
+        assert!(recent.insert("first"));
+    }
+
+    #[test]
+    fn wrap_counts_wide_characters() {
+        assert_eq!(wrap_to_width("中文翻译", 4), vec!["中文", "翻译"]);
+        assert_eq!(wrap_to_width("abc\n\n  de", 2), vec!["ab", "c", "  ", "de"]);
+    }
+
+    #[test]
+    fn pane_is_disabled_on_short_terminals() {
+        assert_eq!(pane_rows(40, true), PANE_MAX_ROWS);
+        assert_eq!(pane_rows(20, true), 5);
+        assert_eq!(pane_rows(PANE_MIN_TERMINAL_ROWS - 1, true), 0);
+        assert_eq!(pane_rows(40, false), 0);
+    }
+
+    #[test]
+    fn pane_truncates_long_translations() {
+        let mut pane = TranslationPane::new((20, 30), 3);
+        pane.text = Some("第一行\n第二行\n第三行".to_string());
+        let drawn = String::from_utf8(pane.draw()).unwrap();
+        assert!(drawn.starts_with("\x1b7\x1b[28;1H"));
+        assert!(drawn.contains("第一行"));
+        assert!(drawn.contains("第二行…"));
+        assert!(!drawn.contains("第三行"));
+        assert!(drawn.ends_with("\x1b8"));
+    }
+}